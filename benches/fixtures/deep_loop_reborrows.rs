@@ -0,0 +1,33 @@
+struct S {
+    a: (u32, u32),
+    b: (u32, u32),
+}
+
+fn f(s: &mut S, n: u32) -> u32 {
+    let mut r = &mut s.a.0;
+    let mut i = 0;
+    while i < n {
+        let mut j = 0;
+        while j < n {
+            let mut k = 0;
+            while k < n {
+                if k % 2 == 0 {
+                    r = &mut s.b.0;
+                } else {
+                    let t = &mut *r;
+                    *t += 1;
+                }
+                k += 1;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    *r += 1;
+    s.a.1 + s.b.1
+}
+
+fn main() {
+    let mut s = S { a: (0, 0), b: (0, 0) };
+    f(&mut s, 3);
+}
@@ -0,0 +1,131 @@
+enum E {
+    V0(u32),
+    V1(u32),
+    V2(u32),
+    V3(u32),
+    V4(u32),
+    V5(u32),
+    V6(u32),
+    V7(u32),
+    V8(u32),
+    V9(u32),
+    V10(u32),
+    V11(u32),
+    V12(u32),
+    V13(u32),
+    V14(u32),
+    V15(u32),
+    V16(u32),
+    V17(u32),
+    V18(u32),
+    V19(u32),
+    V20(u32),
+    V21(u32),
+    V22(u32),
+    V23(u32),
+    V24(u32),
+    V25(u32),
+    V26(u32),
+    V27(u32),
+    V28(u32),
+    V29(u32),
+    V30(u32),
+    V31(u32),
+    V32(u32),
+    V33(u32),
+    V34(u32),
+    V35(u32),
+    V36(u32),
+    V37(u32),
+    V38(u32),
+    V39(u32),
+    V40(u32),
+    V41(u32),
+    V42(u32),
+    V43(u32),
+    V44(u32),
+    V45(u32),
+    V46(u32),
+    V47(u32),
+    V48(u32),
+    V49(u32),
+    V50(u32),
+    V51(u32),
+    V52(u32),
+    V53(u32),
+    V54(u32),
+    V55(u32),
+    V56(u32),
+    V57(u32),
+    V58(u32),
+    V59(u32),
+}
+
+fn f(e: E) -> u32 {
+    match e {
+        E::V0(x) => x + 0,
+        E::V1(x) => x + 1,
+        E::V2(x) => x + 2,
+        E::V3(x) => x + 3,
+        E::V4(x) => x + 4,
+        E::V5(x) => x + 5,
+        E::V6(x) => x + 6,
+        E::V7(x) => x + 7,
+        E::V8(x) => x + 8,
+        E::V9(x) => x + 9,
+        E::V10(x) => x + 10,
+        E::V11(x) => x + 11,
+        E::V12(x) => x + 12,
+        E::V13(x) => x + 13,
+        E::V14(x) => x + 14,
+        E::V15(x) => x + 15,
+        E::V16(x) => x + 16,
+        E::V17(x) => x + 17,
+        E::V18(x) => x + 18,
+        E::V19(x) => x + 19,
+        E::V20(x) => x + 20,
+        E::V21(x) => x + 21,
+        E::V22(x) => x + 22,
+        E::V23(x) => x + 23,
+        E::V24(x) => x + 24,
+        E::V25(x) => x + 25,
+        E::V26(x) => x + 26,
+        E::V27(x) => x + 27,
+        E::V28(x) => x + 28,
+        E::V29(x) => x + 29,
+        E::V30(x) => x + 30,
+        E::V31(x) => x + 31,
+        E::V32(x) => x + 32,
+        E::V33(x) => x + 33,
+        E::V34(x) => x + 34,
+        E::V35(x) => x + 35,
+        E::V36(x) => x + 36,
+        E::V37(x) => x + 37,
+        E::V38(x) => x + 38,
+        E::V39(x) => x + 39,
+        E::V40(x) => x + 40,
+        E::V41(x) => x + 41,
+        E::V42(x) => x + 42,
+        E::V43(x) => x + 43,
+        E::V44(x) => x + 44,
+        E::V45(x) => x + 45,
+        E::V46(x) => x + 46,
+        E::V47(x) => x + 47,
+        E::V48(x) => x + 48,
+        E::V49(x) => x + 49,
+        E::V50(x) => x + 50,
+        E::V51(x) => x + 51,
+        E::V52(x) => x + 52,
+        E::V53(x) => x + 53,
+        E::V54(x) => x + 54,
+        E::V55(x) => x + 55,
+        E::V56(x) => x + 56,
+        E::V57(x) => x + 57,
+        E::V58(x) => x + 58,
+        E::V59(x) => x + 59,
+    }
+}
+
+fn main() {
+    f(E::V3(1));
+}
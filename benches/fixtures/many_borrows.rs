@@ -0,0 +1,944 @@
+// 300 shared borrows that are all live across a loop, so that every
+// block visit carries them
+fn f(n: u32) -> u32 {
+    let a0 = 0;
+    let a1 = 1;
+    let a2 = 2;
+    let a3 = 3;
+    let a4 = 4;
+    let a5 = 5;
+    let a6 = 6;
+    let a7 = 7;
+    let a8 = 8;
+    let a9 = 9;
+    let a10 = 10;
+    let a11 = 11;
+    let a12 = 12;
+    let a13 = 13;
+    let a14 = 14;
+    let a15 = 15;
+    let a16 = 16;
+    let a17 = 17;
+    let a18 = 18;
+    let a19 = 19;
+    let a20 = 20;
+    let a21 = 21;
+    let a22 = 22;
+    let a23 = 23;
+    let a24 = 24;
+    let a25 = 25;
+    let a26 = 26;
+    let a27 = 27;
+    let a28 = 28;
+    let a29 = 29;
+    let a30 = 30;
+    let a31 = 31;
+    let a32 = 32;
+    let a33 = 33;
+    let a34 = 34;
+    let a35 = 35;
+    let a36 = 36;
+    let a37 = 37;
+    let a38 = 38;
+    let a39 = 39;
+    let a40 = 40;
+    let a41 = 41;
+    let a42 = 42;
+    let a43 = 43;
+    let a44 = 44;
+    let a45 = 45;
+    let a46 = 46;
+    let a47 = 47;
+    let a48 = 48;
+    let a49 = 49;
+    let a50 = 50;
+    let a51 = 51;
+    let a52 = 52;
+    let a53 = 53;
+    let a54 = 54;
+    let a55 = 55;
+    let a56 = 56;
+    let a57 = 57;
+    let a58 = 58;
+    let a59 = 59;
+    let a60 = 60;
+    let a61 = 61;
+    let a62 = 62;
+    let a63 = 63;
+    let a64 = 64;
+    let a65 = 65;
+    let a66 = 66;
+    let a67 = 67;
+    let a68 = 68;
+    let a69 = 69;
+    let a70 = 70;
+    let a71 = 71;
+    let a72 = 72;
+    let a73 = 73;
+    let a74 = 74;
+    let a75 = 75;
+    let a76 = 76;
+    let a77 = 77;
+    let a78 = 78;
+    let a79 = 79;
+    let a80 = 80;
+    let a81 = 81;
+    let a82 = 82;
+    let a83 = 83;
+    let a84 = 84;
+    let a85 = 85;
+    let a86 = 86;
+    let a87 = 87;
+    let a88 = 88;
+    let a89 = 89;
+    let a90 = 90;
+    let a91 = 91;
+    let a92 = 92;
+    let a93 = 93;
+    let a94 = 94;
+    let a95 = 95;
+    let a96 = 96;
+    let a97 = 97;
+    let a98 = 98;
+    let a99 = 99;
+    let a100 = 100;
+    let a101 = 101;
+    let a102 = 102;
+    let a103 = 103;
+    let a104 = 104;
+    let a105 = 105;
+    let a106 = 106;
+    let a107 = 107;
+    let a108 = 108;
+    let a109 = 109;
+    let a110 = 110;
+    let a111 = 111;
+    let a112 = 112;
+    let a113 = 113;
+    let a114 = 114;
+    let a115 = 115;
+    let a116 = 116;
+    let a117 = 117;
+    let a118 = 118;
+    let a119 = 119;
+    let a120 = 120;
+    let a121 = 121;
+    let a122 = 122;
+    let a123 = 123;
+    let a124 = 124;
+    let a125 = 125;
+    let a126 = 126;
+    let a127 = 127;
+    let a128 = 128;
+    let a129 = 129;
+    let a130 = 130;
+    let a131 = 131;
+    let a132 = 132;
+    let a133 = 133;
+    let a134 = 134;
+    let a135 = 135;
+    let a136 = 136;
+    let a137 = 137;
+    let a138 = 138;
+    let a139 = 139;
+    let a140 = 140;
+    let a141 = 141;
+    let a142 = 142;
+    let a143 = 143;
+    let a144 = 144;
+    let a145 = 145;
+    let a146 = 146;
+    let a147 = 147;
+    let a148 = 148;
+    let a149 = 149;
+    let a150 = 150;
+    let a151 = 151;
+    let a152 = 152;
+    let a153 = 153;
+    let a154 = 154;
+    let a155 = 155;
+    let a156 = 156;
+    let a157 = 157;
+    let a158 = 158;
+    let a159 = 159;
+    let a160 = 160;
+    let a161 = 161;
+    let a162 = 162;
+    let a163 = 163;
+    let a164 = 164;
+    let a165 = 165;
+    let a166 = 166;
+    let a167 = 167;
+    let a168 = 168;
+    let a169 = 169;
+    let a170 = 170;
+    let a171 = 171;
+    let a172 = 172;
+    let a173 = 173;
+    let a174 = 174;
+    let a175 = 175;
+    let a176 = 176;
+    let a177 = 177;
+    let a178 = 178;
+    let a179 = 179;
+    let a180 = 180;
+    let a181 = 181;
+    let a182 = 182;
+    let a183 = 183;
+    let a184 = 184;
+    let a185 = 185;
+    let a186 = 186;
+    let a187 = 187;
+    let a188 = 188;
+    let a189 = 189;
+    let a190 = 190;
+    let a191 = 191;
+    let a192 = 192;
+    let a193 = 193;
+    let a194 = 194;
+    let a195 = 195;
+    let a196 = 196;
+    let a197 = 197;
+    let a198 = 198;
+    let a199 = 199;
+    let a200 = 200;
+    let a201 = 201;
+    let a202 = 202;
+    let a203 = 203;
+    let a204 = 204;
+    let a205 = 205;
+    let a206 = 206;
+    let a207 = 207;
+    let a208 = 208;
+    let a209 = 209;
+    let a210 = 210;
+    let a211 = 211;
+    let a212 = 212;
+    let a213 = 213;
+    let a214 = 214;
+    let a215 = 215;
+    let a216 = 216;
+    let a217 = 217;
+    let a218 = 218;
+    let a219 = 219;
+    let a220 = 220;
+    let a221 = 221;
+    let a222 = 222;
+    let a223 = 223;
+    let a224 = 224;
+    let a225 = 225;
+    let a226 = 226;
+    let a227 = 227;
+    let a228 = 228;
+    let a229 = 229;
+    let a230 = 230;
+    let a231 = 231;
+    let a232 = 232;
+    let a233 = 233;
+    let a234 = 234;
+    let a235 = 235;
+    let a236 = 236;
+    let a237 = 237;
+    let a238 = 238;
+    let a239 = 239;
+    let a240 = 240;
+    let a241 = 241;
+    let a242 = 242;
+    let a243 = 243;
+    let a244 = 244;
+    let a245 = 245;
+    let a246 = 246;
+    let a247 = 247;
+    let a248 = 248;
+    let a249 = 249;
+    let a250 = 250;
+    let a251 = 251;
+    let a252 = 252;
+    let a253 = 253;
+    let a254 = 254;
+    let a255 = 255;
+    let a256 = 256;
+    let a257 = 257;
+    let a258 = 258;
+    let a259 = 259;
+    let a260 = 260;
+    let a261 = 261;
+    let a262 = 262;
+    let a263 = 263;
+    let a264 = 264;
+    let a265 = 265;
+    let a266 = 266;
+    let a267 = 267;
+    let a268 = 268;
+    let a269 = 269;
+    let a270 = 270;
+    let a271 = 271;
+    let a272 = 272;
+    let a273 = 273;
+    let a274 = 274;
+    let a275 = 275;
+    let a276 = 276;
+    let a277 = 277;
+    let a278 = 278;
+    let a279 = 279;
+    let a280 = 280;
+    let a281 = 281;
+    let a282 = 282;
+    let a283 = 283;
+    let a284 = 284;
+    let a285 = 285;
+    let a286 = 286;
+    let a287 = 287;
+    let a288 = 288;
+    let a289 = 289;
+    let a290 = 290;
+    let a291 = 291;
+    let a292 = 292;
+    let a293 = 293;
+    let a294 = 294;
+    let a295 = 295;
+    let a296 = 296;
+    let a297 = 297;
+    let a298 = 298;
+    let a299 = 299;
+    let r0 = &a0;
+    let r1 = &a1;
+    let r2 = &a2;
+    let r3 = &a3;
+    let r4 = &a4;
+    let r5 = &a5;
+    let r6 = &a6;
+    let r7 = &a7;
+    let r8 = &a8;
+    let r9 = &a9;
+    let r10 = &a10;
+    let r11 = &a11;
+    let r12 = &a12;
+    let r13 = &a13;
+    let r14 = &a14;
+    let r15 = &a15;
+    let r16 = &a16;
+    let r17 = &a17;
+    let r18 = &a18;
+    let r19 = &a19;
+    let r20 = &a20;
+    let r21 = &a21;
+    let r22 = &a22;
+    let r23 = &a23;
+    let r24 = &a24;
+    let r25 = &a25;
+    let r26 = &a26;
+    let r27 = &a27;
+    let r28 = &a28;
+    let r29 = &a29;
+    let r30 = &a30;
+    let r31 = &a31;
+    let r32 = &a32;
+    let r33 = &a33;
+    let r34 = &a34;
+    let r35 = &a35;
+    let r36 = &a36;
+    let r37 = &a37;
+    let r38 = &a38;
+    let r39 = &a39;
+    let r40 = &a40;
+    let r41 = &a41;
+    let r42 = &a42;
+    let r43 = &a43;
+    let r44 = &a44;
+    let r45 = &a45;
+    let r46 = &a46;
+    let r47 = &a47;
+    let r48 = &a48;
+    let r49 = &a49;
+    let r50 = &a50;
+    let r51 = &a51;
+    let r52 = &a52;
+    let r53 = &a53;
+    let r54 = &a54;
+    let r55 = &a55;
+    let r56 = &a56;
+    let r57 = &a57;
+    let r58 = &a58;
+    let r59 = &a59;
+    let r60 = &a60;
+    let r61 = &a61;
+    let r62 = &a62;
+    let r63 = &a63;
+    let r64 = &a64;
+    let r65 = &a65;
+    let r66 = &a66;
+    let r67 = &a67;
+    let r68 = &a68;
+    let r69 = &a69;
+    let r70 = &a70;
+    let r71 = &a71;
+    let r72 = &a72;
+    let r73 = &a73;
+    let r74 = &a74;
+    let r75 = &a75;
+    let r76 = &a76;
+    let r77 = &a77;
+    let r78 = &a78;
+    let r79 = &a79;
+    let r80 = &a80;
+    let r81 = &a81;
+    let r82 = &a82;
+    let r83 = &a83;
+    let r84 = &a84;
+    let r85 = &a85;
+    let r86 = &a86;
+    let r87 = &a87;
+    let r88 = &a88;
+    let r89 = &a89;
+    let r90 = &a90;
+    let r91 = &a91;
+    let r92 = &a92;
+    let r93 = &a93;
+    let r94 = &a94;
+    let r95 = &a95;
+    let r96 = &a96;
+    let r97 = &a97;
+    let r98 = &a98;
+    let r99 = &a99;
+    let r100 = &a100;
+    let r101 = &a101;
+    let r102 = &a102;
+    let r103 = &a103;
+    let r104 = &a104;
+    let r105 = &a105;
+    let r106 = &a106;
+    let r107 = &a107;
+    let r108 = &a108;
+    let r109 = &a109;
+    let r110 = &a110;
+    let r111 = &a111;
+    let r112 = &a112;
+    let r113 = &a113;
+    let r114 = &a114;
+    let r115 = &a115;
+    let r116 = &a116;
+    let r117 = &a117;
+    let r118 = &a118;
+    let r119 = &a119;
+    let r120 = &a120;
+    let r121 = &a121;
+    let r122 = &a122;
+    let r123 = &a123;
+    let r124 = &a124;
+    let r125 = &a125;
+    let r126 = &a126;
+    let r127 = &a127;
+    let r128 = &a128;
+    let r129 = &a129;
+    let r130 = &a130;
+    let r131 = &a131;
+    let r132 = &a132;
+    let r133 = &a133;
+    let r134 = &a134;
+    let r135 = &a135;
+    let r136 = &a136;
+    let r137 = &a137;
+    let r138 = &a138;
+    let r139 = &a139;
+    let r140 = &a140;
+    let r141 = &a141;
+    let r142 = &a142;
+    let r143 = &a143;
+    let r144 = &a144;
+    let r145 = &a145;
+    let r146 = &a146;
+    let r147 = &a147;
+    let r148 = &a148;
+    let r149 = &a149;
+    let r150 = &a150;
+    let r151 = &a151;
+    let r152 = &a152;
+    let r153 = &a153;
+    let r154 = &a154;
+    let r155 = &a155;
+    let r156 = &a156;
+    let r157 = &a157;
+    let r158 = &a158;
+    let r159 = &a159;
+    let r160 = &a160;
+    let r161 = &a161;
+    let r162 = &a162;
+    let r163 = &a163;
+    let r164 = &a164;
+    let r165 = &a165;
+    let r166 = &a166;
+    let r167 = &a167;
+    let r168 = &a168;
+    let r169 = &a169;
+    let r170 = &a170;
+    let r171 = &a171;
+    let r172 = &a172;
+    let r173 = &a173;
+    let r174 = &a174;
+    let r175 = &a175;
+    let r176 = &a176;
+    let r177 = &a177;
+    let r178 = &a178;
+    let r179 = &a179;
+    let r180 = &a180;
+    let r181 = &a181;
+    let r182 = &a182;
+    let r183 = &a183;
+    let r184 = &a184;
+    let r185 = &a185;
+    let r186 = &a186;
+    let r187 = &a187;
+    let r188 = &a188;
+    let r189 = &a189;
+    let r190 = &a190;
+    let r191 = &a191;
+    let r192 = &a192;
+    let r193 = &a193;
+    let r194 = &a194;
+    let r195 = &a195;
+    let r196 = &a196;
+    let r197 = &a197;
+    let r198 = &a198;
+    let r199 = &a199;
+    let r200 = &a200;
+    let r201 = &a201;
+    let r202 = &a202;
+    let r203 = &a203;
+    let r204 = &a204;
+    let r205 = &a205;
+    let r206 = &a206;
+    let r207 = &a207;
+    let r208 = &a208;
+    let r209 = &a209;
+    let r210 = &a210;
+    let r211 = &a211;
+    let r212 = &a212;
+    let r213 = &a213;
+    let r214 = &a214;
+    let r215 = &a215;
+    let r216 = &a216;
+    let r217 = &a217;
+    let r218 = &a218;
+    let r219 = &a219;
+    let r220 = &a220;
+    let r221 = &a221;
+    let r222 = &a222;
+    let r223 = &a223;
+    let r224 = &a224;
+    let r225 = &a225;
+    let r226 = &a226;
+    let r227 = &a227;
+    let r228 = &a228;
+    let r229 = &a229;
+    let r230 = &a230;
+    let r231 = &a231;
+    let r232 = &a232;
+    let r233 = &a233;
+    let r234 = &a234;
+    let r235 = &a235;
+    let r236 = &a236;
+    let r237 = &a237;
+    let r238 = &a238;
+    let r239 = &a239;
+    let r240 = &a240;
+    let r241 = &a241;
+    let r242 = &a242;
+    let r243 = &a243;
+    let r244 = &a244;
+    let r245 = &a245;
+    let r246 = &a246;
+    let r247 = &a247;
+    let r248 = &a248;
+    let r249 = &a249;
+    let r250 = &a250;
+    let r251 = &a251;
+    let r252 = &a252;
+    let r253 = &a253;
+    let r254 = &a254;
+    let r255 = &a255;
+    let r256 = &a256;
+    let r257 = &a257;
+    let r258 = &a258;
+    let r259 = &a259;
+    let r260 = &a260;
+    let r261 = &a261;
+    let r262 = &a262;
+    let r263 = &a263;
+    let r264 = &a264;
+    let r265 = &a265;
+    let r266 = &a266;
+    let r267 = &a267;
+    let r268 = &a268;
+    let r269 = &a269;
+    let r270 = &a270;
+    let r271 = &a271;
+    let r272 = &a272;
+    let r273 = &a273;
+    let r274 = &a274;
+    let r275 = &a275;
+    let r276 = &a276;
+    let r277 = &a277;
+    let r278 = &a278;
+    let r279 = &a279;
+    let r280 = &a280;
+    let r281 = &a281;
+    let r282 = &a282;
+    let r283 = &a283;
+    let r284 = &a284;
+    let r285 = &a285;
+    let r286 = &a286;
+    let r287 = &a287;
+    let r288 = &a288;
+    let r289 = &a289;
+    let r290 = &a290;
+    let r291 = &a291;
+    let r292 = &a292;
+    let r293 = &a293;
+    let r294 = &a294;
+    let r295 = &a295;
+    let r296 = &a296;
+    let r297 = &a297;
+    let r298 = &a298;
+    let r299 = &a299;
+    let mut acc = 0;
+    let mut i = 0;
+    while i < n {
+        if i == 0 {
+            acc ^= *r0;
+        }
+        if i == 1 {
+            acc ^= *r30;
+        }
+        if i == 2 {
+            acc ^= *r60;
+        }
+        if i == 3 {
+            acc ^= *r90;
+        }
+        if i == 4 {
+            acc ^= *r120;
+        }
+        if i == 5 {
+            acc ^= *r150;
+        }
+        if i == 6 {
+            acc ^= *r180;
+        }
+        if i == 7 {
+            acc ^= *r210;
+        }
+        if i == 8 {
+            acc ^= *r240;
+        }
+        if i == 9 {
+            acc ^= *r270;
+        }
+        i += 1;
+    }
+    acc ^= *r0;
+    acc ^= *r1;
+    acc ^= *r2;
+    acc ^= *r3;
+    acc ^= *r4;
+    acc ^= *r5;
+    acc ^= *r6;
+    acc ^= *r7;
+    acc ^= *r8;
+    acc ^= *r9;
+    acc ^= *r10;
+    acc ^= *r11;
+    acc ^= *r12;
+    acc ^= *r13;
+    acc ^= *r14;
+    acc ^= *r15;
+    acc ^= *r16;
+    acc ^= *r17;
+    acc ^= *r18;
+    acc ^= *r19;
+    acc ^= *r20;
+    acc ^= *r21;
+    acc ^= *r22;
+    acc ^= *r23;
+    acc ^= *r24;
+    acc ^= *r25;
+    acc ^= *r26;
+    acc ^= *r27;
+    acc ^= *r28;
+    acc ^= *r29;
+    acc ^= *r30;
+    acc ^= *r31;
+    acc ^= *r32;
+    acc ^= *r33;
+    acc ^= *r34;
+    acc ^= *r35;
+    acc ^= *r36;
+    acc ^= *r37;
+    acc ^= *r38;
+    acc ^= *r39;
+    acc ^= *r40;
+    acc ^= *r41;
+    acc ^= *r42;
+    acc ^= *r43;
+    acc ^= *r44;
+    acc ^= *r45;
+    acc ^= *r46;
+    acc ^= *r47;
+    acc ^= *r48;
+    acc ^= *r49;
+    acc ^= *r50;
+    acc ^= *r51;
+    acc ^= *r52;
+    acc ^= *r53;
+    acc ^= *r54;
+    acc ^= *r55;
+    acc ^= *r56;
+    acc ^= *r57;
+    acc ^= *r58;
+    acc ^= *r59;
+    acc ^= *r60;
+    acc ^= *r61;
+    acc ^= *r62;
+    acc ^= *r63;
+    acc ^= *r64;
+    acc ^= *r65;
+    acc ^= *r66;
+    acc ^= *r67;
+    acc ^= *r68;
+    acc ^= *r69;
+    acc ^= *r70;
+    acc ^= *r71;
+    acc ^= *r72;
+    acc ^= *r73;
+    acc ^= *r74;
+    acc ^= *r75;
+    acc ^= *r76;
+    acc ^= *r77;
+    acc ^= *r78;
+    acc ^= *r79;
+    acc ^= *r80;
+    acc ^= *r81;
+    acc ^= *r82;
+    acc ^= *r83;
+    acc ^= *r84;
+    acc ^= *r85;
+    acc ^= *r86;
+    acc ^= *r87;
+    acc ^= *r88;
+    acc ^= *r89;
+    acc ^= *r90;
+    acc ^= *r91;
+    acc ^= *r92;
+    acc ^= *r93;
+    acc ^= *r94;
+    acc ^= *r95;
+    acc ^= *r96;
+    acc ^= *r97;
+    acc ^= *r98;
+    acc ^= *r99;
+    acc ^= *r100;
+    acc ^= *r101;
+    acc ^= *r102;
+    acc ^= *r103;
+    acc ^= *r104;
+    acc ^= *r105;
+    acc ^= *r106;
+    acc ^= *r107;
+    acc ^= *r108;
+    acc ^= *r109;
+    acc ^= *r110;
+    acc ^= *r111;
+    acc ^= *r112;
+    acc ^= *r113;
+    acc ^= *r114;
+    acc ^= *r115;
+    acc ^= *r116;
+    acc ^= *r117;
+    acc ^= *r118;
+    acc ^= *r119;
+    acc ^= *r120;
+    acc ^= *r121;
+    acc ^= *r122;
+    acc ^= *r123;
+    acc ^= *r124;
+    acc ^= *r125;
+    acc ^= *r126;
+    acc ^= *r127;
+    acc ^= *r128;
+    acc ^= *r129;
+    acc ^= *r130;
+    acc ^= *r131;
+    acc ^= *r132;
+    acc ^= *r133;
+    acc ^= *r134;
+    acc ^= *r135;
+    acc ^= *r136;
+    acc ^= *r137;
+    acc ^= *r138;
+    acc ^= *r139;
+    acc ^= *r140;
+    acc ^= *r141;
+    acc ^= *r142;
+    acc ^= *r143;
+    acc ^= *r144;
+    acc ^= *r145;
+    acc ^= *r146;
+    acc ^= *r147;
+    acc ^= *r148;
+    acc ^= *r149;
+    acc ^= *r150;
+    acc ^= *r151;
+    acc ^= *r152;
+    acc ^= *r153;
+    acc ^= *r154;
+    acc ^= *r155;
+    acc ^= *r156;
+    acc ^= *r157;
+    acc ^= *r158;
+    acc ^= *r159;
+    acc ^= *r160;
+    acc ^= *r161;
+    acc ^= *r162;
+    acc ^= *r163;
+    acc ^= *r164;
+    acc ^= *r165;
+    acc ^= *r166;
+    acc ^= *r167;
+    acc ^= *r168;
+    acc ^= *r169;
+    acc ^= *r170;
+    acc ^= *r171;
+    acc ^= *r172;
+    acc ^= *r173;
+    acc ^= *r174;
+    acc ^= *r175;
+    acc ^= *r176;
+    acc ^= *r177;
+    acc ^= *r178;
+    acc ^= *r179;
+    acc ^= *r180;
+    acc ^= *r181;
+    acc ^= *r182;
+    acc ^= *r183;
+    acc ^= *r184;
+    acc ^= *r185;
+    acc ^= *r186;
+    acc ^= *r187;
+    acc ^= *r188;
+    acc ^= *r189;
+    acc ^= *r190;
+    acc ^= *r191;
+    acc ^= *r192;
+    acc ^= *r193;
+    acc ^= *r194;
+    acc ^= *r195;
+    acc ^= *r196;
+    acc ^= *r197;
+    acc ^= *r198;
+    acc ^= *r199;
+    acc ^= *r200;
+    acc ^= *r201;
+    acc ^= *r202;
+    acc ^= *r203;
+    acc ^= *r204;
+    acc ^= *r205;
+    acc ^= *r206;
+    acc ^= *r207;
+    acc ^= *r208;
+    acc ^= *r209;
+    acc ^= *r210;
+    acc ^= *r211;
+    acc ^= *r212;
+    acc ^= *r213;
+    acc ^= *r214;
+    acc ^= *r215;
+    acc ^= *r216;
+    acc ^= *r217;
+    acc ^= *r218;
+    acc ^= *r219;
+    acc ^= *r220;
+    acc ^= *r221;
+    acc ^= *r222;
+    acc ^= *r223;
+    acc ^= *r224;
+    acc ^= *r225;
+    acc ^= *r226;
+    acc ^= *r227;
+    acc ^= *r228;
+    acc ^= *r229;
+    acc ^= *r230;
+    acc ^= *r231;
+    acc ^= *r232;
+    acc ^= *r233;
+    acc ^= *r234;
+    acc ^= *r235;
+    acc ^= *r236;
+    acc ^= *r237;
+    acc ^= *r238;
+    acc ^= *r239;
+    acc ^= *r240;
+    acc ^= *r241;
+    acc ^= *r242;
+    acc ^= *r243;
+    acc ^= *r244;
+    acc ^= *r245;
+    acc ^= *r246;
+    acc ^= *r247;
+    acc ^= *r248;
+    acc ^= *r249;
+    acc ^= *r250;
+    acc ^= *r251;
+    acc ^= *r252;
+    acc ^= *r253;
+    acc ^= *r254;
+    acc ^= *r255;
+    acc ^= *r256;
+    acc ^= *r257;
+    acc ^= *r258;
+    acc ^= *r259;
+    acc ^= *r260;
+    acc ^= *r261;
+    acc ^= *r262;
+    acc ^= *r263;
+    acc ^= *r264;
+    acc ^= *r265;
+    acc ^= *r266;
+    acc ^= *r267;
+    acc ^= *r268;
+    acc ^= *r269;
+    acc ^= *r270;
+    acc ^= *r271;
+    acc ^= *r272;
+    acc ^= *r273;
+    acc ^= *r274;
+    acc ^= *r275;
+    acc ^= *r276;
+    acc ^= *r277;
+    acc ^= *r278;
+    acc ^= *r279;
+    acc ^= *r280;
+    acc ^= *r281;
+    acc ^= *r282;
+    acc ^= *r283;
+    acc ^= *r284;
+    acc ^= *r285;
+    acc ^= *r286;
+    acc ^= *r287;
+    acc ^= *r288;
+    acc ^= *r289;
+    acc ^= *r290;
+    acc ^= *r291;
+    acc ^= *r292;
+    acc ^= *r293;
+    acc ^= *r294;
+    acc ^= *r295;
+    acc ^= *r296;
+    acc ^= *r297;
+    acc ^= *r298;
+    acc ^= *r299;
+    acc
+}
+
+fn main() {
+    f(3);
+}
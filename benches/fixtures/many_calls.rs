@@ -0,0 +1,116 @@
+fn get(p: &mut (u32, u32), first: bool) -> &mut u32 {
+    if first {
+        &mut p.0
+    } else {
+        &mut p.1
+    }
+}
+
+fn f() -> (u32, u32) {
+    let mut p = (0, 0);
+    let r0 = get(&mut p, true);
+    *r0 += 0;
+    let r1 = get(&mut p, false);
+    *r1 += 1;
+    let r2 = get(&mut p, true);
+    *r2 += 2;
+    let r3 = get(&mut p, false);
+    *r3 += 3;
+    let r4 = get(&mut p, true);
+    *r4 += 4;
+    let r5 = get(&mut p, false);
+    *r5 += 5;
+    let r6 = get(&mut p, true);
+    *r6 += 6;
+    let r7 = get(&mut p, false);
+    *r7 += 7;
+    let r8 = get(&mut p, true);
+    *r8 += 8;
+    let r9 = get(&mut p, false);
+    *r9 += 9;
+    let r10 = get(&mut p, true);
+    *r10 += 10;
+    let r11 = get(&mut p, false);
+    *r11 += 11;
+    let r12 = get(&mut p, true);
+    *r12 += 12;
+    let r13 = get(&mut p, false);
+    *r13 += 13;
+    let r14 = get(&mut p, true);
+    *r14 += 14;
+    let r15 = get(&mut p, false);
+    *r15 += 15;
+    let r16 = get(&mut p, true);
+    *r16 += 16;
+    let r17 = get(&mut p, false);
+    *r17 += 17;
+    let r18 = get(&mut p, true);
+    *r18 += 18;
+    let r19 = get(&mut p, false);
+    *r19 += 19;
+    let r20 = get(&mut p, true);
+    *r20 += 20;
+    let r21 = get(&mut p, false);
+    *r21 += 21;
+    let r22 = get(&mut p, true);
+    *r22 += 22;
+    let r23 = get(&mut p, false);
+    *r23 += 23;
+    let r24 = get(&mut p, true);
+    *r24 += 24;
+    let r25 = get(&mut p, false);
+    *r25 += 25;
+    let r26 = get(&mut p, true);
+    *r26 += 26;
+    let r27 = get(&mut p, false);
+    *r27 += 27;
+    let r28 = get(&mut p, true);
+    *r28 += 28;
+    let r29 = get(&mut p, false);
+    *r29 += 29;
+    let r30 = get(&mut p, true);
+    *r30 += 30;
+    let r31 = get(&mut p, false);
+    *r31 += 31;
+    let r32 = get(&mut p, true);
+    *r32 += 32;
+    let r33 = get(&mut p, false);
+    *r33 += 33;
+    let r34 = get(&mut p, true);
+    *r34 += 34;
+    let r35 = get(&mut p, false);
+    *r35 += 35;
+    let r36 = get(&mut p, true);
+    *r36 += 36;
+    let r37 = get(&mut p, false);
+    *r37 += 37;
+    let r38 = get(&mut p, true);
+    *r38 += 38;
+    let r39 = get(&mut p, false);
+    *r39 += 39;
+    let r40 = get(&mut p, true);
+    *r40 += 40;
+    let r41 = get(&mut p, false);
+    *r41 += 41;
+    let r42 = get(&mut p, true);
+    *r42 += 42;
+    let r43 = get(&mut p, false);
+    *r43 += 43;
+    let r44 = get(&mut p, true);
+    *r44 += 44;
+    let r45 = get(&mut p, false);
+    *r45 += 45;
+    let r46 = get(&mut p, true);
+    *r46 += 46;
+    let r47 = get(&mut p, false);
+    *r47 += 47;
+    let r48 = get(&mut p, true);
+    *r48 += 48;
+    let r49 = get(&mut p, false);
+    *r49 += 49;
+    p
+}
+
+fn main() {
+    f();
+}
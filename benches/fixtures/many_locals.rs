@@ -0,0 +1,408 @@
+#![allow(unused_assignments)]
+
+fn f(x: u32) -> u32 {
+    let mut x0 = x;
+    let mut x1 = x0 + 1;
+    let mut x2 = x1 + 1;
+    let mut x3 = x2 + 1;
+    let mut x4 = x3 + 1;
+    let mut x5 = x4 + 1;
+    let mut x6 = x5 + 1;
+    let mut x7 = x6 + 1;
+    let mut x8 = x7 + 1;
+    let mut x9 = x8 + 1;
+    let mut x10 = x9 + 1;
+    let mut x11 = x10 + 1;
+    let mut x12 = x11 + 1;
+    let mut x13 = x12 + 1;
+    let mut x14 = x13 + 1;
+    let mut x15 = x14 + 1;
+    let mut x16 = x15 + 1;
+    let mut x17 = x16 + 1;
+    let mut x18 = x17 + 1;
+    let mut x19 = x18 + 1;
+    let mut x20 = x19 + 1;
+    let mut x21 = x20 + 1;
+    let mut x22 = x21 + 1;
+    let mut x23 = x22 + 1;
+    let mut x24 = x23 + 1;
+    let mut x25 = x24 + 1;
+    let mut x26 = x25 + 1;
+    let mut x27 = x26 + 1;
+    let mut x28 = x27 + 1;
+    let mut x29 = x28 + 1;
+    let mut x30 = x29 + 1;
+    let mut x31 = x30 + 1;
+    let mut x32 = x31 + 1;
+    let mut x33 = x32 + 1;
+    let mut x34 = x33 + 1;
+    let mut x35 = x34 + 1;
+    let mut x36 = x35 + 1;
+    let mut x37 = x36 + 1;
+    let mut x38 = x37 + 1;
+    let mut x39 = x38 + 1;
+    let mut x40 = x39 + 1;
+    let mut x41 = x40 + 1;
+    let mut x42 = x41 + 1;
+    let mut x43 = x42 + 1;
+    let mut x44 = x43 + 1;
+    let mut x45 = x44 + 1;
+    let mut x46 = x45 + 1;
+    let mut x47 = x46 + 1;
+    let mut x48 = x47 + 1;
+    let mut x49 = x48 + 1;
+    let mut x50 = x49 + 1;
+    let mut x51 = x50 + 1;
+    let mut x52 = x51 + 1;
+    let mut x53 = x52 + 1;
+    let mut x54 = x53 + 1;
+    let mut x55 = x54 + 1;
+    let mut x56 = x55 + 1;
+    let mut x57 = x56 + 1;
+    let mut x58 = x57 + 1;
+    let mut x59 = x58 + 1;
+    let mut x60 = x59 + 1;
+    let mut x61 = x60 + 1;
+    let mut x62 = x61 + 1;
+    let mut x63 = x62 + 1;
+    let mut x64 = x63 + 1;
+    let mut x65 = x64 + 1;
+    let mut x66 = x65 + 1;
+    let mut x67 = x66 + 1;
+    let mut x68 = x67 + 1;
+    let mut x69 = x68 + 1;
+    let mut x70 = x69 + 1;
+    let mut x71 = x70 + 1;
+    let mut x72 = x71 + 1;
+    let mut x73 = x72 + 1;
+    let mut x74 = x73 + 1;
+    let mut x75 = x74 + 1;
+    let mut x76 = x75 + 1;
+    let mut x77 = x76 + 1;
+    let mut x78 = x77 + 1;
+    let mut x79 = x78 + 1;
+    let mut x80 = x79 + 1;
+    let mut x81 = x80 + 1;
+    let mut x82 = x81 + 1;
+    let mut x83 = x82 + 1;
+    let mut x84 = x83 + 1;
+    let mut x85 = x84 + 1;
+    let mut x86 = x85 + 1;
+    let mut x87 = x86 + 1;
+    let mut x88 = x87 + 1;
+    let mut x89 = x88 + 1;
+    let mut x90 = x89 + 1;
+    let mut x91 = x90 + 1;
+    let mut x92 = x91 + 1;
+    let mut x93 = x92 + 1;
+    let mut x94 = x93 + 1;
+    let mut x95 = x94 + 1;
+    let mut x96 = x95 + 1;
+    let mut x97 = x96 + 1;
+    let mut x98 = x97 + 1;
+    let mut x99 = x98 + 1;
+    let mut x100 = x99 + 1;
+    let mut x101 = x100 + 1;
+    let mut x102 = x101 + 1;
+    let mut x103 = x102 + 1;
+    let mut x104 = x103 + 1;
+    let mut x105 = x104 + 1;
+    let mut x106 = x105 + 1;
+    let mut x107 = x106 + 1;
+    let mut x108 = x107 + 1;
+    let mut x109 = x108 + 1;
+    let mut x110 = x109 + 1;
+    let mut x111 = x110 + 1;
+    let mut x112 = x111 + 1;
+    let mut x113 = x112 + 1;
+    let mut x114 = x113 + 1;
+    let mut x115 = x114 + 1;
+    let mut x116 = x115 + 1;
+    let mut x117 = x116 + 1;
+    let mut x118 = x117 + 1;
+    let mut x119 = x118 + 1;
+    let mut x120 = x119 + 1;
+    let mut x121 = x120 + 1;
+    let mut x122 = x121 + 1;
+    let mut x123 = x122 + 1;
+    let mut x124 = x123 + 1;
+    let mut x125 = x124 + 1;
+    let mut x126 = x125 + 1;
+    let mut x127 = x126 + 1;
+    let mut x128 = x127 + 1;
+    let mut x129 = x128 + 1;
+    let mut x130 = x129 + 1;
+    let mut x131 = x130 + 1;
+    let mut x132 = x131 + 1;
+    let mut x133 = x132 + 1;
+    let mut x134 = x133 + 1;
+    let mut x135 = x134 + 1;
+    let mut x136 = x135 + 1;
+    let mut x137 = x136 + 1;
+    let mut x138 = x137 + 1;
+    let mut x139 = x138 + 1;
+    let mut x140 = x139 + 1;
+    let mut x141 = x140 + 1;
+    let mut x142 = x141 + 1;
+    let mut x143 = x142 + 1;
+    let mut x144 = x143 + 1;
+    let mut x145 = x144 + 1;
+    let mut x146 = x145 + 1;
+    let mut x147 = x146 + 1;
+    let mut x148 = x147 + 1;
+    let mut x149 = x148 + 1;
+    let mut x150 = x149 + 1;
+    let mut x151 = x150 + 1;
+    let mut x152 = x151 + 1;
+    let mut x153 = x152 + 1;
+    let mut x154 = x153 + 1;
+    let mut x155 = x154 + 1;
+    let mut x156 = x155 + 1;
+    let mut x157 = x156 + 1;
+    let mut x158 = x157 + 1;
+    let mut x159 = x158 + 1;
+    let mut x160 = x159 + 1;
+    let mut x161 = x160 + 1;
+    let mut x162 = x161 + 1;
+    let mut x163 = x162 + 1;
+    let mut x164 = x163 + 1;
+    let mut x165 = x164 + 1;
+    let mut x166 = x165 + 1;
+    let mut x167 = x166 + 1;
+    let mut x168 = x167 + 1;
+    let mut x169 = x168 + 1;
+    let mut x170 = x169 + 1;
+    let mut x171 = x170 + 1;
+    let mut x172 = x171 + 1;
+    let mut x173 = x172 + 1;
+    let mut x174 = x173 + 1;
+    let mut x175 = x174 + 1;
+    let mut x176 = x175 + 1;
+    let mut x177 = x176 + 1;
+    let mut x178 = x177 + 1;
+    let mut x179 = x178 + 1;
+    let mut x180 = x179 + 1;
+    let mut x181 = x180 + 1;
+    let mut x182 = x181 + 1;
+    let mut x183 = x182 + 1;
+    let mut x184 = x183 + 1;
+    let mut x185 = x184 + 1;
+    let mut x186 = x185 + 1;
+    let mut x187 = x186 + 1;
+    let mut x188 = x187 + 1;
+    let mut x189 = x188 + 1;
+    let mut x190 = x189 + 1;
+    let mut x191 = x190 + 1;
+    let mut x192 = x191 + 1;
+    let mut x193 = x192 + 1;
+    let mut x194 = x193 + 1;
+    let mut x195 = x194 + 1;
+    let mut x196 = x195 + 1;
+    let mut x197 = x196 + 1;
+    let mut x198 = x197 + 1;
+    let mut x199 = x198 + 1;
+    x0 += x1;
+    x1 += x2;
+    x2 += x3;
+    x3 += x4;
+    x4 += x5;
+    x5 += x6;
+    x6 += x7;
+    x7 += x8;
+    x8 += x9;
+    x9 += x10;
+    x10 += x11;
+    x11 += x12;
+    x12 += x13;
+    x13 += x14;
+    x14 += x15;
+    x15 += x16;
+    x16 += x17;
+    x17 += x18;
+    x18 += x19;
+    x19 += x20;
+    x20 += x21;
+    x21 += x22;
+    x22 += x23;
+    x23 += x24;
+    x24 += x25;
+    x25 += x26;
+    x26 += x27;
+    x27 += x28;
+    x28 += x29;
+    x29 += x30;
+    x30 += x31;
+    x31 += x32;
+    x32 += x33;
+    x33 += x34;
+    x34 += x35;
+    x35 += x36;
+    x36 += x37;
+    x37 += x38;
+    x38 += x39;
+    x39 += x40;
+    x40 += x41;
+    x41 += x42;
+    x42 += x43;
+    x43 += x44;
+    x44 += x45;
+    x45 += x46;
+    x46 += x47;
+    x47 += x48;
+    x48 += x49;
+    x49 += x50;
+    x50 += x51;
+    x51 += x52;
+    x52 += x53;
+    x53 += x54;
+    x54 += x55;
+    x55 += x56;
+    x56 += x57;
+    x57 += x58;
+    x58 += x59;
+    x59 += x60;
+    x60 += x61;
+    x61 += x62;
+    x62 += x63;
+    x63 += x64;
+    x64 += x65;
+    x65 += x66;
+    x66 += x67;
+    x67 += x68;
+    x68 += x69;
+    x69 += x70;
+    x70 += x71;
+    x71 += x72;
+    x72 += x73;
+    x73 += x74;
+    x74 += x75;
+    x75 += x76;
+    x76 += x77;
+    x77 += x78;
+    x78 += x79;
+    x79 += x80;
+    x80 += x81;
+    x81 += x82;
+    x82 += x83;
+    x83 += x84;
+    x84 += x85;
+    x85 += x86;
+    x86 += x87;
+    x87 += x88;
+    x88 += x89;
+    x89 += x90;
+    x90 += x91;
+    x91 += x92;
+    x92 += x93;
+    x93 += x94;
+    x94 += x95;
+    x95 += x96;
+    x96 += x97;
+    x97 += x98;
+    x98 += x99;
+    x99 += x100;
+    x100 += x101;
+    x101 += x102;
+    x102 += x103;
+    x103 += x104;
+    x104 += x105;
+    x105 += x106;
+    x106 += x107;
+    x107 += x108;
+    x108 += x109;
+    x109 += x110;
+    x110 += x111;
+    x111 += x112;
+    x112 += x113;
+    x113 += x114;
+    x114 += x115;
+    x115 += x116;
+    x116 += x117;
+    x117 += x118;
+    x118 += x119;
+    x119 += x120;
+    x120 += x121;
+    x121 += x122;
+    x122 += x123;
+    x123 += x124;
+    x124 += x125;
+    x125 += x126;
+    x126 += x127;
+    x127 += x128;
+    x128 += x129;
+    x129 += x130;
+    x130 += x131;
+    x131 += x132;
+    x132 += x133;
+    x133 += x134;
+    x134 += x135;
+    x135 += x136;
+    x136 += x137;
+    x137 += x138;
+    x138 += x139;
+    x139 += x140;
+    x140 += x141;
+    x141 += x142;
+    x142 += x143;
+    x143 += x144;
+    x144 += x145;
+    x145 += x146;
+    x146 += x147;
+    x147 += x148;
+    x148 += x149;
+    x149 += x150;
+    x150 += x151;
+    x151 += x152;
+    x152 += x153;
+    x153 += x154;
+    x154 += x155;
+    x155 += x156;
+    x156 += x157;
+    x157 += x158;
+    x158 += x159;
+    x159 += x160;
+    x160 += x161;
+    x161 += x162;
+    x162 += x163;
+    x163 += x164;
+    x164 += x165;
+    x165 += x166;
+    x166 += x167;
+    x167 += x168;
+    x168 += x169;
+    x169 += x170;
+    x170 += x171;
+    x171 += x172;
+    x172 += x173;
+    x173 += x174;
+    x174 += x175;
+    x175 += x176;
+    x176 += x177;
+    x177 += x178;
+    x178 += x179;
+    x179 += x180;
+    x180 += x181;
+    x181 += x182;
+    x182 += x183;
+    x183 += x184;
+    x184 += x185;
+    x185 += x186;
+    x186 += x187;
+    x187 += x188;
+    x188 += x189;
+    x189 += x190;
+    x190 += x191;
+    x191 += x192;
+    x192 += x193;
+    x193 += x194;
+    x194 += x195;
+    x195 += x196;
+    x196 += x197;
+    x197 += x198;
+    x198 += x199;
+    x0
+}
+
+fn main() {
+    f(1);
+}
@@ -0,0 +1,207 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Times `pcs_bin` on the programs in `benches/fixtures/`. Each fixture is
+//! analysed `PCS_BENCH_ITERS` times (5 by default) with
+//! `--pcs-no-visualization`, and the minimum and median wall time are
+//! reported together with the number of blocks, the number of locals and
+//! the peak number of borrows (at any location) of the largest function, as
+//! printed by `--pcs-stats`. `empty.rs` measures the cost of rustc itself.
+//!
+//! The analysis depends on `rustc_private`, so this is a plain timing
+//! harness rather than a criterion benchmark:
+//!
+//! ```text
+//! cargo bench --bench pipeline
+//! ```
+//!
+//! The results are written to `target/pcs-bench/results.json`. To compare
+//! against a baseline, keep a copy of that file and pass it back in:
+//!
+//! ```text
+//! git checkout main && cargo bench --bench pipeline
+//! cp target/pcs-bench/results.json /tmp/baseline.json
+//! git checkout my-branch
+//! PCS_BENCH_BASELINE=/tmp/baseline.json cargo bench --bench pipeline
+//! ```
+//!
+//! Passing a fixture name, e.g. `cargo bench --bench pipeline -- many_calls`,
+//! only runs the fixtures whose name contains it.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+const FIXTURES_DIR: &str = "benches/fixtures";
+const RESULTS_FILE: &str = "target/pcs-bench/results.json";
+
+/// The `--pcs-stats` line of the function with the most locals.
+#[derive(Default)]
+struct Stats {
+    blocks: u64,
+    locals: u64,
+    peak_borrows: u64,
+}
+
+impl Stats {
+    /// Parses e.g. `[pcs-stats] f: blocks=3 locals=5 peak_borrows=2`.
+    fn parse(line: &str) -> Option<Self> {
+        let (_, fields) = line.strip_prefix("[pcs-stats] ")?.split_once(": ")?;
+        let mut stats = Stats::default();
+        for field in fields.split_whitespace() {
+            let (name, value) = field.split_once('=')?;
+            let value = value.parse().ok()?;
+            match name {
+                "blocks" => stats.blocks = value,
+                "locals" => stats.locals = value,
+                "peak_borrows" => stats.peak_borrows = value,
+                _ => {}
+            }
+        }
+        Some(stats)
+    }
+}
+
+fn run_pcs(fixture: &Path, work_dir: &Path, library_path: &Path, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_pcs_bin"))
+        .args(args)
+        .arg(fixture)
+        .current_dir(work_dir)
+        .env("LD_LIBRARY_PATH", library_path)
+        .output()
+        .expect("Failed to run pcs_bin");
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    // `pcs_bin` exits successfully even if the program does not compile
+    assert!(
+        output.status.success() && !stderr.contains("error"),
+        "pcs_bin failed on {}:\n{stderr}",
+        fixture.display()
+    );
+    stderr
+}
+
+fn bench_fixture(fixture: &Path, iterations: usize, library_path: &Path) -> Value {
+    let name = fixture.file_stem().unwrap().to_str().unwrap();
+    let work_dir = std::env::temp_dir().join(format!("pcs-bench-{}-{name}", std::process::id()));
+    fs::create_dir_all(&work_dir).unwrap();
+
+    let mut times: Vec<Duration> = (0..iterations)
+        .map(|_| {
+            let start = Instant::now();
+            run_pcs(fixture, &work_dir, library_path, &["--pcs-no-visualization"]);
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    let stderr = run_pcs(
+        fixture,
+        &work_dir,
+        library_path,
+        &["--pcs-no-visualization", "--pcs-stats"],
+    );
+    let stats = stderr
+        .lines()
+        .filter_map(Stats::parse)
+        .max_by_key(|stats| stats.locals)
+        .unwrap_or_default();
+    fs::remove_dir_all(&work_dir).unwrap();
+
+    json!({
+        "min_ms": times[0].as_secs_f64() * 1000.0,
+        "median_ms": times[times.len() / 2].as_secs_f64() * 1000.0,
+        "blocks": stats.blocks,
+        "locals": stats.locals,
+        "peak_borrows": stats.peak_borrows,
+    })
+}
+
+/// The change from `baseline` to `current`, e.g. `+12.5%`.
+fn relative_change(current: f64, baseline: f64) -> String {
+    if baseline == 0.0 {
+        return "n/a".to_string();
+    }
+    format!("{:+.1}%", (current - baseline) / baseline * 100.0)
+}
+
+fn main() {
+    // `cargo bench` passes `--bench`; any other argument is a filter
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+    let iterations = std::env::var("PCS_BENCH_ITERS")
+        .ok()
+        .map(|iters| iters.parse().expect("PCS_BENCH_ITERS must be a number"))
+        .unwrap_or(5)
+        .max(1);
+    let baseline: Option<BTreeMap<String, Value>> =
+        std::env::var("PCS_BENCH_BASELINE").ok().map(|path| {
+            let contents = fs::read_to_string(&path)
+                .unwrap_or_else(|err| panic!("Failed to read {path}: {err}"));
+            serde_json::from_str(&contents)
+                .unwrap_or_else(|err| panic!("Failed to parse {path}: {err}"))
+        });
+    let sysroot = Command::new("rustc")
+        .args(["--print", "sysroot"])
+        .output()
+        .expect("Failed to run rustc");
+    let library_path = Path::new(String::from_utf8(sysroot.stdout).unwrap().trim()).join("lib");
+
+    let mut fixtures: Vec<PathBuf> = fs::read_dir(FIXTURES_DIR)
+        .unwrap()
+        .map(|entry| fs::canonicalize(entry.unwrap().path()).unwrap())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rs"))
+        .collect();
+    fixtures.sort();
+
+    let mut results = BTreeMap::new();
+    println!(
+        "{:<24} {:>10} {:>10} {:>7} {:>7} {:>8}",
+        "fixture", "min ms", "median ms", "blocks", "locals", "borrows"
+    );
+    for fixture in fixtures {
+        let name = fixture.file_stem().unwrap().to_str().unwrap().to_string();
+        if filter.as_ref().is_some_and(|filter| !name.contains(filter.as_str())) {
+            continue;
+        }
+        let result = bench_fixture(&fixture, iterations, &library_path);
+        let mut line = format!(
+            "{name:<24} {:>10.1} {:>10.1} {:>7} {:>7} {:>8}",
+            result["min_ms"].as_f64().unwrap(),
+            result["median_ms"].as_f64().unwrap(),
+            result["blocks"].as_u64().unwrap(),
+            result["locals"].as_u64().unwrap(),
+            result["peak_borrows"].as_u64().unwrap()
+        );
+        if let Some(previous) = baseline.as_ref().and_then(|baseline| baseline.get(&name)) {
+            line += &format!(
+                "  (median {}, borrows {})",
+                relative_change(
+                    result["median_ms"].as_f64().unwrap(),
+                    previous["median_ms"].as_f64().unwrap()
+                ),
+                relative_change(
+                    result["peak_borrows"].as_f64().unwrap(),
+                    previous["peak_borrows"].as_f64().unwrap()
+                )
+            );
+        }
+        println!("{line}");
+        results.insert(name, result);
+    }
+
+    let results_file = Path::new(RESULTS_FILE);
+    fs::create_dir_all(results_file.parent().unwrap()).unwrap();
+    fs::write(
+        results_file,
+        serde_json::to_string_pretty(&results).unwrap() + "\n",
+    )
+    .unwrap();
+    println!("Results written to {RESULTS_FILE}");
+}
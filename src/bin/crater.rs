@@ -0,0 +1,385 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! `pcs_crater [--crates=<file>] [--top=<n>] [--out=<dir>] [--timeout=<secs>]`:
+//! runs the analysis on the library of each of a list of crates and reports
+//! the panics encountered, grouped by message.
+//!
+//! The crates are read from `<file>`, one `name version` (or just `name`, for
+//! the newest version) per line, or are otherwise the `<n>` (by default 100)
+//! most downloaded crates on crates.io. Each crate is downloaded and unpacked
+//! into `<dir>/crates` (by default `crater`) and checked with `pcs_bin` as
+//! `RUSTC_WORKSPACE_WRAPPER`, catching panics. The result for each crate is
+//! stored in `<dir>/results`, so that an interrupted run can be resumed, and
+//! the aggregated report is written to `<dir>/crater_report.json`. Requires
+//! `curl` and `tar`.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_derive::{Deserialize, Serialize};
+
+/// See `PANIC_PREFIX` in `pcs_bin`.
+const PANIC_PREFIX: &str = "[pcs-panic]";
+/// The first line of panics not caught by `pcs_bin`, the message follows on
+/// the next line.
+const UNCAUGHT_PANIC_PREFIX: &str = "thread 'rustc' panicked at";
+const REPORT_FILE: &str = "crater_report.json";
+const USER_AGENT: &str = "pcs-crater (https://github.com/zgrannan/pcs)";
+/// The number of example functions listed for each panic message.
+const MAX_EXAMPLES: usize = 5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum CrateStatus {
+    /// `cargo check` succeeded.
+    Ok,
+    /// `cargo check` failed, e.g. because of a panic or because `pcs_bin`
+    /// stops the compilation before metadata is emitted for binaries.
+    Failed,
+    Timeout,
+    DownloadFailed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Panic {
+    /// The function being analysed, if the panic was caught by `pcs_bin`.
+    function: Option<String>,
+    message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CrateResult {
+    name: String,
+    version: String,
+    status: CrateStatus,
+    duration_secs: u64,
+    panics: Vec<Panic>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct PanicGroup {
+    message: String,
+    count: usize,
+    /// E.g. `serde-1.0.193: deserialize`.
+    examples: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Report {
+    crates: usize,
+    statuses: BTreeMap<String, usize>,
+    /// Sorted by decreasing count.
+    panics: Vec<PanicGroup>,
+}
+
+struct Options {
+    crates_file: Option<PathBuf>,
+    top: usize,
+    out_dir: PathBuf,
+    timeout: Duration,
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        crates_file: None,
+        top: 100,
+        out_dir: PathBuf::from("crater"),
+        timeout: Duration::from_secs(600),
+    };
+    let invalid = |arg: &str| -> ! {
+        eprintln!("Invalid argument: {arg}");
+        eprintln!(
+            "Usage: pcs_crater [--crates=<file>] [--top=<n>] [--out=<dir>] [--timeout=<secs>]"
+        );
+        std::process::exit(2);
+    };
+    for arg in std::env::args().skip(1) {
+        if let Some(file) = arg.strip_prefix("--crates=") {
+            options.crates_file = Some(PathBuf::from(file));
+        } else if let Some(top) = arg.strip_prefix("--top=") {
+            options.top = top.parse().unwrap_or_else(|_| invalid(&arg));
+        } else if let Some(dir) = arg.strip_prefix("--out=") {
+            options.out_dir = PathBuf::from(dir);
+        } else if let Some(secs) = arg.strip_prefix("--timeout=") {
+            options.timeout = Duration::from_secs(secs.parse().unwrap_or_else(|_| invalid(&arg)));
+        } else {
+            invalid(&arg);
+        }
+    }
+    options
+}
+
+fn curl(url: &str) -> Option<Vec<u8>> {
+    let output = Command::new("curl")
+        .args([
+            "--silent",
+            "--fail",
+            "--location",
+            "--user-agent",
+            USER_AGENT,
+            url,
+        ])
+        .output()
+        .expect("Failed to run curl");
+    output.status.success().then_some(output.stdout)
+}
+
+fn crates_io_json(url: &str) -> serde_json::Value {
+    let body = curl(url).unwrap_or_else(|| panic!("Failed to fetch {url}"));
+    serde_json::from_slice(&body).unwrap_or_else(|err| panic!("Invalid response from {url}: {err}"))
+}
+
+fn newest_version(name: &str) -> String {
+    let info = crates_io_json(&format!("https://crates.io/api/v1/crates/{name}"));
+    info["crate"]["max_stable_version"]
+        .as_str()
+        .or_else(|| info["crate"]["newest_version"].as_str())
+        .unwrap_or_else(|| panic!("No version found for {name}"))
+        .to_string()
+}
+
+/// The `top` most downloaded crates, with their newest stable versions.
+fn top_crates(top: usize) -> Vec<(String, String)> {
+    let mut crates = Vec::new();
+    let mut page = 1;
+    while crates.len() < top {
+        let per_page = (top - crates.len()).min(100);
+        let response = crates_io_json(&format!(
+            "https://crates.io/api/v1/crates?page={page}&per_page={per_page}&sort=downloads"
+        ));
+        let page_crates = response["crates"].as_array().unwrap();
+        if page_crates.is_empty() {
+            break;
+        }
+        crates.extend(page_crates.iter().map(|krate| {
+            let version = krate["max_stable_version"]
+                .as_str()
+                .or_else(|| krate["newest_version"].as_str())
+                .unwrap();
+            (
+                krate["id"].as_str().unwrap().to_string(),
+                version.to_string(),
+            )
+        }));
+        page += 1;
+    }
+    crates.truncate(top);
+    crates
+}
+
+fn read_crates_file(path: &Path) -> Vec<(String, String)> {
+    fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("Failed to read {}: {err}", path.display()))
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.split_once(char::is_whitespace) {
+            Some((name, version)) => (name.to_string(), version.trim().to_string()),
+            None => (line.to_string(), newest_version(line)),
+        })
+        .collect()
+}
+
+/// Downloads and unpacks the crate into `crates_dir`, returning its directory.
+fn download(name: &str, version: &str, crates_dir: &Path) -> Option<PathBuf> {
+    let crate_dir = crates_dir.join(format!("{name}-{version}"));
+    if crate_dir.join("Cargo.toml").exists() {
+        return Some(crate_dir);
+    }
+    let archive = curl(&format!(
+        "https://static.crates.io/crates/{name}/{name}-{version}.crate"
+    ))?;
+    let archive_path = crates_dir.join(format!("{name}-{version}.crate"));
+    fs::write(&archive_path, archive).ok()?;
+    let unpacked = Command::new("tar")
+        .arg("xzf")
+        .arg(&archive_path)
+        .arg("-C")
+        .arg(crates_dir)
+        .status()
+        .ok()?
+        .success();
+    fs::remove_file(&archive_path).ok()?;
+    unpacked.then_some(crate_dir)
+}
+
+fn parse_panics(stderr: &str) -> Vec<Panic> {
+    let mut panics = Vec::new();
+    let mut lines = stderr.lines();
+    while let Some(line) = lines.next() {
+        if let Some(panic) = line.strip_prefix(PANIC_PREFIX) {
+            let (function, message) = panic.trim().split_once(": ").unwrap_or(("", panic));
+            panics.push(Panic {
+                function: Some(function.to_string()),
+                message: message.to_string(),
+            });
+        } else if line.starts_with(UNCAUGHT_PANIC_PREFIX) {
+            // Caught panics are printed by the panic hook as well
+            let message = lines.next().unwrap_or_default().to_string();
+            let caught = stderr
+                .lines()
+                .any(|line| line.starts_with(PANIC_PREFIX) && line.ends_with(&message));
+            if !caught {
+                panics.push(Panic {
+                    function: None,
+                    message,
+                });
+            }
+        }
+    }
+    panics
+}
+
+/// Runs `cargo check` on the crate in `crate_dir` with `pcs_bin` as the
+/// compiler of the crate itself (but not of its dependencies).
+fn check_crate(
+    crate_dir: &Path,
+    target_dir: &Path,
+    timeout: Duration,
+) -> (CrateStatus, Vec<Panic>) {
+    let pcs_bin = std::env::current_exe().unwrap().with_file_name("pcs_bin");
+    let sysroot = Command::new("rustc")
+        .args(["--print", "sysroot"])
+        .output()
+        .expect("Failed to run rustc");
+    let sysroot = String::from_utf8(sysroot.stdout).unwrap();
+    let stderr_path = target_dir.join("stderr.txt");
+    let mut child = Command::new("cargo")
+        .args(["check", "--lib", "--quiet"])
+        .current_dir(crate_dir)
+        .env("RUSTC_WORKSPACE_WRAPPER", pcs_bin)
+        .env("PCS_FLAGS", "--pcs-catch-panics --pcs-no-visualization")
+        .env("CARGO_TARGET_DIR", target_dir)
+        .env("LD_LIBRARY_PATH", Path::new(sysroot.trim()).join("lib"))
+        .stdout(Stdio::null())
+        .stderr(fs::File::create(&stderr_path).unwrap())
+        .spawn()
+        .expect("Failed to run cargo");
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break Some(status);
+        }
+        if start.elapsed() > timeout {
+            child.kill().unwrap();
+            child.wait().unwrap();
+            break None;
+        }
+        thread::sleep(Duration::from_millis(200));
+    };
+    let stderr = fs::read_to_string(&stderr_path).unwrap_or_default();
+    let status = match status {
+        Some(status) if status.success() => CrateStatus::Ok,
+        Some(_) => CrateStatus::Failed,
+        None => CrateStatus::Timeout,
+    };
+    (status, parse_panics(&stderr))
+}
+
+fn report(results: &[CrateResult]) -> Report {
+    let mut statuses = BTreeMap::new();
+    let mut groups: BTreeMap<&str, PanicGroup> = BTreeMap::new();
+    for result in results {
+        let status = serde_json::to_value(&result.status).unwrap();
+        *statuses
+            .entry(status.as_str().unwrap().to_string())
+            .or_default() += 1;
+        for panic in &result.panics {
+            let group = groups.entry(&panic.message).or_insert_with(|| PanicGroup {
+                message: panic.message.clone(),
+                count: 0,
+                examples: Vec::new(),
+            });
+            group.count += 1;
+            if group.examples.len() < MAX_EXAMPLES {
+                let function = panic.function.as_deref().unwrap_or("<unknown>");
+                group
+                    .examples
+                    .push(format!("{}-{}: {function}", result.name, result.version));
+            }
+        }
+    }
+    let mut panics: Vec<_> = groups.into_values().collect();
+    panics.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.message.cmp(&b.message))
+    });
+    Report {
+        crates: results.len(),
+        statuses,
+        panics,
+    }
+}
+
+fn main() {
+    let options = parse_options();
+    let crates = match &options.crates_file {
+        Some(file) => read_crates_file(file),
+        None => top_crates(options.top),
+    };
+    let crates_dir = options.out_dir.join("crates");
+    let results_dir = options.out_dir.join("results");
+    let target_dir = options.out_dir.join("target");
+    for dir in [&crates_dir, &results_dir, &target_dir] {
+        fs::create_dir_all(dir).expect("Failed to create the output directories");
+    }
+
+    let mut results = Vec::new();
+    for (index, (name, version)) in crates.iter().enumerate() {
+        let result_path = results_dir.join(format!("{name}-{version}.json"));
+        let cached = fs::read_to_string(&result_path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<CrateResult>(&contents).ok());
+        let result = match cached {
+            Some(result) => {
+                eprintln!(
+                    "[{}/{}] {name}-{version}: already processed",
+                    index + 1,
+                    crates.len()
+                );
+                result
+            }
+            None => {
+                eprintln!("[{}/{}] {name}-{version}", index + 1, crates.len());
+                let start = Instant::now();
+                let (status, panics) = match download(name, version, &crates_dir) {
+                    Some(crate_dir) => check_crate(&crate_dir, &target_dir, options.timeout),
+                    None => (CrateStatus::DownloadFailed, Vec::new()),
+                };
+                let result = CrateResult {
+                    name: name.clone(),
+                    version: version.clone(),
+                    status,
+                    duration_secs: start.elapsed().as_secs(),
+                    panics,
+                };
+                fs::write(&result_path, serde_json::to_string_pretty(&result).unwrap())
+                    .expect("Failed to write the result");
+                result
+            }
+        };
+        results.push(result);
+    }
+
+    let report = report(&results);
+    let report_path = options.out_dir.join(REPORT_FILE);
+    fs::write(&report_path, serde_json::to_string_pretty(&report).unwrap())
+        .expect("Failed to write the report");
+    eprintln!(
+        "{} crates, {} distinct panics, report written to {}",
+        report.crates,
+        report.panics.len(),
+        report_path.display()
+    );
+}
@@ -0,0 +1,302 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! `pcs_import_prusti <dir> [<out_dir>]`: copies the Rust files in `dir` (and
+//! its subdirectories) to `out_dir` (by default `test-files/imported`) with
+//! the Prusti specifications removed, so that they can be analysed without
+//! depending on `prusti-contracts`. Removed code is replaced by whitespace,
+//! so line numbers are preserved. The original path of each imported file is
+//! recorded in `out_dir/manifest.json`.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Attributes that only carry specifications, e.g. `#[requires(x > 0)]`.
+const SPEC_ATTRIBUTES: &[&str] = &[
+    "requires",
+    "ensures",
+    "invariant",
+    "pure",
+    "trusted",
+    "after_expiry",
+    "assert_on_expiry",
+];
+
+/// Macros that only carry specifications, e.g. `body_invariant!(i < n);`.
+const SPEC_MACROS: &[&str] = &[
+    "body_invariant",
+    "prusti_assert",
+    "prusti_assume",
+    "prusti_refute",
+];
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// If a string or character literal starts at `i`, the index just past it.
+/// Lifetimes, e.g. `'a`, are not literals.
+fn literal_end(source: &[u8], i: usize) -> Option<usize> {
+    match source[i] {
+        b'"' => {
+            let mut j = i + 1;
+            while j < source.len() && source[j] != b'"' {
+                if source[j] == b'\\' {
+                    j += 1;
+                }
+                j += 1;
+            }
+            Some((j + 1).min(source.len()))
+        }
+        // A raw string, e.g. `r#"..."#`, which has no escapes
+        b'r' if i == 0 || !is_ident_byte(source[i - 1]) => {
+            let hashes = source[i + 1..]
+                .iter()
+                .take_while(|&&byte| byte == b'#')
+                .count();
+            if source.get(i + 1 + hashes) != Some(&b'"') {
+                return None;
+            }
+            let mut closing = vec![b'"'];
+            closing.resize(hashes + 1, b'#');
+            let start = i + 2 + hashes;
+            let end = source[start..]
+                .windows(closing.len())
+                .position(|window| window == closing)
+                .map_or(source.len(), |position| start + position + closing.len());
+            Some(end)
+        }
+        b'\'' if source.get(i + 2) == Some(&b'\'') => Some(i + 3),
+        // An escaped character, e.g. `'\''` or `'\u{7FFF}'`
+        b'\'' if source.get(i + 1) == Some(&b'\\') => {
+            let close = (i + 3..source.len()).find(|&j| source[j] == b'\'')?;
+            Some(close + 1)
+        }
+        _ => None,
+    }
+}
+
+/// The index just past the bracket closing the one at `open`. String and
+/// character literals are skipped, so brackets within them do not count.
+fn matching_bracket(source: &[u8], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = open;
+    while i < source.len() {
+        if let Some(end) = literal_end(source, i) {
+            i = end;
+            continue;
+        }
+        match source[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+fn is_ident_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+fn skip_whitespace(source: &[u8], mut i: usize) -> usize {
+    while i < source.len() && source[i].is_ascii_whitespace() {
+        i += 1;
+    }
+    i
+}
+
+/// If a specification attribute starts at `start` (with `#`), the index just
+/// past it.
+fn spec_attribute_end(source: &[u8], start: usize) -> Option<usize> {
+    let open = skip_whitespace(source, start + 1);
+    if source.get(open) != Some(&b'[') {
+        return None;
+    }
+    let name_start = skip_whitespace(source, open + 1);
+    let name_end = (name_start..source.len())
+        .find(|&i| !is_ident_byte(source[i]))
+        .unwrap_or(source.len());
+    let name = std::str::from_utf8(&source[name_start..name_end]).ok()?;
+    if !SPEC_ATTRIBUTES.contains(&name) {
+        return None;
+    }
+    matching_bracket(source, open)
+}
+
+/// If a specification macro is invoked at `start`, the index just past the
+/// invocation, including a trailing `;`.
+fn spec_macro_end(source: &[u8], start: usize) -> Option<usize> {
+    if start > 0 && is_ident_byte(source[start - 1]) {
+        return None;
+    }
+    let name = SPEC_MACROS.iter().find(|name| {
+        source[start..].starts_with(name.as_bytes())
+            && source.get(start + name.len()) == Some(&b'!')
+    })?;
+    let open = skip_whitespace(source, start + name.len() + 1);
+    if !matches!(source.get(open), Some(b'(' | b'[' | b'{')) {
+        return None;
+    }
+    let end = matching_bracket(source, open)?;
+    let semicolon = skip_whitespace(source, end);
+    Some(if source.get(semicolon) == Some(&b';') {
+        semicolon + 1
+    } else {
+        end
+    })
+}
+
+/// Removes the Prusti specifications and the import of `prusti_contracts`
+/// from `source`, replacing them with whitespace. String and character
+/// literals are kept as they are, even if they look like specifications.
+fn strip_specifications(source: &str) -> String {
+    let mut stripped = source.as_bytes().to_vec();
+    let mut i = 0;
+    while i < stripped.len() {
+        if let Some(end) = literal_end(&stripped, i) {
+            i = end;
+            continue;
+        }
+        let end = match stripped[i] {
+            b'#' => spec_attribute_end(&stripped, i),
+            b'u' if stripped[i..].starts_with(b"use prusti_contracts") => stripped[i..]
+                .iter()
+                .position(|&byte| byte == b';')
+                .map(|end| i + end + 1),
+            _ => spec_macro_end(&stripped, i),
+        };
+        match end {
+            Some(end) => {
+                for byte in &mut stripped[i..end] {
+                    if *byte != b'\n' {
+                        *byte = b' ';
+                    }
+                }
+                i = end;
+            }
+            None => i += 1,
+        }
+    }
+    // Only ASCII bytes were replaced, so this is still valid UTF-8
+    let stripped = String::from_utf8(stripped).unwrap();
+    let mut lines: Vec<_> = stripped.lines().map(str::trim_end).collect();
+    lines.push("");
+    lines.join("\n")
+}
+
+fn collect_rust_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("Failed to read {}: {err}", dir.display()))
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_rust_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (source_dir, out_dir) = match args.as_slice() {
+        [source_dir] => (Path::new(source_dir), Path::new("test-files/imported")),
+        [source_dir, out_dir] => (Path::new(source_dir), Path::new(out_dir)),
+        _ => {
+            eprintln!("Usage: pcs_import_prusti <dir> [<out_dir>]");
+            std::process::exit(2);
+        }
+    };
+    fs::create_dir_all(out_dir).expect("Failed to create the output directory");
+    let manifest_path = out_dir.join(MANIFEST_FILE);
+    let mut manifest: BTreeMap<String, String> = match fs::read_to_string(&manifest_path) {
+        Ok(contents) => serde_json::from_str(&contents).expect("Failed to parse the manifest"),
+        Err(_) => BTreeMap::new(),
+    };
+
+    let mut files = Vec::new();
+    collect_rust_files(source_dir, &mut files);
+    for file in files {
+        // Flatten the path, e.g. `pass/loops/while.rs` becomes
+        // `pass__loops__while.rs`
+        let relative = file.strip_prefix(source_dir).unwrap();
+        let name = relative
+            .iter()
+            .map(|component| component.to_str().unwrap())
+            .collect::<Vec<_>>()
+            .join("__");
+        let source = fs::read_to_string(&file)
+            .unwrap_or_else(|err| panic!("Failed to read {}: {err}", file.display()));
+        fs::write(out_dir.join(&name), strip_specifications(&source))
+            .unwrap_or_else(|err| panic!("Failed to write {name}: {err}"));
+        let original = fs::canonicalize(&file).unwrap_or(file);
+        manifest.insert(name, original.display().to_string());
+    }
+    fs::write(
+        &manifest_path,
+        serde_json::to_string_pretty(&manifest).unwrap() + "\n",
+    )
+    .expect("Failed to write the manifest");
+    eprintln!(
+        "Imported {} files into {}",
+        manifest.len(),
+        out_dir.display()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literals_are_kept() {
+        let source = "fn f() {\n\
+            \x20   let s = \"#[requires(x)]\";\n\
+            \x20   let c = '\\'';\n\
+            \x20   let r = r#\"body_invariant!(\"x\");\"#;\n\
+            }\n";
+        assert_eq!(strip_specifications(source), source);
+    }
+
+    #[test]
+    fn specifications_are_blanked() {
+        let source = "use prusti_contracts::*;\n\
+            #[requires(\n    x > 0 &&\n    x < \")\".len()\n)]\n\
+            #[ensures(result == x)]\n\
+            fn f(x: usize) -> usize {\n\
+            \x20   let mut i = 0;\n\
+            \x20   while i < x {\n\
+            \x20       body_invariant!(i < x);\n\
+            \x20       i += 1;\n\
+            \x20   }\n\
+            \x20   x\n\
+            }\n";
+        let stripped = strip_specifications(source);
+        assert_eq!(stripped.lines().count(), source.lines().count());
+        assert_eq!(
+            stripped,
+            "\n\n\n\n\n\n\
+            fn f(x: usize) -> usize {\n\
+            \x20   let mut i = 0;\n\
+            \x20   while i < x {\n\
+            \n\
+            \x20       i += 1;\n\
+            \x20   }\n\
+            \x20   x\n\
+            }\n"
+        );
+    }
+}
@@ -1,21 +1,34 @@
-use std::rc::Rc;
+use std::{
+    collections::BTreeSet,
+    fmt::{Debug, Display, Formatter, Result},
+    rc::Rc,
+};
 
 use rustc_interface::{
-    borrowck::{borrow_set::BorrowSet, consumers::BorrowIndex},
+    borrowck::{
+        borrow_set::{BorrowSet, TwoPhaseActivation},
+        consumers::BorrowIndex,
+    },
     data_structures::fx::{FxHashMap, FxHashSet},
     dataflow::{AnalysisDomain, JoinSemiLattice},
-    middle::mir::{self, Location, VarDebugInfo},
+    middle::mir::{self, Local, Location, VarDebugInfo},
 };
 
-use crate::{rustc_interface, utils::Place};
+use crate::{
+    rustc_interface,
+    utils::{debug_info::DebugInfo, PersistentSet, Place, PlaceSnapshot, SnapshotLocation},
+};
 
 impl<'tcx> JoinSemiLattice for BorrowsState<'tcx> {
     fn join(&mut self, other: &Self) -> bool {
+        // The borrows of `self` are already merged, and are left untouched
+        // (see `PersistentSet`) if `other` does not add to them
         let mut changed = false;
-        for borrow in &other.borrows {
-            if self.borrows.insert(borrow.clone()) {
-                changed = true;
-            }
+        if !self.borrows.is_superset(&other.borrows) {
+            let borrows = self.borrows.clone();
+            self.borrows.extend(other.borrows.iter().cloned());
+            self.merge_equal_borrows();
+            changed = self.borrows != borrows;
         }
         for region_abstraction in &other.region_abstractions {
             if !self.region_abstractions.contains(region_abstraction) {
@@ -23,6 +36,9 @@ impl<'tcx> JoinSemiLattice for BorrowsState<'tcx> {
                 changed = true;
             }
         }
+        // No garbage is collected here: the joined states were collected
+        // after their last effect, and a union of collected states has no
+        // garbage either
         changed
     }
 }
@@ -48,6 +64,17 @@ impl<'tcx> RegionAbstraction<'tcx> {
     pub fn add_loan_out(&mut self, loan: mir::Place<'tcx>) {
         self.loans_out.insert(loan);
     }
+
+    /// Whether `place` flows into or out of the abstraction. Loans are always
+    /// current places, so old places are never involved.
+    pub fn involves_place(&self, place: &MaybeOldPlace<'tcx>) -> bool {
+        place.is_current()
+            && self
+                .loans_in
+                .iter()
+                .chain(&self.loans_out)
+                .any(|&loan| Place::from(loan) == place.place())
+    }
 }
 
 #[derive(PartialEq, Eq, Clone, Debug, Hash)]
@@ -55,10 +82,7 @@ pub enum MaybeOldPlace<'tcx> {
     Current {
         place: Place<'tcx>,
     },
-    OldPlace {
-        place: Place<'tcx>,
-        before: Location,
-    },
+    OldPlace(PlaceSnapshot<'tcx>),
 }
 
 impl<'tcx> MaybeOldPlace<'tcx> {
@@ -69,27 +93,43 @@ impl<'tcx> MaybeOldPlace<'tcx> {
     pub fn place(&self) -> Place<'tcx> {
         match self {
             MaybeOldPlace::Current { place } => *place,
-            MaybeOldPlace::OldPlace { place, .. } => *place,
+            MaybeOldPlace::OldPlace(snapshot) => snapshot.place,
         }
     }
 
-    pub fn before_location(&self) -> Option<Location> {
+    pub fn snapshot_location(&self) -> Option<SnapshotLocation> {
         match self {
             MaybeOldPlace::Current { .. } => None,
-            MaybeOldPlace::OldPlace { before, .. } => Some(*before),
+            MaybeOldPlace::OldPlace(snapshot) => Some(snapshot.location),
         }
     }
 
-    pub fn to_json(&self, repacker: PlaceRepacker<'_, 'tcx>) -> serde_json::Value {
-        let place_str = match self.place().to_string(repacker) {
-            crate::utils::display::PlaceDisplay::Temporary(p) => format!("{:?}", p),
-            crate::utils::display::PlaceDisplay::User(_, s) => s,
-        };
+    /// `place` as of the same point as `self`, e.g. `x at l` for `*x at l`.
+    pub fn with_place(&self, place: Place<'tcx>) -> Self {
+        match self {
+            MaybeOldPlace::Current { .. } => MaybeOldPlace::Current { place },
+            MaybeOldPlace::OldPlace(snapshot) => {
+                MaybeOldPlace::OldPlace(PlaceSnapshot::at(place, snapshot.location))
+            }
+        }
+    }
 
-        json!({
-            "place": place_str,
-            "before": self.before_location().map(|loc| format!("{:?}", loc)),
-        })
+    pub fn to_schema(&self, repacker: PlaceRepacker<'_, 'tcx>) -> schema::MaybeOldPlace {
+        schema::MaybeOldPlace {
+            place: self.place().to_short_string(repacker),
+            at: self.snapshot_location().map(|loc| loc.to_string()),
+        }
+    }
+}
+
+impl Display for MaybeOldPlace<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            MaybeOldPlace::Current { place } => write!(f, "{place:?}"),
+            MaybeOldPlace::OldPlace(snapshot) => {
+                write!(f, "{:?} at {}", snapshot.place, snapshot.location)
+            }
+        }
     }
 }
 
@@ -99,9 +139,42 @@ pub struct Borrow<'tcx> {
     pub borrowed_place: MaybeOldPlace<'tcx>,
     pub assigned_place: MaybeOldPlace<'tcx>,
     pub is_mut: bool,
+    /// Set for a two-phase borrow of rustc, e.g. the autoref of `v` in
+    /// `v.push(v.len())`.
+    pub two_phase: Option<TwoPhase>,
+    /// Ignored by equality and hashing.
+    pub debug_info: DebugInfo<'static>,
+}
+
+/// A two-phase borrow is reserved where it is created, but only activated
+/// (used mutably) later: in between, the borrowed place can still be read.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct TwoPhase {
+    pub reserved_at: Location,
+    /// `None` if the borrow is never used mutably.
+    pub activated_at: Option<Location>,
+}
+
+impl Display for TwoPhase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.activated_at {
+            Some(location) => write!(f, "activated at {location:?}"),
+            None => write!(f, "never activated"),
+        }
+    }
 }
 
 impl<'tcx> Borrow<'tcx> {
+    /// The rustc loans the borrow was created from, none for the borrows
+    /// introduced by the analysis (e.g. when a reference is moved).
+    pub fn loans(&self) -> impl Iterator<Item = BorrowIndex> + '_ {
+        let loans = match &self.kind {
+            BorrowKind::Rustc(loans) => Some(loans.iter()),
+            BorrowKind::PCS => None,
+        };
+        loans.into_iter().flatten()
+    }
+
     pub fn new(
         kind: BorrowKind,
         borrowed_place: Place<'tcx>,
@@ -117,6 +190,29 @@ impl<'tcx> Borrow<'tcx> {
                 place: assigned_place,
             },
             is_mut,
+            two_phase: None,
+            debug_info: DebugInfo::new_static(),
+        }
+    }
+
+    pub fn rustc(borrow: BorrowIndex, borrow_set: &BorrowSet<'tcx>) -> Self {
+        let data = &borrow_set[borrow];
+        let activated_at = match data.activation_location {
+            TwoPhaseActivation::NotTwoPhase => None,
+            TwoPhaseActivation::NotActivated => Some(None),
+            TwoPhaseActivation::ActivatedAt(location) => Some(Some(location)),
+        };
+        Self {
+            two_phase: activated_at.map(|activated_at| TwoPhase {
+                reserved_at: data.reserve_location,
+                activated_at,
+            }),
+            ..Self::new(
+                BorrowKind::Rustc(Loans::new(borrow)),
+                data.borrowed_place.into(),
+                data.assigned_place.into(),
+                matches!(data.kind, mir::BorrowKind::Mut { .. }),
+            )
         }
     }
 
@@ -124,34 +220,119 @@ impl<'tcx> Borrow<'tcx> {
         self.borrowed_place.is_current() && self.assigned_place.is_current()
     }
 
-    pub fn to_json(&self, repacker: PlaceRepacker<'_, 'tcx>) -> serde_json::Value {
-        json!({
-            "kind": format!("{:?}", self.kind),
-            "borrowed_place": self.borrowed_place.to_json(repacker),
-            "assigned_place": self.assigned_place.to_json(repacker),
-            "is_mut": self.is_mut,
-        })
+    /// Whether `place` is the borrowed or the assigned place of this borrow.
+    /// Places related to `place` by projection (e.g. `x.f` for `x`) are not
+    /// involved.
+    pub fn involves_place(&self, place: &MaybeOldPlace<'tcx>) -> bool {
+        &self.borrowed_place == place || &self.assigned_place == place
+    }
+
+    /// Whether this is a mutable (as opposed to a shared) borrow.
+    pub fn is_mutable(&self) -> bool {
+        self.is_mut
+    }
+
+    pub fn to_schema(&self, repacker: PlaceRepacker<'_, 'tcx>) -> schema::Borrow {
+        schema::Borrow {
+            kind: format!("{:?}", self.kind),
+            borrowed_place: self.borrowed_place.to_schema(repacker),
+            assigned_place: self.assigned_place.to_schema(repacker),
+            is_mut: self.is_mut,
+            two_phase: self.two_phase.map(|two_phase| two_phase.to_string()),
+            debug_info: self.debug_info.describe(),
+        }
+    }
+}
+
+impl Display for Borrow<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mutability = if self.is_mutable() { "mut " } else { "" };
+        write!(
+            f,
+            "{:?}: &{mutability}{} -> {}",
+            self.kind, self.borrowed_place, self.assigned_place
+        )
     }
 }
 
 #[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub enum BorrowKind {
-    Rustc(BorrowIndex),
+    Rustc(Loans),
     PCS,
 }
 
-#[derive(PartialEq, Eq, Clone, Debug)]
+/// The rustc loans an edge was created from: a single one, unless the same
+/// borrow was created on several paths and the edges were merged at a join
+/// (see [`BorrowsState::merge_equal_borrows`]).
+#[derive(PartialEq, Eq, Clone, Hash)]
+pub struct Loans(BTreeSet<BorrowIndex>);
+
+impl Loans {
+    pub fn new(loan: BorrowIndex) -> Self {
+        Self(BTreeSet::from([loan]))
+    }
+
+    pub fn contains(&self, loan: BorrowIndex) -> bool {
+        self.0.contains(&loan)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = BorrowIndex> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+impl Debug for Loans {
+    /// `bw0` for a single loan, `{bw0, bw1}` for merged ones.
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.0.first() {
+            Some(loan) if self.0.len() == 1 => write!(f, "{loan:?}"),
+            _ => f.debug_set().entries(&self.0).finish(),
+        }
+    }
+}
+
+#[derive(Eq, Clone, Debug)]
 pub struct BorrowsState<'tcx> {
-    pub borrows: FxHashSet<Borrow<'tcx>>,
+    /// Shared between clones of the state, see [`PersistentSet`].
+    pub borrows: PersistentSet<Borrow<'tcx>>,
+    /// Without duplicates. Their order depends on the order of the joins, and
+    /// is ignored by equality.
     pub region_abstractions: Vec<RegionAbstraction<'tcx>>,
+    /// Joined separately by [`Latest::join`], which needs to know the block.
+    pub latest: Latest<'tcx>,
+}
+
+impl PartialEq for BorrowsState<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.borrows == other.borrows
+            && self.region_abstractions.len() == other.region_abstractions.len()
+            && self
+                .region_abstractions
+                .iter()
+                .all(|abstraction| other.region_abstractions.contains(abstraction))
+            && self.latest == other.latest
+    }
 }
 
-use crate::utils::PlaceRepacker;
-use serde_json::{json, Value};
+use crate::{utils::PlaceRepacker, visualization::schema};
+use serde_json::Value;
 
-use super::engine::BorrowAction;
+use super::{engine::BorrowAction, latest::Latest};
 
 impl<'tcx> BorrowsState<'tcx> {
+    /// The borrows added and removed in `self` compared to `before`, for
+    /// tracing. Empty if nothing changed.
+    pub fn borrows_diff(&self, before: &Self) -> String {
+        let mut diff = String::new();
+        for borrow in self.borrows.difference(&before.borrows) {
+            diff.push_str(&format!("\n  + {borrow}"));
+        }
+        for borrow in before.borrows.difference(&self.borrows) {
+            diff.push_str(&format!("\n  - {borrow}"));
+        }
+        diff
+    }
+
     pub fn contains_borrow(&self, borrow: &Borrow<'tcx>) -> bool {
         self.borrows.contains(borrow)
     }
@@ -163,27 +344,325 @@ impl<'tcx> BorrowsState<'tcx> {
         };
     }
 
+    pub fn to_schema(&self, repacker: PlaceRepacker<'_, 'tcx>) -> schema::BorrowsState {
+        schema::BorrowsState {
+            borrows: self
+                .borrows
+                .iter()
+                .map(|borrow| borrow.to_schema(repacker))
+                .collect(),
+            latest: self.latest.to_schema(repacker),
+            blocked_sets: self.blocked_sets_to_schema(repacker),
+        }
+    }
+
+    fn blocked_sets_to_schema(&self, repacker: PlaceRepacker<'_, 'tcx>) -> Vec<schema::BlockedSet> {
+        let order = |place: &schema::MaybeOldPlace| (place.place.clone(), place.at.clone());
+        let mut blocked_sets: Vec<_> = self
+            .leaf_places()
+            .into_iter()
+            .map(|place| {
+                let mut blocked: Vec<_> = self
+                    .blocked_by_transitive(&place)
+                    .iter()
+                    .map(|place| place.to_schema(repacker))
+                    .collect();
+                blocked.sort_by_key(order);
+                schema::BlockedSet {
+                    place: place.to_schema(repacker),
+                    blocked,
+                }
+            })
+            .collect();
+        blocked_sets.sort_by_key(|blocked_set| order(&blocked_set.place));
+        blocked_sets
+    }
+
     pub fn to_json(&self, repacker: PlaceRepacker<'_, 'tcx>) -> Value {
-        json!({
-            "borrows": self.borrows.iter().map(|borrow| {
-                borrow.to_json(repacker)
-            }).collect::<Vec<_>>(),
-        })
+        serde_json::to_value(self.to_schema(repacker)).unwrap()
+    }
+
+    pub fn to_graph_schema(&self, repacker: PlaceRepacker<'_, 'tcx>) -> schema::BorrowsGraph {
+        fn node_id(
+            nodes: &mut Vec<schema::BorrowsGraphNode>,
+            node: schema::BorrowsGraphNode,
+        ) -> usize {
+            nodes.iter().position(|n| *n == node).unwrap_or_else(|| {
+                nodes.push(node);
+                nodes.len() - 1
+            })
+        }
+        let mut nodes = vec![];
+        let mut edges = vec![];
+        let place_id = |nodes: &mut Vec<_>, place: &MaybeOldPlace<'tcx>| {
+            node_id(
+                nodes,
+                schema::BorrowsGraphNode::Place {
+                    place: place.to_schema(repacker),
+                },
+            )
+        };
+        for borrow in &self.borrows {
+            edges.push(schema::BorrowsGraphEdge::Borrow {
+                kind: format!("{:?}", borrow.kind),
+                borrowed_place: place_id(&mut nodes, &borrow.borrowed_place),
+                assigned_place: place_id(&mut nodes, &borrow.assigned_place),
+                is_mut: borrow.is_mut,
+            });
+        }
+        for (base, expansion) in self.deref_expansions() {
+            let mut expansion_places: Vec<_> = expansion
+                .iter()
+                .map(|place| place.place().to_short_string(repacker))
+                .collect();
+            expansion_places.sort();
+            edges.push(schema::BorrowsGraphEdge::DerefExpansion {
+                base: place_id(&mut nodes, &base),
+                expansion: expansion
+                    .iter()
+                    .map(|place| place_id(&mut nodes, place))
+                    .collect(),
+                base_place: base.place().to_short_string(repacker),
+                expansion_places,
+            });
+        }
+        for (index, abstraction) in self.region_abstractions.iter().enumerate() {
+            let abstraction_id =
+                node_id(&mut nodes, schema::BorrowsGraphNode::Abstraction { index });
+            for loan_in in &abstraction.loans_in {
+                let place = MaybeOldPlace::Current {
+                    place: (*loan_in).into(),
+                };
+                edges.push(schema::BorrowsGraphEdge::AbstractionLoanIn {
+                    place: place_id(&mut nodes, &place),
+                    abstraction: abstraction_id,
+                });
+            }
+            for loan_out in &abstraction.loans_out {
+                let place = MaybeOldPlace::Current {
+                    place: (*loan_out).into(),
+                };
+                edges.push(schema::BorrowsGraphEdge::AbstractionLoanOut {
+                    abstraction: abstraction_id,
+                    place: place_id(&mut nodes, &place),
+                });
+            }
+        }
+        schema::BorrowsGraph { nodes, edges }
     }
 }
 
 impl<'tcx> BorrowsState<'tcx> {
     pub fn new() -> Self {
         Self {
-            borrows: FxHashSet::default(),
+            borrows: PersistentSet::default(),
             region_abstractions: vec![],
+            latest: Latest::bottom(),
+        }
+    }
+
+    /// The point at which the current value of `place` was written, or `None`
+    /// if it has not been written since the start of the function.
+    pub fn latest_of(&self, place: Place<'tcx>) -> Option<SnapshotLocation> {
+        self.latest.get(place)
+    }
+
+    /// The borrows satisfying `predicate`, e.g. `Borrow::is_mutable`.
+    pub fn borrows_filtered<'a>(
+        &'a self,
+        predicate: impl Fn(&Borrow<'tcx>) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a Borrow<'tcx>> + 'a {
+        self.borrows.iter().filter(move |borrow| predicate(borrow))
+    }
+
+    /// The two-phase borrows, whose reservation and activation are at
+    /// different locations (see [`TwoPhase`]).
+    pub fn two_phase_borrows(&self) -> Vec<Borrow<'tcx>> {
+        self.borrows_filtered(|borrow| borrow.two_phase.is_some())
+            .cloned()
+            .collect()
+    }
+
+    /// The expansions of old places through their dereferences, one
+    /// dereference at a time: each dereferenced base is mapped to the places
+    /// reached through it up to the next dereference. For example, when
+    /// `(*p).x` and `(*p).y` are old, `p` expands into both of them, and when
+    /// `**a` is old, `a` expands into `*a` and `*a` into `**a`. The bases are
+    /// old as of the same location, but need not appear in any borrow.
+    pub fn deref_expansions(&self) -> Vec<(MaybeOldPlace<'tcx>, Vec<MaybeOldPlace<'tcx>>)> {
+        let old_places: FxHashSet<_> = self
+            .borrows
+            .iter()
+            .flat_map(|borrow| [borrow.borrowed_place.clone(), borrow.assigned_place.clone()])
+            .filter_map(|place| match place {
+                MaybeOldPlace::OldPlace(snapshot) => Some(snapshot),
+                MaybeOldPlace::Current { .. } => None,
+            })
+            .collect();
+        let mut expansions: Vec<(MaybeOldPlace<'tcx>, Vec<MaybeOldPlace<'tcx>>)> = vec![];
+        for snapshot in &old_places {
+            let at = |place| MaybeOldPlace::OldPlace(PlaceSnapshot::at(place, snapshot.location));
+            let mut expansion = snapshot.place;
+            while let Some(index) = expansion.last_deref_index() {
+                let base = Place::new(expansion.local, &expansion.projection[..index]);
+                match expansions.iter_mut().find(|(other, _)| *other == at(base)) {
+                    Some((_, places)) => {
+                        if !places.contains(&at(expansion)) {
+                            places.push(at(expansion));
+                        }
+                    }
+                    None => expansions.push((at(base), vec![at(expansion)])),
+                }
+                expansion = base;
+            }
         }
+        expansions
+    }
+
+    /// The edge created from `loan`, preferring the current one if the
+    /// assigned or borrowed place has also been made old.
+    pub fn edge_for_loan(&self, loan: BorrowIndex) -> Option<&Borrow<'tcx>> {
+        self.borrows_filtered(move |borrow| borrow.loans().any(|other| other == loan))
+            .max_by_key(|borrow| borrow.is_current())
     }
 
     pub fn live_borrows(&self) -> impl Iterator<Item = &Borrow<'tcx>> {
-        self.borrows.iter().filter(|borrow| {
-            borrow.assigned_place.is_current() && borrow.borrowed_place.is_current()
-        })
+        self.borrows_filtered(Borrow::is_current)
+    }
+
+    /// The places that (transitively) borrow memory owned by the caller
+    /// through the argument `remote`, e.g. with `x: &mut (T, T)` an argument,
+    /// `r` for `r = &mut (*x).0` and then also `s` for `s = &mut *r`.
+    pub fn places_blocked_by_remote(
+        &self,
+        remote: Local,
+        repacker: PlaceRepacker<'_, 'tcx>,
+    ) -> FxHashSet<MaybeOldPlace<'tcx>> {
+        assert!(
+            (1..=repacker.body().arg_count).contains(&remote.as_usize()),
+            "{remote:?} is not an argument"
+        );
+        let mut blocked = FxHashSet::default();
+        let mut worklist: Vec<_> = self
+            .borrows
+            .iter()
+            .filter(|borrow| {
+                let borrowed = borrow.borrowed_place.place();
+                borrowed.local == remote && borrowed.last_deref_index().is_some()
+            })
+            .map(|borrow| borrow.assigned_place.clone())
+            .collect();
+        while let Some(place) = worklist.pop() {
+            if !blocked.insert(place.clone()) {
+                continue;
+            }
+            worklist.extend(
+                self.borrows
+                    .iter()
+                    .filter(|borrow| {
+                        borrow.borrowed_place.snapshot_location() == place.snapshot_location()
+                            && place
+                                .place()
+                                .is_strict_prefix_of(borrow.borrowed_place.place())
+                    })
+                    .map(|borrow| borrow.assigned_place.clone()),
+            );
+        }
+        blocked
+    }
+
+    /// The places that stay blocked as long as `place` is kept alive: those
+    /// borrowed by `place` (or by its fields), the references through which
+    /// these are reached, the places those borrow in turn, and so on. The
+    /// result of a call blocks the loans flowing into its region
+    /// abstractions. For example, with `s = &mut x` and `r = &mut *s`,
+    /// keeping `r` alive blocks `*s`, `s` and `x`.
+    pub fn blocked_by_transitive(
+        &self,
+        place: &MaybeOldPlace<'tcx>,
+    ) -> FxHashSet<MaybeOldPlace<'tcx>> {
+        let mut blocked = FxHashSet::default();
+        let mut worklist = vec![place.clone()];
+        while let Some(place) = worklist.pop() {
+            let mut next: Vec<_> = self
+                .borrows
+                .iter()
+                .filter(|borrow| {
+                    borrow.assigned_place.snapshot_location() == place.snapshot_location()
+                        && place.place().is_prefix(borrow.assigned_place.place())
+                })
+                .map(|borrow| borrow.borrowed_place.clone())
+                .collect();
+            if let Some(index) = place.place().last_deref_index() {
+                let reference = Place::new(place.place().local, &place.place().projection[..index]);
+                next.push(place.with_place(reference));
+            }
+            if place.is_current() {
+                for abstraction in &self.region_abstractions {
+                    if abstraction
+                        .loans_out
+                        .iter()
+                        .any(|&loan_out| Place::from(loan_out).is_prefix(place.place()))
+                    {
+                        next.extend(abstraction.loans_in.iter().map(|&loan_in| {
+                            MaybeOldPlace::Current {
+                                place: loan_in.into(),
+                            }
+                        }));
+                    }
+                }
+            }
+            // Each place is only visited once, which also ends cycles
+            for place in next {
+                if blocked.insert(place.clone()) {
+                    worklist.push(place);
+                }
+            }
+        }
+        blocked.remove(place);
+        blocked
+    }
+
+    /// The places at the ends of the chains of borrows and region
+    /// abstractions: the current places assigned a borrow, or flowed into by
+    /// an abstraction, that do not block anything in turn.
+    pub fn leaf_places(&self) -> Vec<MaybeOldPlace<'tcx>> {
+        let blocked: Vec<MaybeOldPlace<'tcx>> = self
+            .borrows
+            .iter()
+            .map(|borrow| borrow.borrowed_place.clone())
+            .chain(self.region_abstractions.iter().flat_map(|abstraction| {
+                abstraction
+                    .loans_in
+                    .iter()
+                    .map(|&loan_in| MaybeOldPlace::Current {
+                        place: loan_in.into(),
+                    })
+            }))
+            .filter(MaybeOldPlace::is_current)
+            .collect();
+        let mut leaves: Vec<MaybeOldPlace<'tcx>> = vec![];
+        let candidates = self
+            .live_borrows()
+            .map(|borrow| borrow.assigned_place.clone())
+            .chain(self.region_abstractions.iter().flat_map(|abstraction| {
+                abstraction
+                    .loans_out
+                    .iter()
+                    .map(|&loan_out| MaybeOldPlace::Current {
+                        place: loan_out.into(),
+                    })
+            }));
+        for candidate in candidates {
+            if !leaves.contains(&candidate)
+                && !blocked
+                    .iter()
+                    .any(|place| candidate.place().is_prefix(place.place()))
+            {
+                leaves.push(candidate);
+            }
+        }
+        leaves
     }
 
     pub fn reference_targeting_place(
@@ -198,6 +677,105 @@ impl<'tcx> BorrowsState<'tcx> {
             .map(|borrow| borrow.assigned_place.place())
     }
 
+    /// Whether `place` is memory of the caller reached through an argument,
+    /// e.g. `(*x).0` for `x: &mut (T, T)`. The arguments are live for the
+    /// whole body: whether an argument was reassigned is not taken into
+    /// account, since it differs between the iterations of a loop, and the
+    /// graph must only shrink as the iterations add to it.
+    fn is_live_remote(place: &MaybeOldPlace<'tcx>, arg_count: usize) -> bool {
+        place.is_current()
+            && (1..=arg_count).contains(&place.place().local.as_usize())
+            && place.place().last_deref_index().is_some()
+    }
+
+    /// Removes the parts of the graph that can no longer block anything, and
+    /// returns the number of nodes removed:
+    /// - the old places that no surviving edge borrows through, together with
+    ///   the edges assigned to them: the value they snapshot is gone, so
+    ///   nothing can use these borrows anymore, but they would otherwise stay
+    ///   until the `StorageDead` of their local. Edges borrowing a remote
+    ///   place of a live argument, or a loan flowing into a region
+    ///   abstraction, are kept.
+    /// - the region abstractions none of whose incoming loans is still
+    ///   borrowed: the loans were killed.
+    ///
+    /// Removing either can make the other collectable, so this repeats until
+    /// nothing is removed.
+    pub fn collect_garbage(&mut self, arg_count: usize) -> usize {
+        let mut collected = 0;
+        loop {
+            let removed = self.collect_old_places(arg_count) + self.collect_abstractions();
+            if removed == 0 {
+                return collected;
+            }
+            collected += removed;
+        }
+    }
+
+    fn collect_old_places(&mut self, arg_count: usize) -> usize {
+        let loans_in: FxHashSet<Place<'tcx>> = self
+            .region_abstractions
+            .iter()
+            .flat_map(|abstraction| abstraction.loans_in.iter().map(|&loan| loan.into()))
+            .collect();
+        let garbage: Vec<Borrow<'tcx>> = self
+            .borrows
+            .iter()
+            .filter(|borrow| {
+                let Some(location) = borrow.assigned_place.snapshot_location() else {
+                    return false;
+                };
+                let borrowed = &borrow.borrowed_place;
+                let flows_into_abstraction =
+                    borrowed.is_current() && loans_in.contains(&borrowed.place());
+                if Self::is_live_remote(borrowed, arg_count) || flows_into_abstraction {
+                    return false;
+                }
+                let assigned = borrow.assigned_place.place();
+                !self.borrows.iter().any(|other| {
+                    other.borrowed_place.snapshot_location() == Some(location)
+                        && assigned.is_prefix(other.borrowed_place.place())
+                })
+            })
+            .cloned()
+            .collect();
+        for borrow in &garbage {
+            self.borrows.remove(borrow);
+        }
+        let old_places: FxHashSet<_> = garbage
+            .iter()
+            .map(|borrow| &borrow.assigned_place)
+            .collect();
+        old_places
+            .into_iter()
+            .filter(|&place| {
+                !self
+                    .borrows
+                    .iter()
+                    .any(|borrow| borrow.involves_place(place))
+            })
+            .count()
+    }
+
+    fn collect_abstractions(&mut self) -> usize {
+        if self.region_abstractions.is_empty() {
+            return 0;
+        }
+        let borrowed: FxHashSet<Place<'tcx>> = self
+            .borrows
+            .iter()
+            .map(|borrow| borrow.borrowed_place.place())
+            .collect();
+        let len = self.region_abstractions.len();
+        self.region_abstractions.retain(|abstraction| {
+            abstraction
+                .loans_in
+                .iter()
+                .any(|&loan| borrowed.contains(&loan.into()))
+        });
+        len - self.region_abstractions.len()
+    }
+
     pub fn add_region_abstraction(&mut self, abstraction: RegionAbstraction<'tcx>) {
         if !self.region_abstractions.contains(&abstraction) {
             self.region_abstractions.push(abstraction);
@@ -209,16 +787,397 @@ impl<'tcx> BorrowsState<'tcx> {
     }
 
     pub fn add_rustc_borrow(&mut self, borrow: BorrowIndex, borrow_set: &BorrowSet<'tcx>) {
-        self.borrows.insert(Borrow::new(
-            BorrowKind::Rustc(borrow),
-            borrow_set[borrow].borrowed_place.into(),
-            borrow_set[borrow].assigned_place.into(),
-            matches!(borrow_set[borrow].kind, mir::BorrowKind::Mut { .. }),
-        ));
+        self.borrows.insert(Borrow::rustc(borrow, borrow_set));
     }
 
+    /// Removes `borrow` from the current edges created from it. A merged edge
+    /// (see [`Self::merge_equal_borrows`]) is only removed with its last loan.
     pub fn remove_rustc_borrow(&mut self, borrow: &BorrowIndex) {
-        self.borrows
-            .retain(|b| !b.is_current() || b.kind != BorrowKind::Rustc(*borrow));
+        let edges: Vec<_> = self
+            .borrows_filtered(|b| b.is_current() && b.loans().any(|loan| loan == *borrow))
+            .cloned()
+            .collect();
+        for mut edge in edges {
+            self.borrows.remove(&edge);
+            if let BorrowKind::Rustc(loans) = &mut edge.kind {
+                loans.0.remove(borrow);
+                if !loans.0.is_empty() {
+                    self.borrows.insert(edge);
+                }
+            }
+        }
+    }
+
+    /// Merges the edges that only differ in the loans they were created from
+    /// into a single edge holding all of them, e.g. after `r = &mut x` in both
+    /// branches of an `if`: the edges would otherwise be kept, and removed,
+    /// separately for the rest of the function. Two-phase borrows are kept
+    /// apart, since their reservations differ.
+    fn merge_equal_borrows(&mut self) {
+        let mut groups: FxHashMap<_, Vec<&Borrow<'tcx>>> = FxHashMap::default();
+        for borrow in &self.borrows {
+            if matches!(borrow.kind, BorrowKind::Rustc(_)) && borrow.two_phase.is_none() {
+                let key = (
+                    &borrow.borrowed_place,
+                    &borrow.assigned_place,
+                    borrow.is_mut,
+                );
+                groups.entry(key).or_default().push(borrow);
+            }
+        }
+        // Only the groups of several edges are replaced, so that the others
+        // stay shared with the clones of the state (see `PersistentSet`)
+        let equal: Vec<Vec<Borrow<'tcx>>> = groups
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .map(|group| group.into_iter().cloned().collect())
+            .collect();
+        for group in equal {
+            let mut merged = group[0].clone();
+            for borrow in &group {
+                self.borrows.remove(borrow);
+                if let (BorrowKind::Rustc(merged_loans), BorrowKind::Rustc(loans)) =
+                    (&mut merged.kind, &borrow.kind)
+                {
+                    merged_loans.0.extend(loans.iter());
+                }
+            }
+            self.borrows.insert(merged);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_interface::middle::mir::START_BLOCK;
+
+    use super::*;
+    use crate::{
+        combined_pcs::PcsContext,
+        test_utils::{place, with_body, BorrowsStateBuilder},
+    };
+
+    #[test]
+    fn graph_has_an_edge_of_each_kind() {
+        let source = "pub fn f<'a>(x: &'a mut &mut u32) -> &'a mut u32 { let y = &mut **x; y }";
+        let graph = with_body(source, "f", |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            let y = place(rp, "y");
+            let mut state = BorrowsStateBuilder::new()
+                .abstraction(&[y.local.as_usize()], 0)
+                .build();
+            let mut borrow = Borrow::new(BorrowKind::PCS, place(rp, "**x"), y, true);
+            borrow.borrowed_place = MaybeOldPlace::OldPlace(PlaceSnapshot::at(
+                place(rp, "**x"),
+                SnapshotLocation::Join(START_BLOCK),
+            ));
+            state.add_borrow(borrow);
+            state.to_graph_schema(rp)
+        });
+
+        let node = |id: usize| &graph.nodes[id];
+        let place_node = |id: usize| match node(id) {
+            schema::BorrowsGraphNode::Place { place } => place.clone(),
+            node => panic!("{node:?} is not a place"),
+        };
+        let mut kinds = vec![];
+        for edge in &graph.edges {
+            match edge {
+                schema::BorrowsGraphEdge::Borrow {
+                    borrowed_place,
+                    assigned_place,
+                    ..
+                } => {
+                    kinds.push("borrow");
+                    assert_eq!(place_node(*borrowed_place).place, "**x");
+                    assert!(place_node(*borrowed_place).at.is_some());
+                    assert_eq!(place_node(*assigned_place).place, "y");
+                }
+                schema::BorrowsGraphEdge::DerefExpansion {
+                    base,
+                    expansion,
+                    base_place,
+                    expansion_places,
+                } => {
+                    kinds.push("deref expansion");
+                    assert_eq!(place_node(*base).place, *base_place);
+                    let expected = match base_place.as_str() {
+                        "x" => "*x",
+                        "*x" => "**x",
+                        base => panic!("Unexpected expansion of {base}"),
+                    };
+                    assert_eq!(*expansion_places, [expected]);
+                    assert_eq!(place_node(expansion[0]).place, expected);
+                }
+                schema::BorrowsGraphEdge::AbstractionLoanIn { place, abstraction } => {
+                    kinds.push("loan in");
+                    assert_eq!(place_node(*place).place, "y");
+                    assert!(matches!(
+                        node(*abstraction),
+                        schema::BorrowsGraphNode::Abstraction { index: 0 }
+                    ));
+                }
+                schema::BorrowsGraphEdge::AbstractionLoanOut { abstraction, place } => {
+                    kinds.push("loan out");
+                    assert_eq!(place_node(*place).place, "RETURN");
+                    assert!(matches!(
+                        node(*abstraction),
+                        schema::BorrowsGraphNode::Abstraction { index: 0 }
+                    ));
+                }
+            }
+        }
+        kinds.sort();
+        let expected = [
+            "borrow",
+            "deref expansion",
+            "deref expansion",
+            "loan in",
+            "loan out",
+        ];
+        assert_eq!(kinds, expected);
+    }
+
+    #[test]
+    fn join_is_union_of_borrows() {
+        let mut state = BorrowsStateBuilder::new().borrow(1, 2, true).build();
+        let other = BorrowsStateBuilder::new()
+            .borrow(1, 2, true)
+            .borrow(2, 3, false)
+            .build();
+        assert!(state.join(&other));
+        assert_eq!(state.borrows, other.borrows);
+        assert!(!state.join(&other));
+    }
+
+    /// Joins `states` into the first one in order, as at the entry of `bb1`.
+    fn join_all(states: &[&BorrowsState<'static>]) -> BorrowsState<'static> {
+        let mut joined = states[0].clone();
+        for state in &states[1..] {
+            joined.join(state);
+            joined
+                .latest
+                .join(&state.latest, mir::BasicBlock::from_usize(1));
+        }
+        joined
+    }
+
+    #[test]
+    fn join_is_order_independent() {
+        let before = |statement_index| {
+            SnapshotLocation::Before(Location {
+                block: mir::START_BLOCK,
+                statement_index,
+            })
+        };
+        let a = BorrowsStateBuilder::new()
+            .borrow(1, 2, true)
+            .abstraction(&[1], 4)
+            .written(1, before(0))
+            .build();
+        let b = BorrowsStateBuilder::new()
+            .loan(0, 1, 2, true)
+            .borrow(3, 5, false)
+            .abstraction(&[3], 6)
+            .written(1, before(1))
+            .written(3, before(1))
+            .build();
+        let c = BorrowsStateBuilder::new()
+            .loan(1, 1, 2, true)
+            .borrow_into_old(1, 7, before(2), true)
+            .abstraction(&[1], 4)
+            .written(3, before(2))
+            .build();
+        let joined = join_all(&[&a, &b, &c]);
+        for order in [
+            [&a, &c, &b],
+            [&b, &a, &c],
+            [&b, &c, &a],
+            [&c, &a, &b],
+            [&c, &b, &a],
+        ] {
+            assert_eq!(join_all(&order), joined);
+        }
+        assert_eq!(join_all(&[&a, &join_all(&[&b, &c])]), joined);
+        assert_eq!(join_all(&[&joined, &a]), joined);
+    }
+
+    #[test]
+    fn blocked_through_abstraction() {
+        // `_5 = f(&mut _1, &mut _3)`, then `_6 = &mut _5`
+        let state = BorrowsStateBuilder::new()
+            .borrow(1, 2, true)
+            .borrow(3, 4, true)
+            .abstraction(&[1, 3], 5)
+            .borrow(5, 6, true)
+            .build();
+        let place = |local: usize| MaybeOldPlace::Current {
+            place: Local::from_usize(local).into(),
+        };
+        assert!(state.leaf_places().contains(&place(6)));
+        assert!(!state.leaf_places().contains(&place(5)));
+        assert_eq!(
+            state.blocked_by_transitive(&place(6)),
+            [place(5), place(1), place(3)].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn live_borrows_exclude_old_places() {
+        let location = SnapshotLocation::Before(mir::START_BLOCK.start_location());
+        let state = BorrowsStateBuilder::new()
+            .borrow(1, 2, true)
+            .borrow_into_old(3, 4, location, true)
+            .build();
+        let live: Vec<_> = state.live_borrows().collect();
+        assert_eq!(live.len(), 1);
+        let assigned: mir::Place<'_> = Local::from_usize(2).into();
+        assert_eq!(live[0].assigned_place.place(), assigned.into());
+    }
+
+    #[test]
+    fn garbage_is_abstractions_without_borrowed_loans() {
+        // `_1` is still borrowed, the loan of `_3` was killed
+        let mut state = BorrowsStateBuilder::new()
+            .borrow(1, 2, true)
+            .abstraction(&[1], 4)
+            .abstraction(&[3], 5)
+            .abstraction(&[1, 3], 6)
+            .build();
+        assert_eq!(state.collect_garbage(0), 1);
+        assert_eq!(state.region_abstractions.len(), 2);
+        assert_eq!(state.collect_garbage(0), 0);
+    }
+
+    /// A borrow of `borrowed` assigned to `assigned`, either of which may be
+    /// old.
+    fn borrow<'tcx>(borrowed: MaybeOldPlace<'tcx>, assigned: MaybeOldPlace<'tcx>) -> Borrow<'tcx> {
+        let mut borrow = Borrow::new(BorrowKind::PCS, borrowed.place(), assigned.place(), true);
+        borrow.borrowed_place = borrowed;
+        borrow.assigned_place = assigned;
+        borrow
+    }
+
+    #[test]
+    fn garbage_is_old_places_nothing_borrows_through() {
+        let source = "
+            pub fn f(x: &mut u32, mut y: u32) {
+                let r = &mut y;
+                let s = &mut *r;
+                *s = 0;
+                let t = &mut *x;
+                *t = 0;
+            }
+        ";
+        with_body(source, "f", |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            let at = |name: &str, statement_index: usize| {
+                MaybeOldPlace::OldPlace(PlaceSnapshot::at(
+                    place(rp, name),
+                    SnapshotLocation::Before(Location {
+                        block: START_BLOCK,
+                        statement_index,
+                    }),
+                ))
+            };
+            let current = |name: &str| MaybeOldPlace::Current {
+                place: place(rp, name),
+            };
+            let mut state = BorrowsState::new();
+            // `y -> r` and `*r -> s` as of before `r` and `s` were overwritten
+            state.add_borrow(borrow(current("y"), at("r", 1)));
+            state.add_borrow(borrow(at("*r", 1), at("s", 2)));
+            // `*x -> t` borrows the memory of the caller
+            state.add_borrow(borrow(current("*x"), at("t", 3)));
+            let arg_count = rp.body().arg_count;
+
+            // `s` is collected first, then `r` which nothing borrows through
+            assert_eq!(state.collect_garbage(arg_count), 2);
+            assert_eq!(state.borrows.len(), 1);
+            assert_eq!(state.collect_garbage(arg_count), 0);
+            // Without `x` being an argument, nothing keeps `t`
+            assert_eq!(state.collect_garbage(0), 1);
+            assert!(state.borrows.is_empty());
+        });
+    }
+
+    #[test]
+    fn edge_for_loan_is_created_from_the_loan() {
+        let state = BorrowsStateBuilder::new()
+            .loan(0, 1, 2, true)
+            .borrow(3, 4, true)
+            .build();
+        let edge = state.edge_for_loan(BorrowIndex::from_usize(0)).unwrap();
+        let assigned: mir::Place<'_> = Local::from_usize(2).into();
+        assert_eq!(edge.assigned_place.place(), assigned.into());
+        assert!(state.edge_for_loan(BorrowIndex::from_usize(1)).is_none());
+    }
+
+    #[test]
+    fn join_merges_borrows_of_different_loans() {
+        // `_2 = &mut _1` with loan 0 in one branch and loan 1 in the other
+        let mut state = BorrowsStateBuilder::new().loan(0, 1, 2, true).build();
+        let other = BorrowsStateBuilder::new().loan(1, 1, 2, true).build();
+        assert!(state.join(&other));
+        assert_eq!(state.borrows.len(), 1);
+        let edge = state.edge_for_loan(BorrowIndex::from_usize(0)).unwrap();
+        assert_eq!(edge.loans().count(), 2);
+        assert!(!state.join(&other));
+
+        // The edge is only removed with its last loan
+        state.remove_rustc_borrow(&BorrowIndex::from_usize(0));
+        assert!(state.edge_for_loan(BorrowIndex::from_usize(0)).is_none());
+        assert!(state.edge_for_loan(BorrowIndex::from_usize(1)).is_some());
+        state.remove_rustc_borrow(&BorrowIndex::from_usize(1));
+        assert!(state.borrows.is_empty());
+    }
+
+    #[test]
+    fn borrows_filtered_by_mutability() {
+        // `let r = &x; let s = &mut y;`
+        let state = BorrowsStateBuilder::new()
+            .borrow(1, 3, false)
+            .borrow(2, 4, true)
+            .build();
+        let borrowed = |mutable: bool| {
+            state
+                .borrows_filtered(|borrow| borrow.is_mutable() == mutable)
+                .map(|borrow| borrow.borrowed_place.place().local.as_usize())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(borrowed(true), [2]);
+        assert_eq!(borrowed(false), [1]);
+        assert_eq!(state.borrows_filtered(|_| true).count(), 2);
+    }
+
+    #[test]
+    fn places_involved_in_each_kind() {
+        // `_2 = &mut _1`, then `_4 = f(move _3)` for `_3 = &mut _1`
+        let state = BorrowsStateBuilder::new()
+            .borrow(1, 2, true)
+            .abstraction(&[3], 4)
+            .build();
+        let current = |local: usize| MaybeOldPlace::Current {
+            place: Local::from_usize(local).into(),
+        };
+        let old = |local: usize| {
+            MaybeOldPlace::OldPlace(PlaceSnapshot::at(
+                Local::from_usize(local).into(),
+                SnapshotLocation::Before(mir::START_BLOCK.start_location()),
+            ))
+        };
+        let borrow = state.borrows.iter().next().unwrap();
+        let involved_in_borrow: Vec<_> = (1..=4)
+            .filter(|&local| borrow.involves_place(&current(local)))
+            .collect();
+        assert_eq!(involved_in_borrow, [1, 2]);
+        assert!(!borrow.involves_place(&old(1)));
+
+        let abstraction = &state.region_abstractions[0];
+        let involved_in_abstraction: Vec<_> = (1..=4)
+            .filter(|&local| abstraction.involves_place(&current(local)))
+            .collect();
+        assert_eq!(involved_in_abstraction, [3, 4]);
+        assert!(!abstraction.involves_place(&old(3)));
     }
 }
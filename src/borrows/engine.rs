@@ -12,31 +12,42 @@ use rustc_interface::{
     index::IndexVec,
     middle::{
         mir::{
+            self,
             visit::{TyContext, Visitor},VarDebugInfo,
-            BasicBlock, Body, CallReturnPlaces, HasLocalDecls, Local, Location, Operand, Place,
+            BasicBlock, Body, CallReturnPlaces, FakeReadCause, HasLocalDecls, Local, Location, Operand, Place,
             ProjectionElem, Promoted, Rvalue, Statement, StatementKind, Terminator,
             TerminatorEdges, TerminatorKind, RETURN_PLACE, START_BLOCK,
         },
         ty::{self, Region, RegionKind, RegionVid, TyCtxt, TypeVisitor},
     },
 };
-use serde_json::{json, Value};
+use serde_json::Value;
 
 use crate::{
+    add_debug_note, pcs_trace,
     borrows::domain::RegionAbstraction,
-    rustc_interface,
-    utils::{self, PlaceRepacker},
+    rustc_interface, unsupported,
+    utils::{self, PlaceRepacker, PlaceSnapshot, SnapshotLocation},
+    visualization::schema,
 };
 
-use super::domain::{Borrow, BorrowKind, BorrowsState, MaybeOldPlace};
+use super::{
+    domain::{Borrow, BorrowKind, BorrowsState, MaybeOldPlace},
+    known_calls::{KnownCallEffect, KnownCalls},
+};
 
 pub struct BorrowsEngine<'mir, 'tcx> {
     tcx: TyCtxt<'tcx>,
     body: &'mir Body<'tcx>,
-    location_table: &'mir LocationTable,
-    input_facts: &'mir PoloniusInput,
+    /// The loans invalidated at each point, by the location of the point and
+    /// whether it is the start (or the mid) point of the location. Indexed
+    /// once, since scanning the facts at each effect is quadratic.
+    loans_invalidated_at: FxHashMap<(Location, bool), Vec<BorrowIndex>>,
+    /// The loan issued at each point, indexed like `loans_invalidated_at`.
+    loans_issued_at: FxHashMap<(Location, bool), BorrowIndex>,
     borrow_set: Rc<BorrowSet<'tcx>>,
     region_inference_context: Rc<RegionInferenceContext<'tcx>>,
+    known_calls: KnownCalls,
 }
 impl<'mir, 'tcx> BorrowsEngine<'mir, 'tcx> {
     pub fn new(
@@ -47,32 +58,68 @@ impl<'mir, 'tcx> BorrowsEngine<'mir, 'tcx> {
         borrow_set: Rc<BorrowSet<'tcx>>,
         region_inference_context: Rc<RegionInferenceContext<'tcx>>,
     ) -> Self {
+        let point = |point| match location_table.to_location(point) {
+            RichLocation::Start(location) => (location, true),
+            RichLocation::Mid(location) => (location, false),
+        };
+        let mut loans_invalidated_at: FxHashMap<_, Vec<_>> = FxHashMap::default();
+        for &(loan_point, loan) in &input_facts.loan_invalidated_at {
+            loans_invalidated_at
+                .entry(point(loan_point))
+                .or_default()
+                .push(loan);
+        }
+        let mut loans_issued_at = FxHashMap::default();
+        for &(_, loan, loan_point) in &input_facts.loan_issued_at {
+            loans_issued_at.entry(point(loan_point)).or_insert(loan);
+        }
         BorrowsEngine {
             tcx,
             body,
-            location_table,
-            input_facts,
+            loans_invalidated_at,
+            loans_issued_at,
             borrow_set,
             region_inference_context,
+            known_calls: KnownCalls::default(),
         }
     }
 
+    pub fn set_known_calls(&mut self, known_calls: KnownCalls) {
+        self.known_calls = known_calls;
+    }
+
+    /// Whether `arg` is a `&mut` argument of a call to `func` that the
+    /// callee only writes through, see
+    /// [`KnownCallEffect::WritesThroughMutRefs`].
+    fn is_written_through(&self, func: &Operand<'tcx>, arg: &Operand<'tcx>) -> bool {
+        self.known_calls.effect_of(func, self.tcx) == Some(KnownCallEffect::WritesThroughMutRefs)
+            && arg.ty(self.body.local_decls(), self.tcx).ref_mutability()
+                == Some(mir::Mutability::Mut)
+    }
+
     fn tag_deref_of_place_with_location(
         &self,
         state: &mut BorrowsState<'tcx>,
         place: utils::Place<'tcx>,
         location: Location,
     ) {
+        if !state
+            .borrows
+            .iter()
+            .any(|borrow| borrow.borrowed_place.place().is_through_deref_of(place))
+        {
+            return;
+        }
         state.borrows = state
             .borrows
-            .clone()
-            .into_iter()
+            .iter()
+            .cloned()
             .map(|mut borrow| {
-                if borrow.borrowed_place.place().is_deref_of(place) {
-                    borrow.borrowed_place = MaybeOldPlace::OldPlace {
-                        place: borrow.borrowed_place.place(),
-                        before: location,
-                    };
+                if borrow.borrowed_place.place().is_through_deref_of(place) {
+                    borrow.borrowed_place = MaybeOldPlace::OldPlace(PlaceSnapshot::at(
+                        borrow.borrowed_place.place(),
+                        SnapshotLocation::Before(location),
+                    ));
                 }
                 borrow
             })
@@ -85,16 +132,30 @@ impl<'mir, 'tcx> BorrowsEngine<'mir, 'tcx> {
         impl<'tcx> ty::TypeVisitor<ty::TyCtxt<'tcx>> for RegionVisitor {
             fn visit_region(&mut self, region: Region<'tcx>) -> ControlFlow<Self::BreakTy> {
                 match region.kind() {
-                    RegionKind::ReEarlyBound(_) => todo!(),
-                    RegionKind::ReLateBound(_, _) => todo!(),
-                    RegionKind::ReFree(_) => todo!(),
-                    RegionKind::ReStatic => todo!(),
+                    RegionKind::ReEarlyBound(_) => {
+                        unsupported!("RegionKind::ReEarlyBound");
+                    }
+                    RegionKind::ReLateBound(_, _) => {
+                        unsupported!("RegionKind::ReLateBound");
+                    }
+                    RegionKind::ReFree(_) => {
+                        unsupported!("RegionKind::ReFree");
+                    }
+                    RegionKind::ReStatic => {
+                        unsupported!("RegionKind::ReStatic");
+                    }
                     RegionKind::ReVar(vid) => {
                         self.0.insert(vid);
                     }
-                    RegionKind::RePlaceholder(_) => todo!(),
-                    RegionKind::ReErased => todo!(),
-                    RegionKind::ReError(_) => todo!(),
+                    RegionKind::RePlaceholder(_) => {
+                        unsupported!("RegionKind::RePlaceholder");
+                    }
+                    RegionKind::ReErased => {
+                        unsupported!("RegionKind::ReErased");
+                    }
+                    RegionKind::ReError(_) => {
+                        unsupported!("RegionKind::ReError");
+                    }
                 }
                 ControlFlow::Continue(())
             }
@@ -104,31 +165,14 @@ impl<'mir, 'tcx> BorrowsEngine<'mir, 'tcx> {
         visitor.0
     }
 
-    fn loans_invalidated_at(&self, location: Location, start: bool) -> Vec<BorrowIndex> {
-        self.input_facts
-            .loan_invalidated_at
-            .iter()
-            .filter_map(
-                |(loan_point, loan)| match self.location_table.to_location(*loan_point) {
-                    RichLocation::Start(loan_location) if loan_location == location && start => Some(*loan),
-                    RichLocation::Mid(loan_location) if loan_location == location && !start => Some(*loan),
-                    _ => None,
-                },
-            )
-            .collect()
+    fn loans_invalidated_at(&self, location: Location, start: bool) -> &[BorrowIndex] {
+        self.loans_invalidated_at
+            .get(&(location, start))
+            .map_or(&[], Vec::as_slice)
     }
 
     fn loan_issued_at_location(&self, location: Location, start: bool) -> Option<BorrowIndex> {
-        self.input_facts
-            .loan_issued_at
-            .iter()
-            .find_map(
-                |(_, loan, loan_point)| match self.location_table.to_location(*loan_point) {
-                    RichLocation::Start(loan_location) if loan_location == location && start => Some(*loan),
-                    RichLocation::Mid(loan_location) if loan_location == location && !start => Some(*loan),
-                    _ => None,
-                },
-            )
+        self.loans_issued_at.get(&(location, start)).copied()
     }
 
     fn placed_loaned_to_place(&self, place: Place<'tcx>) -> Vec<Place<'tcx>> {
@@ -145,28 +189,107 @@ impl<'mir, 'tcx> BorrowsEngine<'mir, 'tcx> {
         state: &mut BorrowsState<'tcx>,
         assigned_to: Place<'tcx>,
     ) -> FxHashSet<Borrow<'tcx>> {
-        let (to_remove, to_keep): (FxHashSet<_>, FxHashSet<_>) = state
+        let assigned_to =
+            |borrow: &Borrow<'tcx>| borrow.assigned_place.place() == assigned_to.into();
+        let to_remove: FxHashSet<_> = state
             .borrows
-            .clone()
-            .into_iter()
-            .partition(|borrow| borrow.assigned_place.place() == assigned_to.into());
+            .iter()
+            .filter(|borrow| assigned_to(borrow))
+            .cloned()
+            .collect();
+        if !to_remove.is_empty() {
+            state.borrows.retain(|borrow| !assigned_to(borrow));
+        }
+        to_remove
+    }
 
-        state.borrows = to_keep;
+    /// Makes the borrows held by the reference `operand` held by `element`,
+    /// the place of an aggregate the reference is stored into. The borrows
+    /// keep their loans, which live as long as the aggregate: the borrow is
+    /// removed when the loan is killed, e.g. when the borrowed place is used
+    /// again. A copied reference holds the borrows as well.
+    fn store_loans(
+        &self,
+        state: &mut BorrowsState<'tcx>,
+        operand: &Operand<'tcx>,
+        element: Place<'tcx>,
+        location: Location,
+    ) {
+        let (from, copy) = match operand {
+            Operand::Move(from) => (*from, false),
+            Operand::Copy(from) => (*from, true),
+            Operand::Constant(_) => return,
+        };
+        let stored = if copy {
+            state
+                .borrows
+                .iter()
+                .filter(|borrow| borrow.assigned_place.place() == from.into())
+                .cloned()
+                .collect()
+        } else {
+            self.remove_loans_assigned_to(state, from)
+        };
+        for mut borrow in stored {
+            borrow.assigned_place = MaybeOldPlace::Current {
+                place: element.into(),
+            };
+            add_debug_note!(borrow.debug_info, "stored from {from:?} at {location:?}");
+            state.add_borrow(borrow);
+        }
+    }
 
-        to_remove
+    /// Whether `borrow` is a shallow borrow of a match scrutinee, which only
+    /// lives while a guard is evaluated.
+    fn is_shallow(&self, borrow: &Borrow<'tcx>) -> bool {
+        matches!(
+            &borrow.kind,
+            BorrowKind::Rustc(loans)
+                if loans.iter().all(|loan| self.borrow_set[loan].kind == mir::BorrowKind::Shallow)
+        )
     }
 
+    /// Whether `sup` (transitively) outlives `sub`. The constraints may
+    /// contain cycles, e.g. `'a: 'b` and `'b: 'a` for invariant regions, so
+    /// each region is only visited once.
     fn outlives_or_eq(&self, sup: RegionVid, sub: RegionVid) -> bool {
-        if sup == sub {
-            true
-        } else {
-            self.region_inference_context
-                .outlives_constraints()
-                .any(|constraint| {
-                    sup == constraint.sup
-                        && (sub == constraint.sub || self.outlives_or_eq(constraint.sub, sub))
-                })
+        let mut visited = FxHashSet::default();
+        let mut worklist = vec![sup];
+        while let Some(region) = worklist.pop() {
+            if region == sub {
+                return true;
+            }
+            if !visited.insert(region) {
+                continue;
+            }
+            worklist.extend(
+                self.region_inference_context
+                    .outlives_constraints()
+                    .filter(|constraint| constraint.sup == region)
+                    .map(|constraint| constraint.sub),
+            );
+        }
+        false
+    }
+
+    /// Groups `regions` into the strongly connected components of the
+    /// outlives relation, i.e. the sets of regions that all outlive each
+    /// other, such as the regions of `&'a mut Node<'a>`. The components and
+    /// their members are ordered by region index.
+    fn region_sccs(&self, regions: impl IntoIterator<Item = RegionVid>) -> Vec<Vec<RegionVid>> {
+        let mut regions: Vec<_> = regions.into_iter().collect();
+        regions.sort();
+        let mut sccs: Vec<Vec<RegionVid>> = vec![];
+        for region in regions {
+            let scc = sccs.iter_mut().find(|scc| {
+                self.outlives_or_eq(scc[0], region) && self.outlives_or_eq(region, scc[0])
+            });
+            match scc {
+                Some(scc) => scc.push(region),
+                None => sccs.push(vec![region]),
+            }
         }
+        sccs
     }
 }
 
@@ -176,16 +299,37 @@ pub struct BorrowsDomain<'tcx> {
     before_after: BorrowsState<'tcx>,
     start: BorrowsState<'tcx>,
     pub after: BorrowsState<'tcx>,
+    /// The number of nodes collected as garbage by the effects at the
+    /// location of `after`.
+    collected: usize,
 }
 
 impl<'tcx> BorrowsDomain<'tcx> {
+    fn trace_before_effect(&self, location: Location, what: &impl std::fmt::Debug) {
+        pcs_trace!(
+            "borrows before {location:?} ({what:?}):{}",
+            self.before_after.borrows_diff(&self.before_start)
+        );
+    }
+
+    fn trace_effect(&self, location: Location, what: &impl std::fmt::Debug) {
+        pcs_trace!(
+            "borrows at {location:?} ({what:?}):{}",
+            self.after.borrows_diff(&self.start)
+        );
+    }
+
+    pub fn to_schema(&self, repacker: PlaceRepacker<'_, 'tcx>) -> schema::BorrowsDomain {
+        schema::BorrowsDomain {
+            before_start: self.before_start.to_schema(repacker),
+            before_after: self.before_after.to_schema(repacker),
+            start: self.start.to_schema(repacker),
+            after: self.after.to_schema(repacker),
+        }
+    }
+
     pub fn to_json(&self, repacker: PlaceRepacker<'_, 'tcx>) -> Value {
-        json!({
-            "before_start": self.before_start.to_json(repacker),
-            "before_after": self.before_after.to_json(repacker),
-            "start": self.start.to_json(repacker),
-            "after": self.after.to_json(repacker),
-        })
+        serde_json::to_value(self.to_schema(repacker)).unwrap()
     }
 
     pub fn new() -> Self {
@@ -194,30 +338,57 @@ impl<'tcx> BorrowsDomain<'tcx> {
             before_after: BorrowsState::new(),
             start: BorrowsState::new(),
             after: BorrowsState::new(),
+            collected: 0,
         }
     }
 
+    /// The old places in the state after the statement that were not in the
+    /// state before it.
+    pub fn new_old_places(&self) -> impl Iterator<Item = &MaybeOldPlace<'tcx>> {
+        fn old_places<'a, 'tcx>(
+            state: &'a BorrowsState<'tcx>,
+        ) -> FxHashSet<&'a MaybeOldPlace<'tcx>> {
+            state
+                .borrows
+                .iter()
+                .flat_map(|borrow| [&borrow.borrowed_place, &borrow.assigned_place])
+                .filter(|place| !place.is_current())
+                .collect()
+        }
+        let before = old_places(&self.before_start);
+        old_places(&self.after)
+            .into_iter()
+            .filter(move |place| !before.contains(place))
+    }
+
     fn apply_to_end_state(&mut self, action: BorrowAction<'_, 'tcx>) {
         self.after.apply_action(action)
     }
 
+    /// The number of nodes collected as garbage at this location, see
+    /// [`BorrowsState::collect_garbage`].
+    pub fn collected_garbage(&self) -> usize {
+        self.collected
+    }
+
     pub fn actions<'a>(&'a self, start: bool) -> Vec<BorrowAction<'a, 'tcx>> {
         let (s, e) = if start {
             (&self.before_start, &self.start)
         } else {
             (&self.before_after, &self.after)
         };
-        let mut actions = vec![];
-        for borrow in s.borrows.iter() {
-            if !e.contains_borrow(borrow) {
-                actions.push(BorrowAction::RemoveBorrow(borrow));
-            }
-        }
-        for borrow in e.borrows.iter() {
-            if !s.contains_borrow(borrow) {
-                actions.push(BorrowAction::AddBorrow(Cow::Borrowed(borrow)));
-            }
-        }
+        // Most effects leave the borrows untouched, in which case they are
+        // still shared and the differences are empty (see `PersistentSet`)
+        let mut actions: Vec<_> = s
+            .borrows
+            .difference(&e.borrows)
+            .map(BorrowAction::RemoveBorrow)
+            .collect();
+        actions.extend(
+            e.borrows
+                .difference(&s.borrows)
+                .map(|borrow| BorrowAction::AddBorrow(Cow::Borrowed(borrow))),
+        );
         actions
     }
 }
@@ -229,19 +400,21 @@ pub enum BorrowAction<'state, 'tcx> {
 }
 
 impl <'state, 'tcx> BorrowAction<'state, 'tcx> {
-    pub fn to_json(&self, repacker: PlaceRepacker<'_, 'tcx>) -> serde_json::Value {
+    pub fn to_schema(&self, repacker: PlaceRepacker<'_, 'tcx>) -> schema::BorrowAction {
         match self {
-            BorrowAction::AddBorrow(borrow) => json!({
-                "action": "AddBorrow",
-                "borrow": borrow.to_json(repacker)
-            }),
-            BorrowAction::RemoveBorrow(borrow) => json!({
-                "action": "RemoveBorrow",
-                "borrow": borrow.to_json(repacker)
-            }),
+            BorrowAction::AddBorrow(borrow) => schema::BorrowAction::AddBorrow {
+                borrow: borrow.to_schema(repacker),
+            },
+            BorrowAction::RemoveBorrow(borrow) => schema::BorrowAction::RemoveBorrow {
+                borrow: borrow.to_schema(repacker),
+            },
         }
     }
 
+    pub fn to_json(&self, repacker: PlaceRepacker<'_, 'tcx>) -> serde_json::Value {
+        serde_json::to_value(self.to_schema(repacker)).unwrap()
+    }
+
 }
 
 impl<'tcx> JoinSemiLattice for BorrowsDomain<'tcx> {
@@ -280,12 +453,16 @@ impl<'tcx, 'a> Analysis<'tcx> for BorrowsEngine<'a, 'tcx> {
     ) {
         state.before_start = state.after.clone();
         for loan in self.loans_invalidated_at(location, true) {
-            state.after.remove_rustc_borrow(&loan);
+            state.after.remove_rustc_borrow(loan);
         }
         if let Some(loan) = self.loan_issued_at_location(location, true) {
-            state.after.add_rustc_borrow(loan, &self.borrow_set);
+            let borrow = Borrow::rustc(loan, &self.borrow_set);
+            add_debug_note!(borrow.debug_info, "issued at {location:?}: {statement:?}");
+            state.after.add_borrow(borrow);
         }
+        state.collected = state.after.collect_garbage(self.body.arg_count);
         state.before_after = state.after.clone();
+        state.trace_before_effect(location, statement);
     }
 
     fn apply_statement_effect(
@@ -296,19 +473,21 @@ impl<'tcx, 'a> Analysis<'tcx> for BorrowsEngine<'a, 'tcx> {
     ) {
         state.start = state.after.clone();
         for loan in self.loans_invalidated_at(location, false) {
-            state.after.remove_rustc_borrow(&loan);
+            state.after.remove_rustc_borrow(loan);
         }
         if let Some(loan) = self.loan_issued_at_location(location, false) {
-            state.after.add_rustc_borrow(loan, &self.borrow_set);
+            let borrow = Borrow::rustc(loan, &self.borrow_set);
+            add_debug_note!(borrow.debug_info, "issued at {location:?}: {statement:?}");
+            state.after.add_borrow(borrow);
         }
         match &statement.kind {
             StatementKind::Assign(box (target, rvalue)) => match rvalue {
                 Rvalue::Use(Operand::Move(from)) => {
                     for mut borrow in self.remove_loans_assigned_to(&mut state.after, *target) {
-                        borrow.assigned_place = MaybeOldPlace::OldPlace {
-                            place: (*target).into(),
-                            before: location,
-                        };
+                        borrow.assigned_place = MaybeOldPlace::OldPlace(PlaceSnapshot::at(
+                            (*target).into(),
+                            SnapshotLocation::Before(location),
+                        ));
                         state.after.add_borrow(borrow);
                         // state.log_action(format!(
                         //     "Removed loan assigned to {:?} due to move {:?} -> {:?}:  {:?}",
@@ -317,32 +496,75 @@ impl<'tcx, 'a> Analysis<'tcx> for BorrowsEngine<'a, 'tcx> {
                     }
                     let loans_to_move = self.remove_loans_assigned_to(&mut state.after, *from);
                     for loan in loans_to_move {
-                        state.after.add_borrow(Borrow::new(
+                        let borrow = Borrow::new(
                             BorrowKind::PCS,
                             loan.borrowed_place.place(),
                             (*target).into(),
-                            loan.is_mut
-                        ));
+                            loan.is_mut,
+                        );
+                        add_debug_note!(
+                            borrow.debug_info,
+                            "moved from {from:?} at {location:?}: {statement:?}"
+                        );
+                        state.after.add_borrow(borrow);
                     }
                     self.tag_deref_of_place_with_location(
                         &mut state.after,
                         (*target).into(),
                         location,
                     );
+                    state
+                        .after
+                        .latest
+                        .insert((*target).into(), SnapshotLocation::After(location));
+                }
+                Rvalue::Aggregate(box mir::AggregateKind::Array(_), operands) => {
+                    let min_length = operands.len() as u64;
+                    for (offset, operand) in operands.iter().enumerate() {
+                        let element = target.project_deeper(
+                            &[ProjectionElem::ConstantIndex {
+                                offset: offset as u64,
+                                min_length,
+                                from_end: false,
+                            }],
+                            self.tcx,
+                        );
+                        self.store_loans(&mut state.after, operand, element, location);
+                    }
+                    state
+                        .after
+                        .latest
+                        .insert((*target).into(), SnapshotLocation::After(location));
+                }
+                Rvalue::Repeat(operand, _) => {
+                    self.store_loans(&mut state.after, operand, *target, location);
+                    state
+                        .after
+                        .latest
+                        .insert((*target).into(), SnapshotLocation::After(location));
+                }
+                _ => {
+                    state
+                        .after
+                        .latest
+                        .insert((*target).into(), SnapshotLocation::After(location));
                 }
-                _ => {}
             },
+            // The guard succeeded: the arm is entered and the scrutinee may
+            // be used freely again
+            StatementKind::FakeRead(box (FakeReadCause::ForMatchGuard, place)) => {
+                self.remove_loans_assigned_to(&mut state.after, *place);
+            }
             StatementKind::StorageDead(local) => {
-                state.after.borrows.retain(|borrow| {
-                    if borrow.assigned_place.place().local == *local {
-                        false
-                    } else {
-                        true
-                    }
-                });
+                state
+                    .after
+                    .borrows
+                    .retain(|borrow| borrow.assigned_place.place().local != *local);
             }
             _ => {}
         }
+        state.collected += state.after.collect_garbage(self.body.arg_count);
+        state.trace_effect(location, statement);
     }
 
     fn apply_before_terminator_effect(
@@ -361,18 +583,31 @@ impl<'tcx, 'a> Analysis<'tcx> for BorrowsEngine<'a, 'tcx> {
                 unwind,
                 call_source,
                 fn_span,
-            } => {
-                for dest_region in self.get_regions_in(
+            } if target.is_some() => {
+                // One abstraction per SCC of the regions of the result: its
+                // loans are those of the arguments outliving any member.
+                // Since the members outlive each other, they are outlived by
+                // the same regions, so this loses no precision compared to
+                // one abstraction per region, and cannot relate the result
+                // to itself through a cycle of constraints.
+                let dest_regions = self.get_regions_in(
                     destination.ty(self.body.local_decls(), self.tcx).ty,
                     location,
-                ) {
+                );
+                for dest_scc in self.region_sccs(dest_regions) {
                     let mut region_abstraction = RegionAbstraction::new();
                     region_abstraction.add_loan_out(*destination);
                     for arg in args.iter() {
+                        if self.is_written_through(func, arg) {
+                            continue;
+                        }
                         for arg_region in
                             self.get_regions_in(arg.ty(self.body.local_decls(), self.tcx), location)
                         {
-                            if self.outlives_or_eq(arg_region, dest_region) {
+                            if dest_scc
+                                .iter()
+                                .any(|&dest_region| self.outlives_or_eq(arg_region, dest_region))
+                            {
                                 for origin_place in
                                     self.placed_loaned_to_place(arg.place().unwrap())
                                 {
@@ -381,13 +616,14 @@ impl<'tcx, 'a> Analysis<'tcx> for BorrowsEngine<'a, 'tcx> {
                             }
                         }
                     }
-                    eprintln!("Add RA {:?}", region_abstraction);
                     state.after.add_region_abstraction(region_abstraction);
                 }
             }
             _ => {}
         }
+        state.collected = state.after.collect_garbage(self.body.arg_count);
         state.before_after = state.after.clone();
+        state.trace_before_effect(location, &terminator.kind);
     }
 
     fn apply_terminator_effect<'mir>(
@@ -398,16 +634,59 @@ impl<'tcx, 'a> Analysis<'tcx> for BorrowsEngine<'a, 'tcx> {
     ) -> TerminatorEdges<'mir, 'tcx> {
         state.start = state.after.clone();
         match &terminator.kind {
-            TerminatorKind::Call { args, .. } => {
+            TerminatorKind::Call {
+                func,
+                args,
+                destination,
+                target,
+                ..
+            } => {
+                // The loans held by moved arguments flow into the callee: keep
+                // them as of the point where the arguments are consumed, which
+                // is distinct from the destination write after the call.
                 for arg in args {
-                    if let Operand::Move(arg) = arg {
-                        self.remove_loans_assigned_to(&mut state.after, *arg);
+                    if self.is_written_through(func, arg) {
+                        // The reference is given back when the call returns,
+                        // with a fresh value behind it
+                        let arg = arg.place().unwrap();
+                        for borrow in self.remove_loans_assigned_to(&mut state.after, arg) {
+                            if borrow.borrowed_place.is_current() {
+                                state.after.latest.insert(
+                                    borrow.borrowed_place.place(),
+                                    SnapshotLocation::After(location),
+                                );
+                            }
+                        }
+                    } else if let Operand::Move(arg) = arg {
+                        for mut borrow in self.remove_loans_assigned_to(&mut state.after, *arg) {
+                            if borrow.assigned_place.is_current() {
+                                borrow.assigned_place = MaybeOldPlace::OldPlace(PlaceSnapshot::at(
+                                    (*arg).into(),
+                                    SnapshotLocation::Mid(location),
+                                ));
+                            }
+                            state.after.add_borrow(borrow);
+                        }
                     }
                 }
+                if target.is_some() {
+                    state
+                        .after
+                        .latest
+                        .insert((*destination).into(), SnapshotLocation::After(location));
+                }
+            }
+            // A guard failed (or no guard was evaluated yet): control moves
+            // on to the next candidate, whose guard borrows the scrutinee
+            // anew
+            TerminatorKind::FalseEdge { .. } => {
+                state.after.borrows.retain(|borrow| !self.is_shallow(borrow));
             }
             _ => {}
         }
-        terminator.edges()
+        state.collected += state.after.collect_garbage(self.body.arg_count);
+        state.trace_effect(location, &terminator.kind);
+        utils::real_edges(terminator)
     }
 
     fn apply_call_return_effect(
@@ -419,3 +698,83 @@ impl<'tcx, 'a> Analysis<'tcx> for BorrowsEngine<'a, 'tcx> {
         todo!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use super::*;
+    use crate::{run_combined_pcs, run_free_pcs_no_output, test_utils::with_body};
+
+    #[test]
+    fn moved_argument_is_snapshot_mid_call() {
+        let source = "
+            pub fn g(r: &mut u32) -> u32 { *r }
+            pub fn f(x: &mut u32) -> u32 {
+                g(x)
+            }
+        ";
+        with_body(source, "f", |tcx, mir| {
+            let mut analysis = run_free_pcs_no_output(mir, tcx);
+            let call = mir.body.terminator_loc(START_BLOCK);
+            let returned = analysis.get_all_for_bb(START_BLOCK).terminator.succs[0]
+                .extra
+                .after
+                .clone();
+            // The implicit reborrow of `*x` passed to `g` is held by the argument as of
+            // when it is moved into the call, before the result is assigned.
+            // It is not collected, since it borrows the memory of the caller
+            let destination = match &mir.body.basic_blocks[START_BLOCK].terminator().kind {
+                TerminatorKind::Call { destination, .. } => (*destination).into(),
+                _ => unreachable!(),
+            };
+            assert_eq!(
+                returned.latest_of(destination),
+                Some(SnapshotLocation::After(call))
+            );
+            let moved = returned
+                .borrows
+                .iter()
+                .find_map(|borrow| match borrow.assigned_place {
+                    MaybeOldPlace::OldPlace(snapshot) => Some(snapshot),
+                    MaybeOldPlace::Current { .. } => None,
+                })
+                .unwrap();
+            assert_eq!(moved.location, SnapshotLocation::Mid(call));
+        });
+    }
+
+    #[test]
+    fn graph_stays_bounded_on_a_long_function() {
+        // `r = s` makes the reborrow held by `r` old, and nothing borrows
+        // through the old value of `r`: without collecting it, the graph
+        // would grow by one edge per assignment until the end of `f`
+        let reassignments = (1..=30).fold(String::new(), |mut source, i| {
+            write!(
+                source,
+                "let mut v{i} = 0; {{ let s = &mut v{i}; r = s; }} *r = {i};"
+            )
+            .unwrap();
+            source
+        });
+        let source =
+            format!("pub fn f() {{ let mut v0 = 0; let mut r = &mut v0; {reassignments} }}");
+        with_body(&source, "f", |tcx, mir| {
+            let mut cursor = run_combined_pcs(mir, tcx);
+            let mut peak = 0;
+            let mut collected = 0;
+            for (block, data) in mir.body.basic_blocks.iter_enumerated() {
+                for statement_index in 0..=data.statements.len() {
+                    cursor.seek(Location {
+                        block,
+                        statement_index,
+                    });
+                    peak = peak.max(cursor.borrows().borrows.len());
+                    collected += cursor.collected_garbage();
+                }
+            }
+            assert!(peak <= 3, "{peak} borrows");
+            assert_eq!(collected, 30);
+        });
+    }
+}
@@ -0,0 +1,171 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Invariants of the borrows state, checked after every statement so that a
+//! malformed state is reported where it arises rather than many statements
+//! later. Deref expansions are derived from the borrows when the graph is
+//! drawn (see [`BorrowsState::to_graph_schema`]), so they are consistent by
+//! construction and not checked here.
+
+use rustc_interface::middle::mir::{Body, TerminatorKind};
+
+use crate::{
+    rustc_interface,
+    utils::{PlaceRepacker, SnapshotLocation},
+};
+
+use super::domain::{Borrow, BorrowsState, MaybeOldPlace};
+
+/// Why `location` is not a valid snapshot location in `body`, if it is not.
+fn snapshot_location_violation(location: SnapshotLocation, body: &Body<'_>) -> Option<String> {
+    let at = location.location();
+    let Some(block) = body.basic_blocks.get(at.block) else {
+        return Some(format!("{location} refers to a block not in the body"));
+    };
+    if at.statement_index > block.statements.len() {
+        return Some(format!(
+            "{location} is out of bounds: {:?} has {} statements and a terminator",
+            at.block,
+            block.statements.len()
+        ));
+    }
+    let is_call = at.statement_index == block.statements.len()
+        && matches!(block.terminator().kind, TerminatorKind::Call { .. });
+    if matches!(location, SnapshotLocation::Mid(_)) && !is_call {
+        return Some(format!("{location} is not at a call terminator"));
+    }
+    None
+}
+
+impl<'tcx> BorrowsState<'tcx> {
+    /// Why `place`, referenced by `borrow`, is malformed, if it is. An old
+    /// place must have been snapshot at a location of the body, and that
+    /// location must not be the latest write of the place: otherwise its
+    /// value is the current one and it should not be old.
+    fn old_place_violation(
+        &self,
+        borrow: &Borrow<'tcx>,
+        place: &MaybeOldPlace<'tcx>,
+        body: &Body<'tcx>,
+    ) -> Option<String> {
+        let location = place.snapshot_location()?;
+        if let Some(violation) = snapshot_location_violation(location, body) {
+            return Some(format!("{place} in {borrow}: {violation}"));
+        }
+        (self.latest_of(place.place()) == Some(location)).then(|| {
+            format!("{place} in {borrow} is old, but {location} is the latest write of it")
+        })
+    }
+
+    /// The first invariant of the state that is violated, if any:
+    /// - no borrow is assigned to the place it borrows, and
+    /// - every old place has a consistent snapshot location.
+    pub fn invariant_violation(&self, repacker: PlaceRepacker<'_, 'tcx>) -> Option<String> {
+        let body = repacker.body();
+        self.borrows.iter().find_map(|borrow| {
+            if borrow.borrowed_place == borrow.assigned_place {
+                return Some(format!("{borrow} is assigned to the place it borrows"));
+            }
+            self.old_place_violation(borrow, &borrow.borrowed_place, body)
+                .or_else(|| self.old_place_violation(borrow, &borrow.assigned_place, body))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_interface::middle::mir::{BasicBlock, Location, START_BLOCK};
+
+    use crate::{
+        combined_pcs::PcsContext,
+        rustc_interface,
+        test_utils::{with_body, BorrowsStateBuilder},
+        utils::SnapshotLocation,
+    };
+
+    const CALL: &str = "
+        pub fn f(x: u32) -> u32 { g(x) }
+        fn g(x: u32) -> u32 { x }
+    ";
+
+    /// The violation of the state built by `build`, given the location of
+    /// the call in `f` and an empty builder, in the body of `f`.
+    fn violation_of(
+        build: impl for<'tcx> FnOnce(Location, BorrowsStateBuilder<'tcx>) -> BorrowsStateBuilder<'tcx>
+            + Send,
+    ) -> Option<String> {
+        with_body(CALL, "f", |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            let call = Location {
+                block: START_BLOCK,
+                statement_index: rp.body()[START_BLOCK].statements.len(),
+            };
+            build(call, BorrowsStateBuilder::new())
+                .build()
+                .invariant_violation(rp)
+        })
+    }
+
+    #[test]
+    fn consistent_state() {
+        let violation = violation_of(|call, state| {
+            state
+                .borrow(1, 2, true)
+                .borrow_into_old(2, 3, SnapshotLocation::Mid(call), true)
+        });
+        assert_eq!(violation, None);
+    }
+
+    #[test]
+    fn self_borrow() {
+        let violation = violation_of(|_, state| state.borrow(1, 1, true));
+        assert!(violation
+            .unwrap()
+            .contains("assigned to the place it borrows"));
+    }
+
+    #[test]
+    fn snapshot_out_of_bounds() {
+        let violation = violation_of(|call, state| {
+            let past_terminator = Location {
+                statement_index: call.statement_index + 1,
+                ..call
+            };
+            state.borrow_into_old(1, 2, SnapshotLocation::After(past_terminator), true)
+        });
+        assert!(violation.unwrap().contains("is out of bounds"));
+        let violation = violation_of(|_, state| {
+            let not_a_block = BasicBlock::from_usize(100).start_location();
+            state.borrow_into_old(1, 2, SnapshotLocation::After(not_a_block), true)
+        });
+        assert!(violation
+            .unwrap()
+            .contains("refers to a block not in the body"));
+    }
+
+    #[test]
+    fn mid_snapshot_not_at_call() {
+        let violation = violation_of(|_, state| {
+            state.borrow_into_old(
+                1,
+                2,
+                SnapshotLocation::Mid(START_BLOCK.start_location()),
+                true,
+            )
+        });
+        assert!(violation.unwrap().contains("is not at a call terminator"));
+    }
+
+    #[test]
+    fn snapshot_at_latest_write() {
+        let violation = violation_of(|call, state| {
+            state
+                .borrow_into_old(1, 2, SnapshotLocation::After(call), true)
+                .written(2, SnapshotLocation::After(call))
+        });
+        assert!(violation.unwrap().contains("is the latest write"));
+    }
+}
@@ -0,0 +1,83 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Library functions whose effect on their reference arguments is understood
+//! precisely. A call to any other function is abstracted conservatively: the
+//! loans held by its arguments are kept (as of the point where the arguments
+//! are consumed) and may flow into the result.
+
+use rustc_interface::{
+    data_structures::fx::FxHashMap,
+    middle::{mir::Operand, ty::TyCtxt},
+};
+
+use crate::rustc_interface;
+
+/// The effect of a known function on its arguments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KnownCallEffect {
+    /// The function only writes to the places behind its `&mut` arguments,
+    /// and neither they nor the loans they hold flow into the result. The
+    /// references are given back when the call returns, with fresh values
+    /// behind them: e.g. `std::mem::swap`, `std::mem::replace`.
+    WritesThroughMutRefs,
+}
+
+/// The functions whose calls are not abstracted conservatively, by path as
+/// printed by `TyCtxt::def_path_str` (e.g. `std::mem::swap` or, for a
+/// method, `std::option::Option::<T>::take`).
+///
+/// ```ignore
+/// let mut known_calls = KnownCalls::default();
+/// known_calls.register("my_crate::swap_fields", KnownCallEffect::WritesThroughMutRefs);
+/// engine.set_known_calls(known_calls);
+/// ```
+#[derive(Clone, Debug)]
+pub struct KnownCalls(FxHashMap<String, KnownCallEffect>);
+
+impl KnownCalls {
+    /// A registry without any function.
+    pub fn empty() -> Self {
+        Self(FxHashMap::default())
+    }
+
+    pub fn register(&mut self, path: impl Into<String>, effect: KnownCallEffect) {
+        self.0.insert(path.into(), effect);
+    }
+
+    /// The effect of a call to `func`, if the called function is known.
+    pub fn effect_of<'tcx>(
+        &self,
+        func: &Operand<'tcx>,
+        tcx: TyCtxt<'tcx>,
+    ) -> Option<KnownCallEffect> {
+        let (def_id, _) = func.const_fn_def()?;
+        self.0.get(&tcx.def_path_str(def_id)).copied()
+    }
+}
+
+impl Default for KnownCalls {
+    /// The reference-manipulating functions of the standard library. Both
+    /// the `std` and the `core` paths are registered, since the latter are
+    /// printed in `#![no_std]` crates.
+    fn default() -> Self {
+        let mut known_calls = Self::empty();
+        for krate in ["std", "core"] {
+            for function in [
+                "mem::swap",
+                "mem::replace",
+                "mem::take",
+                "option::Option::<T>::take",
+            ] {
+                known_calls.register(
+                    format!("{krate}::{function}"),
+                    KnownCallEffect::WritesThroughMutRefs,
+                );
+            }
+        }
+        known_calls
+    }
+}
@@ -0,0 +1,154 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use rustc_interface::middle::mir::BasicBlock;
+use serde_json::Value;
+
+use crate::{
+    rustc_interface,
+    utils::{Place, PlaceMap, PlaceRepacker, SnapshotLocation},
+    visualization::schema,
+};
+
+/// Tracks, for each place, the point at which its current value was written.
+/// Places that are not in the map (and have no prefix in it) have not been
+/// written since the start of the function. `None` is the bottom element,
+/// i.e. the state of blocks that have not been reached yet. The entries are
+/// shared between clones, see [`PlaceMap`].
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct Latest<'tcx>(Option<PlaceMap<'tcx, SnapshotLocation>>);
+
+impl<'tcx> Latest<'tcx> {
+    /// The state at the start of the function, where nothing was written yet.
+    pub fn new() -> Self {
+        Self(Some(PlaceMap::default()))
+    }
+
+    pub fn bottom() -> Self {
+        Self(None)
+    }
+
+    fn entries(&self) -> impl Iterator<Item = (Place<'tcx>, &SnapshotLocation)> {
+        self.0.iter().flat_map(|entries| entries.iter())
+    }
+
+    /// The point of the most recent write to `place` or one of its prefixes.
+    pub fn get(&self, place: Place<'tcx>) -> Option<SnapshotLocation> {
+        self.0
+            .as_ref()?
+            .iter_prefixes(place)
+            .last()
+            .map(|(_, location)| *location)
+    }
+
+    /// Records that `place` (and thereby all places it is a prefix of) is
+    /// written at `location`. Only the entries that are overwritten are
+    /// copied, see [`PlaceMap`].
+    pub fn insert(&mut self, place: Place<'tcx>, location: SnapshotLocation) {
+        let entries = self.0.get_or_insert_with(PlaceMap::default);
+        let overwritten: Vec<_> = entries
+            .iter_extensions(place)
+            .map(|(other, _)| other)
+            .filter(|&other| other != place)
+            .collect();
+        for other in overwritten {
+            entries.remove(other);
+        }
+        if entries.get(place) != Some(&location) {
+            entries.insert(place, location);
+        }
+    }
+
+    /// Places whose latest write differs between `self` and `other` are
+    /// considered to be written at the join at the entry of `block`. Only the
+    /// shortest of them are recorded, so that the entries do not depend on
+    /// the order of the arguments (or of the joins).
+    pub fn join(&mut self, other: &Self, block: BasicBlock) -> bool {
+        let Some(other_entries) = &other.0 else {
+            return false;
+        };
+        let Some(entries) = &self.0 else {
+            *self = other.clone();
+            return true;
+        };
+        let join = SnapshotLocation::Join(block);
+        let differing: Vec<_> = entries
+            .places()
+            .chain(other_entries.places())
+            .filter(|place| self.get(*place) != other.get(*place) && self.get(*place) != Some(join))
+            .collect();
+        for place in &differing {
+            if !differing
+                .iter()
+                .any(|other| other.is_strict_prefix_of(*place))
+            {
+                self.insert(*place, join);
+            }
+        }
+        !differing.is_empty()
+    }
+
+    pub fn to_schema(&self, repacker: PlaceRepacker<'_, 'tcx>) -> schema::Latest {
+        self.entries()
+            .map(|(place, location)| (place.to_short_string(repacker), location.to_string()))
+            .collect()
+    }
+
+    pub fn to_json(&self, repacker: PlaceRepacker<'_, 'tcx>) -> Value {
+        serde_json::to_value(self.to_schema(repacker)).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_interface::middle::mir::{Local, Location};
+
+    use super::*;
+
+    fn after(block: usize) -> SnapshotLocation {
+        SnapshotLocation::After(Location {
+            block: BasicBlock::from_usize(block),
+            statement_index: 0,
+        })
+    }
+
+    #[test]
+    fn differently_written_places_are_written_at_the_join() {
+        let (x, y) = (Local::from_usize(1).into(), Local::from_usize(2).into());
+        // bb0 writes `x` and `y`, then bb1 writes `x` again and bb2 does not
+        let mut written_twice = Latest::new();
+        written_twice.insert(x, after(0));
+        written_twice.insert(y, after(0));
+        let mut written_once = written_twice.clone();
+        written_twice.insert(x, after(1));
+
+        let join = BasicBlock::from_usize(3);
+        let mut joined = written_twice.clone();
+        assert!(joined.join(&written_once, join));
+        assert_eq!(joined.get(x), Some(SnapshotLocation::Join(join)));
+        assert_eq!(joined.get(y), Some(after(0)));
+        assert!(!joined.join(&written_once, join));
+
+        assert!(written_once.join(&written_twice, join));
+        assert_eq!(written_once, joined);
+    }
+
+    fn shared<'tcx>(a: &Latest<'tcx>, b: &Latest<'tcx>) -> bool {
+        a.0.as_ref().unwrap().ptr_eq(b.0.as_ref().unwrap())
+    }
+
+    #[test]
+    fn rewriting_the_same_location_keeps_the_entries_shared() {
+        let x = Local::from_usize(1).into();
+        let mut latest = Latest::new();
+        latest.insert(x, after(0));
+        let mut rewritten = latest.clone();
+        rewritten.insert(x, after(0));
+        assert!(shared(&latest, &rewritten));
+        rewritten.insert(x, after(1));
+        assert!(!shared(&latest, &rewritten));
+    }
+}
@@ -0,0 +1,90 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Cross-checks the borrows state against the borrow checker, enabled with
+//! `--pcs-check-loans`: at each location, every loan in scope should have an
+//! edge in the state (see [`BorrowsState::edge_for_loan`]), and every current
+//! edge created from a loan should be for a loan in scope. The discrepancies
+//! are written to `warnings.json`; they are not necessarily bugs, e.g. the
+//! loans moved to another reference are tracked by edges that no longer know
+//! their loan.
+//!
+//! Polonius only reports the loans live at each point when its facts are
+//! dumped, so the loans in scope are those of rustc's `Borrows` analysis,
+//! which computes the scope of loans from the same region inference.
+
+use rustc_interface::{
+    borrowck::{
+        borrow_set::BorrowSet,
+        consumers::{BorrowIndex, Borrows},
+    },
+    dataflow::{Analysis, ResultsCursor},
+    middle::{
+        mir::{Body, Location},
+        ty::TyCtxt,
+    },
+};
+
+use super::domain::BorrowsState;
+use crate::{combined_pcs::BodyWithBorrowckFacts, rustc_interface};
+
+pub struct LoanChecker<'mir, 'tcx> {
+    in_scope: ResultsCursor<'mir, 'tcx, Borrows<'mir, 'tcx>>,
+    borrow_set: &'mir BorrowSet<'tcx>,
+}
+
+impl<'mir, 'tcx> LoanChecker<'mir, 'tcx> {
+    pub fn new(tcx: TyCtxt<'tcx>, mir: &'mir BodyWithBorrowckFacts<'tcx>) -> Self {
+        let body: &'mir Body<'tcx> = &mir.body;
+        let in_scope = Borrows::new(tcx, body, &mir.region_inference_context, &mir.borrow_set)
+            .into_engine(tcx, body)
+            .iterate_to_fixpoint()
+            .into_results_cursor(body);
+        Self {
+            in_scope,
+            borrow_set: &mir.borrow_set,
+        }
+    }
+
+    /// Whether `loan` is in scope after the statement (or terminator) at
+    /// `location`.
+    pub fn in_scope_after(&mut self, loan: BorrowIndex, location: Location) -> bool {
+        self.in_scope.seek_after_primary_effect(location);
+        self.in_scope.get().contains(loan)
+    }
+
+    /// The discrepancies between `borrows`, the state after the statement
+    /// (or terminator) at `location`, and the loans in scope after it.
+    pub fn discrepancies(
+        &mut self,
+        borrows: &BorrowsState<'tcx>,
+        location: Location,
+    ) -> Vec<String> {
+        self.in_scope.seek_after_primary_effect(location);
+        let in_scope = self.in_scope.get();
+        let mut discrepancies = vec![];
+        for loan in in_scope.iter() {
+            if borrows.edge_for_loan(loan).is_none() {
+                discrepancies.push(format!(
+                    "loan {loan:?} ({}) is in scope, but has no edge",
+                    self.borrow_set[loan]
+                ));
+            }
+        }
+        let mut dead_edges: Vec<_> = borrows
+            .live_borrows()
+            .flat_map(|borrow| borrow.loans().map(move |loan| (loan, borrow)))
+            .filter(|(loan, _)| !in_scope.contains(*loan))
+            .collect();
+        dead_edges.sort_by_key(|(loan, _)| *loan);
+        for (loan, borrow) in dead_edges {
+            discrepancies.push(format!(
+                "edge {borrow} of loan {loan:?} is present, but the loan is not in scope"
+            ));
+        }
+        discrepancies
+    }
+}
@@ -1,2 +1,6 @@
 pub mod domain;
 pub mod engine;
+pub mod invariants;
+pub mod known_calls;
+pub mod latest;
+pub mod loan_check;
@@ -0,0 +1,132 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use rustc_interface::{
+    data_structures::fx::FxHashMap,
+    hir::def_id::DefId,
+    middle::{mir::Location, ty::TyCtxt},
+};
+
+use crate::{
+    borrows::domain::BorrowsState, free_pcs::CapabilitySummary, run_free_pcs_no_output,
+    rustc_interface,
+};
+
+use super::BodyWithBorrowckFacts;
+
+/// The state of a body after each of its locations.
+pub struct PcsBodyResults<'tcx> {
+    /// The hash of the MIR the results were computed for.
+    mir_hash: u64,
+    states: FxHashMap<Location, (CapabilitySummary<'tcx>, BorrowsState<'tcx>)>,
+}
+
+impl<'tcx> PcsBodyResults<'tcx> {
+    pub fn capabilities_after(&self, location: Location) -> Option<&CapabilitySummary<'tcx>> {
+        self.states
+            .get(&location)
+            .map(|(capabilities, _)| capabilities)
+    }
+
+    pub fn borrows_after(&self, location: Location) -> Option<&BorrowsState<'tcx>> {
+        self.states.get(&location).map(|(_, borrows)| borrows)
+    }
+}
+
+/// Memoizes the analysis results of bodies, such that tools querying the
+/// state at many locations of the same function only run the analysis once.
+#[derive(Default)]
+pub struct PcgCache<'tcx> {
+    bodies: FxHashMap<DefId, PcsBodyResults<'tcx>>,
+    /// The number of times the analysis was run.
+    computations: usize,
+}
+
+impl<'tcx> PcgCache<'tcx> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the results for `def_id`, running the analysis only if there
+    /// are no results yet or they were computed for a different `mir_hash`.
+    pub fn get_or_compute(
+        &mut self,
+        def_id: DefId,
+        mir_hash: u64,
+        mir: &BodyWithBorrowckFacts<'tcx>,
+        tcx: TyCtxt<'tcx>,
+    ) -> &PcsBodyResults<'tcx> {
+        let stale = self
+            .bodies
+            .get(&def_id)
+            .map_or(true, |results| results.mir_hash != mir_hash);
+        if stale {
+            self.computations += 1;
+            self.bodies
+                .insert(def_id, Self::compute(mir_hash, mir, tcx));
+        }
+        &self.bodies[&def_id]
+    }
+
+    pub fn invalidate(&mut self, def_id: DefId) {
+        self.bodies.remove(&def_id);
+    }
+
+    pub fn computations(&self) -> usize {
+        self.computations
+    }
+
+    fn compute(
+        mir_hash: u64,
+        mir: &BodyWithBorrowckFacts<'tcx>,
+        tcx: TyCtxt<'tcx>,
+    ) -> PcsBodyResults<'tcx> {
+        let mut analysis = run_free_pcs_no_output(mir, tcx);
+        let mut states = FxHashMap::default();
+        for block in mir.body.basic_blocks.indices() {
+            for statement in analysis.get_all_for_bb(block).statements {
+                states.insert(statement.location, (statement.state, statement.extra.after));
+            }
+        }
+        PcsBodyResults { mir_hash, states }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_interface::middle::mir::START_BLOCK;
+
+    use super::*;
+    use crate::test_utils::with_body;
+
+    #[test]
+    fn results_are_computed_once_per_hash() {
+        with_body("pub fn f(x: u32) -> u32 { x + 1 }", "f", |tcx, mir| {
+            let def_id = mir.body.source.def_id();
+            let mut cache = PcgCache::new();
+            let location = START_BLOCK.start_location();
+            let first = cache
+                .get_or_compute(def_id, 0, mir, tcx)
+                .capabilities_after(location)
+                .cloned();
+            assert!(first.is_some());
+            assert_eq!(cache.computations(), 1);
+
+            let second = cache
+                .get_or_compute(def_id, 0, mir, tcx)
+                .capabilities_after(location)
+                .cloned();
+            assert_eq!(cache.computations(), 1);
+            assert_eq!(first, second);
+
+            cache.get_or_compute(def_id, 1, mir, tcx);
+            assert_eq!(cache.computations(), 2);
+            cache.invalidate(def_id);
+            cache.get_or_compute(def_id, 1, mir, tcx);
+            assert_eq!(cache.computations(), 3);
+        });
+    }
+}
@@ -0,0 +1,344 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use rustc_interface::{
+    dataflow::ResultsCursor,
+    middle::mir::{Body, Location},
+};
+
+use crate::{
+    borrows::{
+        domain::{Borrow, BorrowsState},
+        engine::{BorrowAction, BorrowsDomain},
+    },
+    free_pcs::{CapabilitySummary, RepackOp},
+    rustc_interface,
+    utils::PlaceRepacker,
+    visualization::schema,
+};
+
+use super::PcsEngine;
+
+/// A step taken by the analysis at a location, in addition to the effect of
+/// the statement itself.
+#[derive(Clone, Debug)]
+pub enum PcsAction<'tcx> {
+    Repack(RepackOp<'tcx>),
+    AddBorrow(Borrow<'tcx>),
+    RemoveBorrow(Borrow<'tcx>),
+}
+
+impl<'tcx> From<BorrowAction<'_, 'tcx>> for PcsAction<'tcx> {
+    fn from(action: BorrowAction<'_, 'tcx>) -> Self {
+        match action {
+            BorrowAction::AddBorrow(borrow) => PcsAction::AddBorrow(borrow.into_owned()),
+            BorrowAction::RemoveBorrow(borrow) => PcsAction::RemoveBorrow(borrow.clone()),
+        }
+    }
+}
+
+impl<'tcx> PcsAction<'tcx> {
+    /// Whether `self` and `other` have the same effect on the places, which
+    /// is the case if they are equal up to where the borrows they add or
+    /// remove were created: the loan of a rustc borrow and the snapshot
+    /// locations of old places are ignored. For example, removing
+    /// `&mut x -> r` is logically equivalent whether the borrow was created in
+    /// the `then` or in the `else` branch of an `if`.
+    pub fn logically_equivalent(&self, other: &PcsAction<'tcx>) -> bool {
+        let equivalent_borrows = |left: &Borrow<'tcx>, right: &Borrow<'tcx>| {
+            left.is_mut == right.is_mut
+                && left.borrowed_place.place() == right.borrowed_place.place()
+                && left.assigned_place.place() == right.assigned_place.place()
+                && left.borrowed_place.is_current() == right.borrowed_place.is_current()
+                && left.assigned_place.is_current() == right.assigned_place.is_current()
+        };
+        match (self, other) {
+            (PcsAction::Repack(left), PcsAction::Repack(right)) => left == right,
+            (PcsAction::AddBorrow(left), PcsAction::AddBorrow(right))
+            | (PcsAction::RemoveBorrow(left), PcsAction::RemoveBorrow(right)) => {
+                equivalent_borrows(left, right)
+            }
+            _ => false,
+        }
+    }
+
+    pub fn to_schema(&self, repacker: PlaceRepacker<'_, 'tcx>) -> schema::PcsAction {
+        match self {
+            PcsAction::Repack(op) => op.to_schema(repacker),
+            PcsAction::AddBorrow(borrow) => schema::PcsAction::AddBorrow {
+                borrow: borrow.to_schema(repacker),
+            },
+            PcsAction::RemoveBorrow(borrow) => schema::PcsAction::RemoveBorrow {
+                borrow: borrow.to_schema(repacker),
+            },
+        }
+    }
+}
+
+/// The actions taken at a location, in the order in which `PcsEngine` applies
+/// them, given the repacks and the borrows state at that location.
+pub fn pcs_actions<'tcx>(
+    repacks_start: &[RepackOp<'tcx>],
+    repacks_middle: &[RepackOp<'tcx>],
+    borrows: &BorrowsDomain<'tcx>,
+) -> Vec<PcsAction<'tcx>> {
+    borrows
+        .actions(true)
+        .into_iter()
+        .map(PcsAction::from)
+        .chain(repacks_start.iter().copied().map(PcsAction::Repack))
+        .chain(borrows.actions(false).into_iter().map(PcsAction::from))
+        .chain(repacks_middle.iter().copied().map(PcsAction::Repack))
+        .collect()
+}
+
+/// Removes the actions that are logically equivalent to an earlier one (see
+/// [`PcsAction::logically_equivalent`]), keeping the first of each.
+///
+/// This is only safe if the equivalent actions stem from different paths, so
+/// that at most one of them applies to any execution: e.g. after a join, where
+/// a borrow created on each incoming path is removed. Actions on the same path
+/// that only differ in their snapshot locations refer to different values
+/// and must all be kept.
+pub fn dedup_logically_equivalent(actions: &mut Vec<PcsAction<'_>>) {
+    let mut deduped: Vec<PcsAction<'_>> = Vec::with_capacity(actions.len());
+    for action in actions.drain(..) {
+        if !deduped
+            .iter()
+            .any(|other| other.logically_equivalent(&action))
+        {
+            deduped.push(action);
+        }
+    }
+    *actions = deduped;
+}
+
+/// Gives access to the owned places (free PCS) and the borrows at a location
+/// at once. Both are components of the same dataflow state, so they are
+/// always in sync.
+///
+/// ```ignore
+/// let mut cursor = pcs::run_combined_pcs(&mir, tcx);
+/// for (block, data) in mir.body.basic_blocks.iter_enumerated() {
+///     for statement_index in 0..=data.statements.len() {
+///         cursor.seek(Location { block, statement_index });
+///         for action in cursor.actions() {
+///             // e.g. encode the repacks and borrow expiries
+///         }
+///         let owned = cursor.owned();
+///         let borrows = cursor.borrows();
+///     }
+/// }
+/// ```
+pub struct CombinedPcsCursor<'mir, 'tcx> {
+    cursor: ResultsCursor<'mir, 'tcx, PcsEngine<'mir, 'tcx>>,
+    location: Option<Location>,
+    owned_before: CapabilitySummary<'tcx>,
+    actions: Vec<PcsAction<'tcx>>,
+}
+
+impl<'mir, 'tcx> CombinedPcsCursor<'mir, 'tcx> {
+    pub fn new(cursor: ResultsCursor<'mir, 'tcx, PcsEngine<'mir, 'tcx>>) -> Self {
+        Self {
+            cursor,
+            location: None,
+            owned_before: CapabilitySummary::empty(),
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn body(&self) -> &'mir Body<'tcx> {
+        self.cursor.body()
+    }
+
+    pub(crate) fn analysis(&self) -> &PcsEngine<'mir, 'tcx> {
+        self.cursor.analysis()
+    }
+
+    /// Moves to the state after the statement (or terminator) at `location`
+    /// has been executed, and computes the actions taken at `location`.
+    pub fn seek(&mut self, location: Location) {
+        let block = &self.body()[location.block];
+        assert!(
+            location.statement_index <= block.statements.len(),
+            "{location:?} is out of bounds: {:?} has {} statements and a terminator",
+            location.block,
+            block.statements.len()
+        );
+        if location.statement_index == 0 {
+            self.cursor.seek_to_block_start(location.block);
+        } else {
+            self.cursor.seek_after_primary_effect(Location {
+                statement_index: location.statement_index - 1,
+                ..location
+            });
+        }
+        self.owned_before = self.cursor.get().fpcs.after.clone();
+        self.cursor.seek_after_primary_effect(location);
+        let state = self.cursor.get();
+        let (repacks_start, repacks_middle) = state.fpcs.repack_ops(&self.owned_before);
+        self.actions = pcs_actions(&repacks_start, &repacks_middle, &state.borrows);
+        self.location = Some(location);
+    }
+
+    /// The location last passed to [`Self::seek`].
+    pub fn location(&self) -> Location {
+        self.location.expect("`seek` has not been called")
+    }
+
+    pub fn owned(&self) -> &CapabilitySummary<'tcx> {
+        self.location();
+        &self.cursor.get().fpcs.after
+    }
+
+    /// The owned capabilities before the statement (or terminator) at
+    /// [`Self::location`] was executed.
+    pub fn owned_before(&self) -> &CapabilitySummary<'tcx> {
+        self.location();
+        &self.owned_before
+    }
+
+    pub fn borrows(&self) -> &BorrowsState<'tcx> {
+        self.location();
+        &self.cursor.get().borrows.after
+    }
+
+    /// The number of nodes of the borrows graph collected as garbage at
+    /// [`Self::location`].
+    pub fn collected_garbage(&self) -> usize {
+        self.location();
+        self.cursor.get().borrows.collected_garbage()
+    }
+
+    pub fn actions(&self) -> &[PcsAction<'tcx>] {
+        self.location();
+        &self.actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_interface::middle::mir::START_BLOCK;
+
+    use super::*;
+    use crate::{
+        run_combined_pcs,
+        test_utils::{with_body, BorrowsStateBuilder},
+        utils::SnapshotLocation,
+    };
+
+    #[test]
+    fn walk_of_a_function() {
+        let source = "
+            pub fn f(x: &mut u32) {
+                let r = &mut *x;
+                *r = 1;
+            }
+        ";
+        with_body(source, "f", |tcx, mir| {
+            let mut cursor = run_combined_pcs(mir, tcx);
+            let data = &mir.body.basic_blocks[START_BLOCK];
+            let mut previous: Option<CapabilitySummary<'_>> = None;
+            let mut walk = Vec::new();
+            for statement_index in 0..=data.statements.len() {
+                let location = Location {
+                    block: START_BLOCK,
+                    statement_index,
+                };
+                cursor.seek(location);
+                assert_eq!(cursor.location(), location);
+                if let Some(previous) = previous {
+                    assert_eq!(cursor.owned_before(), &previous);
+                }
+                previous = Some(cursor.owned().clone());
+                let actions: Vec<_> = cursor
+                    .actions()
+                    .iter()
+                    .map(|action| match action {
+                        PcsAction::Repack(_) => "repack",
+                        PcsAction::AddBorrow(_) => "add",
+                        PcsAction::RemoveBorrow(_) => "remove",
+                    })
+                    .collect();
+                let live = cursor.borrows().live_borrows().count();
+                walk.push(format!(
+                    "{statement_index}: [{}] {live}",
+                    actions.join(", ")
+                ));
+            }
+            // The borrow `&mut *x -> r` is live from its creation up to the
+            // `StorageDead(r)`
+            assert_eq!(
+                walk,
+                [
+                    "0: [] 0",
+                    "1: [repack, add] 1",
+                    "2: [] 1",
+                    "3: [repack] 1",
+                    "4: [] 1",
+                    "5: [remove, repack, repack] 0",
+                    "6: [repack, repack] 0",
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn actions_equivalent_up_to_loans_and_snapshots() {
+        let before = |statement_index| {
+            SnapshotLocation::Before(Location {
+                block: START_BLOCK,
+                statement_index,
+            })
+        };
+        let borrow = |builder: BorrowsStateBuilder<'static>| {
+            let state = builder.build();
+            state.borrows.iter().next().unwrap().clone()
+        };
+        // `&mut _1 -> _2` created in two branches, and once shared
+        let then_loan = borrow(BorrowsStateBuilder::new().loan(0, 1, 2, true));
+        let else_loan = borrow(BorrowsStateBuilder::new().loan(1, 1, 2, true));
+        let shared_loan = borrow(BorrowsStateBuilder::new().loan(2, 1, 2, false));
+        // `&mut _1 -> _3` before `_3` is overwritten in either branch
+        let then_old = borrow(BorrowsStateBuilder::new().borrow_into_old(1, 3, before(0), true));
+        let else_old = borrow(BorrowsStateBuilder::new().borrow_into_old(1, 3, before(1), true));
+        let current = borrow(BorrowsStateBuilder::new().borrow(1, 3, true));
+
+        let remove = |borrow: &Borrow<'static>| PcsAction::RemoveBorrow(borrow.clone());
+        assert!(remove(&then_loan).logically_equivalent(&remove(&else_loan)));
+        assert!(!remove(&then_loan).logically_equivalent(&PcsAction::AddBorrow(else_loan.clone())));
+        assert!(!remove(&then_loan).logically_equivalent(&remove(&shared_loan)));
+        assert!(remove(&then_old).logically_equivalent(&remove(&else_old)));
+        assert!(!remove(&then_old).logically_equivalent(&remove(&current)));
+
+        let mut actions = vec![
+            remove(&then_loan),
+            remove(&then_old),
+            remove(&else_loan),
+            PcsAction::AddBorrow(else_loan.clone()),
+            remove(&else_old),
+            remove(&current),
+        ];
+        dedup_logically_equivalent(&mut actions);
+        let kept: Vec<_> = actions
+            .iter()
+            .map(|action| match action {
+                PcsAction::AddBorrow(borrow) => format!("add {borrow}"),
+                PcsAction::RemoveBorrow(borrow) => format!("remove {borrow}"),
+                PcsAction::Repack(_) => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            kept,
+            [
+                "remove Rustc(bw0): &mut _1 -> _2",
+                "remove PCS: &mut _1 -> _3 at before bb0[0]",
+                "add Rustc(bw1): &mut _1 -> _2",
+                "remove PCS: &mut _1 -> _3",
+            ]
+        );
+    }
+}
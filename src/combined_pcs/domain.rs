@@ -18,7 +18,7 @@ use rustc_interface::{
 use crate::{
     borrows::engine::BorrowsDomain, free_pcs::{
         CapabilityLocal, CapabilityProjections, FreePlaceCapabilitySummary, HasFpcs, RepackOp
-    }, rustc_interface, utils::{Place, PlaceRepacker}
+    }, pcs_trace, rustc_interface, utils::{verbose::is_verbose, Place, PlaceRepacker}
 };
 
 use super::{PcsContext, PcsEngine};
@@ -55,9 +55,22 @@ impl Debug for PlaceCapabilitySummary<'_, '_> {
 
 impl JoinSemiLattice for PlaceCapabilitySummary<'_, '_> {
     fn join(&mut self, other: &Self) -> bool {
+        let before = is_verbose().then(|| self.borrows.after.clone());
         let fpcs = self.fpcs.join(&other.fpcs);
         let borrows = self.borrows.join(&other.borrows);
-        fpcs || borrows
+        let latest = self
+            .borrows
+            .after
+            .latest
+            .join(&other.borrows.after.latest, self.block);
+        if let Some(before) = before {
+            pcs_trace!(
+                "join into {:?}: fpcs changed: {fpcs}, borrows changed: {borrows}, latest changed: {latest}{}",
+                self.block,
+                self.borrows.after.borrows_diff(&before)
+            );
+        }
+        fpcs || borrows || latest
     }
 }
 
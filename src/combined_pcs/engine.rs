@@ -11,7 +11,7 @@ use rustc_interface::{
         borrow_set::BorrowSet,
         consumers::{self, LocationTable, PoloniusInput, PoloniusOutput, RegionInferenceContext},
     },
-    dataflow::{Analysis, AnalysisDomain},
+    dataflow::{Analysis, AnalysisDomain, ResultsCursor},
     index::{Idx, IndexVec},
     middle::{
         mir::{
@@ -23,16 +23,23 @@ use rustc_interface::{
 };
 
 use crate::{
-    borrows::{domain::BorrowsState, engine::BorrowsEngine},
+    borrows::{
+        domain::BorrowsState, engine::BorrowsEngine, known_calls::KnownCalls, latest::Latest,
+    },
     free_pcs::{
         engine::FpcsEngine, CapabilityKind, CapabilityLocal, CapabilitySummary,
         FreePlaceCapabilitySummary,
     },
     rustc_interface,
-    utils::{PlaceOrdering, PlaceRepacker},
+    utils::{ignore_cleanup_enabled, real_edges, PlaceOrdering, PlaceRepacker, RepackerCache},
+    visualization::dot_graph,
 };
 
-use super::domain::PlaceCapabilitySummary;
+use super::{
+    domain::PlaceCapabilitySummary,
+    initial::{initial_state, InitialStateHook},
+    validity_checks_enabled,
+};
 
 pub struct BodyWithBorrowckFacts<'tcx> {
     pub body: Body<'tcx>,
@@ -42,6 +49,7 @@ pub struct BodyWithBorrowckFacts<'tcx> {
     pub location_table: Option<Rc<LocationTable>>,
     pub input_facts: Option<Box<PoloniusInput>>,
     pub output_facts: Option<Rc<PoloniusOutput>>,
+    pub repacker_cache: RepackerCache<'tcx>,
 }
 
 impl<'tcx> From<consumers::BodyWithBorrowckFacts<'tcx>> for BodyWithBorrowckFacts<'tcx> {
@@ -54,6 +62,7 @@ impl<'tcx> From<consumers::BodyWithBorrowckFacts<'tcx>> for BodyWithBorrowckFact
             location_table: value.location_table.map(Rc::new),
             input_facts: value.input_facts,
             output_facts: value.output_facts,
+            repacker_cache: RepackerCache::default(),
         }
     }
 }
@@ -65,17 +74,51 @@ pub struct PcsContext<'a, 'tcx> {
 
 impl<'a, 'tcx> PcsContext<'a, 'tcx> {
     pub fn new(tcx: TyCtxt<'tcx>, mir: &'a BodyWithBorrowckFacts<'tcx>) -> Self {
-        let rp = PlaceRepacker::new(&mir.body, &mir.promoted, tcx);
+        let rp = PlaceRepacker::new(&mir.body, &mir.promoted, tcx, &mir.repacker_cache);
         Self { rp, mir }
     }
 }
 
+/// Called with the entry state of a block every time the dataflow engine
+/// visits that block.
+pub type VisitHook<'a, 'tcx> = Box<dyn FnMut(BasicBlock, &PlaceCapabilitySummary<'a, 'tcx>) + 'a>;
+
+/// Called with the state after each statement (or terminator), see
+/// [`replay_effects`].
+pub(crate) type EffectHook<'a, 'tcx> =
+    Box<dyn FnMut(Location, &PlaceCapabilitySummary<'a, 'tcx>) + 'a>;
+
+/// Applies the effects of each block once more to the entry state of the
+/// block, calling `hook` with the state after each statement and terminator.
+/// `cursor` must hold the results of an analysis that reached its fixpoint,
+/// so that the states are final.
+pub(crate) fn replay_effects<'mir, 'tcx>(
+    cursor: &mut ResultsCursor<'mir, 'tcx, PcsEngine<'mir, 'tcx>>,
+    hook: EffectHook<'mir, 'tcx>,
+) {
+    cursor.mut_analysis().effect_hook = Some(hook);
+    for block in cursor.body().basic_blocks.indices() {
+        cursor.seek_to_block_end(block);
+    }
+    cursor.mut_analysis().effect_hook = None;
+}
+
 pub struct PcsEngine<'a, 'tcx> {
     pub(crate) cgx: Rc<PcsContext<'a, 'tcx>>,
     block: Cell<BasicBlock>,
 
     pub(crate) fpcs: FpcsEngine<'a, 'tcx>,
     pub(crate) borrows: BorrowsEngine<'a, 'tcx>,
+    visit_hook: Option<VisitHook<'a, 'tcx>>,
+    effect_hook: Option<EffectHook<'a, 'tcx>>,
+    initial_state_hook: Option<InitialStateHook<'a, 'tcx>>,
+    /// Whether the invariants of the borrows state are checked after every
+    /// statement, which is the case in debug builds or with
+    /// `PCS_VALIDITY_CHECKS=1`.
+    check_invariants: bool,
+    /// Whether cleanup blocks are skipped, with `PCS_IGNORE_CLEANUP=1`: their
+    /// state stays the bottom value.
+    ignore_cleanup: bool,
 }
 impl<'a, 'tcx> PcsEngine<'a, 'tcx> {
     pub fn new(cgx: PcsContext<'a, 'tcx>) -> Self {
@@ -94,6 +137,75 @@ impl<'a, 'tcx> PcsEngine<'a, 'tcx> {
             block: Cell::new(START_BLOCK),
             fpcs,
             borrows,
+            visit_hook: None,
+            effect_hook: None,
+            initial_state_hook: None,
+            check_invariants: cfg!(debug_assertions) || validity_checks_enabled(),
+            ignore_cleanup: ignore_cleanup_enabled(),
+        }
+    }
+
+    pub fn set_visit_hook(&mut self, hook: VisitHook<'a, 'tcx>) {
+        self.visit_hook = Some(hook);
+    }
+
+    /// Should be called once the fixpoint is reached, such that the hook is
+    /// not called again when the results are inspected with a cursor.
+    pub fn clear_visit_hook(&mut self) {
+        self.visit_hook = None;
+    }
+
+    /// Replaces the library functions whose calls are understood precisely,
+    /// see [`KnownCalls`].
+    pub fn set_known_calls(&mut self, known_calls: KnownCalls) {
+        self.borrows.set_known_calls(known_calls);
+    }
+
+    /// Replaces [`initial_state`] as the state at the entry of the function,
+    /// e.g. to start with some arguments only partially initialized.
+    pub fn set_initial_state_hook(&mut self, hook: InitialStateHook<'a, 'tcx>) {
+        self.initial_state_hook = Some(hook);
+    }
+
+    /// Panics with the state drawn as a graph if the borrows state after
+    /// the statement (or terminator) at `location` violates an invariant, see
+    /// [`BorrowsState::invariant_violation`].
+    fn assert_invariants(&self, state: &PlaceCapabilitySummary<'a, 'tcx>, location: Location) {
+        if !self.check_invariants {
+            return;
+        }
+        if let Some(violation) = state.borrows.after.invariant_violation(self.cgx.rp) {
+            panic!(
+                "Invalid borrows state at {location:?}: {violation}\n{}",
+                dot_graph(
+                    Rc::new(self.cgx.rp),
+                    &state.fpcs.after,
+                    &state.borrows.after,
+                    &self.cgx.mir.borrow_set
+                )
+            );
+        }
+    }
+
+    /// Whether `block` is a cleanup block that is not analyzed, see
+    /// [`ignore_cleanup_enabled`]. No edges to it are followed, but the
+    /// dataflow engine still visits it once.
+    fn skips(&self, block: BasicBlock) -> bool {
+        self.ignore_cleanup && self.cgx.rp.body()[block].is_cleanup
+    }
+
+    fn visit_block_entry(&mut self, state: &PlaceCapabilitySummary<'a, 'tcx>, location: Location) {
+        if location.statement_index == 0 {
+            if let Some(hook) = &mut self.visit_hook {
+                hook(location.block, state);
+            }
+        }
+    }
+
+    fn visit_effect(&mut self, state: &PlaceCapabilitySummary<'a, 'tcx>, location: Location) {
+        self.assert_invariants(state, location);
+        if let Some(hook) = &mut self.effect_hook {
+            hook(location, state);
         }
     }
 }
@@ -110,8 +222,12 @@ impl<'a, 'tcx> AnalysisDomain<'tcx> for PcsEngine<'a, 'tcx> {
 
     fn initialize_start_block(&self, _body: &Body<'tcx>, state: &mut Self::Domain) {
         self.block.set(START_BLOCK);
-        state.fpcs.initialize_as_start_block();
-        // Initialize borrows if needed
+        let (owned, borrows) = match &self.initial_state_hook {
+            Some(hook) => hook(self.cgx.rp),
+            None => initial_state(self.cgx.rp),
+        };
+        state.fpcs.after = owned;
+        state.borrows.after = borrows;
     }
 }
 
@@ -147,12 +263,20 @@ impl<'a, 'tcx> Analysis<'tcx> for PcsEngine<'a, 'tcx> {
         statement: &Statement<'tcx>,
         location: Location,
     ) {
+        if self.skips(location.block) {
+            return;
+        }
+        self.visit_block_entry(state, location);
         match &statement.kind {
             StatementKind::Assign(box (place, Rvalue::Use(operand))) if let Some(place) = operand.place() => {
                 if let Some(place) = state.borrows.after.reference_targeting_place(place.into(), self.cgx.mir.borrow_set.as_ref()) {
                     if let CapabilityLocal::Allocated(cap) = &mut state.fpcs.after[place.local] {
-                        let related = cap.find_all_related(place, Some(crate::utils::PlaceOrdering::Suffix));
-                        cap.collapse(related.get_from(), place, self.cgx.rp);
+                        let related = cap.find_all_related(place, None);
+                        // The place is not expanded if the reference has
+                        // been retargeted, e.g. in an earlier loop iteration
+                        if related.relation == PlaceOrdering::Suffix {
+                            cap.collapse(related.get_from(), place, self.cgx.rp);
+                        }
                     }
                 }
             }
@@ -171,11 +295,15 @@ impl<'a, 'tcx> Analysis<'tcx> for PcsEngine<'a, 'tcx> {
         statement: &Statement<'tcx>,
         location: Location,
     ) {
+        if self.skips(location.block) {
+            return;
+        }
         self.borrows
             .apply_statement_effect(&mut state.borrows, statement, location);
         self.apply_borrow_actions_to_fpcs(&mut state.fpcs.after, state.borrows.actions(false));
         self.fpcs
             .apply_statement_effect(&mut state.fpcs, statement, location);
+        self.visit_effect(state, location);
     }
     fn apply_before_terminator_effect(
         &mut self,
@@ -183,6 +311,10 @@ impl<'a, 'tcx> Analysis<'tcx> for PcsEngine<'a, 'tcx> {
         terminator: &Terminator<'tcx>,
         location: Location,
     ) {
+        if self.skips(location.block) {
+            return;
+        }
+        self.visit_block_entry(state, location);
         self.borrows
             .apply_before_terminator_effect(&mut state.borrows, terminator, location);
         self.fpcs
@@ -194,11 +326,15 @@ impl<'a, 'tcx> Analysis<'tcx> for PcsEngine<'a, 'tcx> {
         terminator: &'mir Terminator<'tcx>,
         location: Location,
     ) -> TerminatorEdges<'mir, 'tcx> {
+        if self.skips(location.block) {
+            return TerminatorEdges::None;
+        }
         self.borrows
             .apply_terminator_effect(&mut state.borrows, terminator, location);
         self.fpcs
             .apply_terminator_effect(&mut state.fpcs, terminator, location);
-        terminator.edges()
+        self.visit_effect(state, location);
+        real_edges(terminator)
     }
 
     fn apply_call_return_effect(
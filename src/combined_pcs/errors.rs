@@ -0,0 +1,113 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The errors found by the analysis itself, compared against the verdict of
+//! the borrow checker with `--pcs-only-errors`: a place is used while it is
+//! uninitialized, e.g. after it was moved out.
+
+use rustc_interface::middle::mir::{
+    self,
+    visit::{MutatingUseContext, NonMutatingUseContext, PlaceContext, Visitor},
+    Location,
+};
+
+use crate::{
+    free_pcs::{CapabilityKind, CapabilityLocal, CapabilitySummary},
+    rustc_interface,
+    utils::{ignore_cleanup_enabled, Place, PlaceRepacker},
+};
+
+use super::CombinedPcsCursor;
+
+impl<'mir, 'tcx> CombinedPcsCursor<'mir, 'tcx> {
+    /// The uses of uninitialized places, one message per use. A place is
+    /// uninitialized if its local is unallocated, or if it overlaps a place
+    /// with only write capability before the statement (or terminator).
+    ///
+    /// A place is used if it is read or borrowed, or if it is written through
+    /// a dereference, which reads the reference. Drops are not uses, since
+    /// drops of places that may have been moved out are only elaborated
+    /// after borrow checking.
+    ///
+    /// Cleanup blocks are skipped with `PCS_IGNORE_CLEANUP=1`.
+    pub fn use_errors(&mut self) -> Vec<String> {
+        let body = self.body();
+        let mut errors = vec![];
+        for (block, data) in body.basic_blocks.iter_enumerated() {
+            if data.is_cleanup && ignore_cleanup_enabled() {
+                // Not analyzed, see `ignore_cleanup_enabled`
+                continue;
+            }
+            for statement_index in 0..=data.statements.len() {
+                let location = Location {
+                    block,
+                    statement_index,
+                };
+                self.seek(location);
+                let mut uses = Uses(vec![]);
+                match data.statements.get(statement_index) {
+                    Some(statement) => uses.visit_statement(statement, location),
+                    None => uses.visit_terminator(data.terminator(), location),
+                }
+                let rp = self.analysis().cgx.rp;
+                for place in uses.0 {
+                    if let Some(reason) = uninitialized(self.owned_before(), place, rp) {
+                        errors.push(format!("At {location:?}, {place:?} is used, but {reason}"));
+                    }
+                }
+            }
+        }
+        errors
+    }
+}
+
+/// Why `place` is uninitialized in `owned`, if it is.
+fn uninitialized<'tcx>(
+    owned: &CapabilitySummary<'tcx>,
+    place: Place<'tcx>,
+    repacker: PlaceRepacker<'_, 'tcx>,
+) -> Option<String> {
+    match &owned[place.local] {
+        CapabilityLocal::Unallocated => Some(format!("{:?} is unallocated", place.local)),
+        CapabilityLocal::Allocated(projections) => projections
+            .iter()
+            .find(|&(&other, &kind)| {
+                kind == CapabilityKind::Write && other.conflicts_with(place, repacker)
+            })
+            .map(|(other, _)| format!("{other:?} is uninitialized")),
+    }
+}
+
+/// The places used by a statement or terminator.
+struct Uses<'tcx>(Vec<Place<'tcx>>);
+
+impl<'tcx> Visitor<'tcx> for Uses<'tcx> {
+    fn visit_place(&mut self, place: &mir::Place<'tcx>, context: PlaceContext, _: Location) {
+        let place: Place<'tcx> = (*place).into();
+        let read = matches!(
+            context,
+            PlaceContext::NonMutatingUse(
+                NonMutatingUseContext::Copy
+                    | NonMutatingUseContext::Move
+                    | NonMutatingUseContext::Inspect
+                    | NonMutatingUseContext::SharedBorrow
+                    | NonMutatingUseContext::ShallowBorrow
+                    | NonMutatingUseContext::AddressOf
+            ) | PlaceContext::MutatingUse(
+                MutatingUseContext::Borrow | MutatingUseContext::AddressOf
+            )
+        );
+        if read {
+            self.0.push(place);
+        } else if !matches!(context, PlaceContext::MutatingUse(MutatingUseContext::Drop)) {
+            // Writing through a dereference reads the reference
+            if let Some(index) = place.last_deref_index() {
+                self.0
+                    .push(Place::new(place.local, &place.projection[..index]));
+            }
+        }
+    }
+}
@@ -0,0 +1,280 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use rustc_interface::{
+    data_structures::fx::FxHashSet,
+    middle::mir::{Local, Location, TerminatorKind, RETURN_PLACE},
+};
+
+use crate::{
+    borrows::domain::{Borrow, BorrowsState, MaybeOldPlace, RegionAbstraction},
+    rustc_interface,
+    utils::Place,
+};
+
+use super::{dedup_logically_equivalent, CombinedPcsCursor, PcsAction};
+
+/// What the caller observes when the function returns: the memory of the
+/// arguments that the result still borrows, and the borrows that have to end
+/// before returning.
+#[derive(Clone, Debug)]
+pub struct ExitSummary<'tcx> {
+    /// The location of the `Return` terminator.
+    pub location: Location,
+    /// For each argument whose memory (behind a reference) the result
+    /// borrows, the places within the return place that borrow it, e.g. `_0`
+    /// for `fn first<'a>(x: &'a mut (T, T)) -> &'a mut T { &mut x.0 }`. The
+    /// caller must keep these arguments blocked while the result is live.
+    pub blocked_remotes: Vec<(Local, Vec<MaybeOldPlace<'tcx>>)>,
+    /// The borrows live at the `Return` that do not flow into the return
+    /// place, in the order in which they can be ended: a borrow is removed
+    /// only after all borrows that reborrow through it.
+    pub unblock_actions: Vec<PcsAction<'tcx>>,
+}
+
+impl<'tcx> ExitSummary<'tcx> {
+    /// Removes the unblock actions that are logically equivalent to earlier
+    /// ones, e.g. when a borrow of the same place was created on each path to
+    /// the `Return`. See [`dedup_logically_equivalent`] for when this is safe.
+    pub fn dedup_unblock_actions(&mut self) {
+        dedup_logically_equivalent(&mut self.unblock_actions);
+    }
+}
+
+/// Whether `reborrow` borrows memory reached through the place assigned by
+/// `borrow`, e.g. `s = &mut *r` for `r = &mut x`.
+fn reborrows_through<'tcx>(reborrow: &Borrow<'tcx>, borrow: &Borrow<'tcx>) -> bool {
+    borrow
+        .assigned_place
+        .place()
+        .is_strict_prefix_of(reborrow.borrowed_place.place())
+}
+
+/// The borrows in `state` that the return place (transitively) depends on:
+/// those assigned to the return place and those they reborrow through.
+fn escaping_borrows<'a, 'tcx>(state: &'a BorrowsState<'tcx>) -> Vec<&'a Borrow<'tcx>> {
+    let mut escaping: Vec<_> = state
+        .borrows_filtered(|borrow| borrow.assigned_place.place().local == RETURN_PLACE)
+        .collect();
+    let mut worklist = escaping.clone();
+    while let Some(reborrow) = worklist.pop() {
+        for borrow in state.borrows.iter() {
+            if reborrow.borrowed_place.snapshot_location()
+                == borrow.assigned_place.snapshot_location()
+                && reborrows_through(reborrow, borrow)
+                && !escaping.contains(&borrow)
+            {
+                escaping.push(borrow);
+                worklist.push(borrow);
+            }
+        }
+    }
+    escaping
+}
+
+/// The arguments whose memory is (transitively) borrowed by `borrow`, given
+/// all borrows and region abstractions that exist at some point of the body.
+/// The intermediate references of a reborrow chain (e.g. `r` in
+/// `r = &mut (*x).0; &mut *r`) are usually dead by the time the function
+/// returns, which is why the chain is followed through `all_borrows` rather
+/// than the state at the `Return`. A reference returned by a call, e.g.
+/// `index_mut(&mut *v, 0)`, borrows the loans flowing into its abstraction.
+fn remotes_borrowed_by<'tcx>(
+    borrow: &Borrow<'tcx>,
+    all_borrows: &FxHashSet<Borrow<'tcx>>,
+    all_abstractions: &[RegionAbstraction<'tcx>],
+    arg_count: usize,
+) -> FxHashSet<Local> {
+    let mut remotes = FxHashSet::default();
+    let mut visited = FxHashSet::default();
+    let mut worklist = vec![borrow.borrowed_place.place()];
+    while let Some(borrowed) = worklist.pop() {
+        if !visited.insert(borrowed) {
+            continue;
+        }
+        if (1..=arg_count).contains(&borrowed.local.as_usize())
+            && borrowed.last_deref_index().is_some()
+        {
+            remotes.insert(borrowed.local);
+        }
+        worklist.extend(
+            all_borrows
+                .iter()
+                .filter(|borrow| borrow.assigned_place.place().is_strict_prefix_of(borrowed))
+                .map(|borrow| borrow.borrowed_place.place()),
+        );
+        worklist.extend(
+            all_abstractions
+                .iter()
+                .filter(|abstraction| {
+                    abstraction
+                        .loans_out
+                        .iter()
+                        .any(|&loan| Place::from(loan).is_strict_prefix_of(borrowed))
+                })
+                .flat_map(|abstraction| abstraction.loans_in.iter().map(|&loan| Place::from(loan))),
+        );
+    }
+    remotes
+}
+
+/// The actions removing `remaining`, the borrows live at the `Return` at
+/// `location`, each after all borrows that reborrow through it.
+///
+/// Panics if the borrows reborrow through each other in a cycle.
+fn unblock_order<'tcx>(
+    mut remaining: Vec<&Borrow<'tcx>>,
+    location: Location,
+) -> Vec<PcsAction<'tcx>> {
+    let mut unblock_actions = Vec::new();
+    while !remaining.is_empty() {
+        let (unblocked, blocked): (Vec<&Borrow<'tcx>>, Vec<_>) =
+            remaining.iter().partition(|&&borrow| {
+                !remaining.iter().any(|&reborrow| {
+                    reborrow != borrow
+                        && reborrow.borrowed_place.snapshot_location()
+                            == borrow.assigned_place.snapshot_location()
+                        && reborrows_through(reborrow, borrow)
+                })
+            });
+        assert!(
+            !unblocked.is_empty(),
+            "Cyclic reborrows at {location:?}: {blocked:?}"
+        );
+        unblock_actions.extend(
+            unblocked
+                .into_iter()
+                .map(|borrow| PcsAction::RemoveBorrow(borrow.clone())),
+        );
+        remaining = blocked;
+    }
+    unblock_actions
+}
+
+/// Computes the [`ExitSummary`] of the body analysed by `cursor`, or `None` if
+/// the function never returns. MIR building produces at most one `Return`
+/// terminator, so the first one found is used.
+///
+/// A reference local assigned several times may be considered to borrow from
+/// all of the places it was ever assigned a borrow of, so
+/// [`ExitSummary::blocked_remotes`] may over-approximate.
+pub fn exit_summary<'tcx>(cursor: &mut CombinedPcsCursor<'_, 'tcx>) -> Option<ExitSummary<'tcx>> {
+    let body = cursor.body();
+    let (block, data) = body
+        .basic_blocks
+        .iter_enumerated()
+        .find(|(_, data)| matches!(data.terminator().kind, TerminatorKind::Return))?;
+    let location = Location {
+        block,
+        statement_index: data.statements.len(),
+    };
+
+    let mut all_borrows = FxHashSet::default();
+    let mut all_abstractions = Vec::new();
+    for (block, data) in body.basic_blocks.iter_enumerated() {
+        for statement_index in 0..=data.statements.len() {
+            cursor.seek(Location {
+                block,
+                statement_index,
+            });
+            all_borrows.extend(cursor.borrows().borrows.iter().cloned());
+            for abstraction in &cursor.borrows().region_abstractions {
+                if !all_abstractions.contains(abstraction) {
+                    all_abstractions.push(abstraction.clone());
+                }
+            }
+        }
+    }
+
+    cursor.seek(location);
+    let state = cursor.borrows();
+
+    let mut blocked_remotes: Vec<(Local, Vec<MaybeOldPlace<'tcx>>)> = Vec::new();
+    for borrow in
+        state.borrows_filtered(|borrow| borrow.assigned_place.place().local == RETURN_PLACE)
+    {
+        for remote in remotes_borrowed_by(borrow, &all_borrows, &all_abstractions, body.arg_count) {
+            match blocked_remotes.iter_mut().find(|(arg, _)| *arg == remote) {
+                Some((_, places)) => places.push(borrow.assigned_place.clone()),
+                None => blocked_remotes.push((remote, vec![borrow.assigned_place.clone()])),
+            }
+        }
+    }
+    blocked_remotes.sort_by_key(|(arg, _)| *arg);
+    for (_, places) in &mut blocked_remotes {
+        places.sort_by_key(ToString::to_string);
+        places.dedup();
+    }
+
+    let escaping = escaping_borrows(state);
+    let mut remaining: Vec<_> = state
+        .borrows
+        .iter()
+        .filter(|borrow| !escaping.contains(borrow))
+        .collect();
+    remaining.sort_by_key(|borrow| borrow.to_string());
+    let unblock_actions = unblock_order(remaining, location);
+
+    Some(ExitSummary {
+        location,
+        blocked_remotes,
+        unblock_actions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        borrows::domain::BorrowKind,
+        combined_pcs::PcsContext,
+        run_combined_pcs,
+        test_utils::{place, with_body},
+    };
+
+    #[test]
+    fn returned_reference_blocks_the_argument() {
+        let source = "
+            pub fn first<'a, T>(v: &'a mut Vec<T>) -> &'a mut T {
+                &mut v[0]
+            }
+        ";
+        with_body(source, "first", |tcx, mir| {
+            let mut cursor = run_combined_pcs(mir, tcx);
+            let summary = exit_summary(&mut cursor).unwrap();
+            // `_0 = &mut *_2` for the result `_2` of `index_mut(&mut *v, 0)`
+            assert_eq!(
+                summary.blocked_remotes,
+                [(
+                    Local::from_usize(1),
+                    vec![MaybeOldPlace::Current {
+                        place: RETURN_PLACE.into()
+                    }]
+                )]
+            );
+            assert!(summary.unblock_actions.is_empty());
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Cyclic reborrows")]
+    fn cyclic_reborrows_cannot_be_ordered() {
+        let source = "pub fn f(a: &mut &mut u32, b: &mut &mut u32) { **a = **b; }";
+        with_body(source, "f", |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            let borrow = |borrowed, assigned| {
+                Borrow::new(
+                    BorrowKind::PCS,
+                    place(rp, borrowed),
+                    place(rp, assigned),
+                    true,
+                )
+            };
+            let (a, b) = (borrow("*b", "a"), borrow("*a", "b"));
+            unblock_order(vec![&a, &b], Location::START);
+        });
+    }
+}
@@ -0,0 +1,139 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::{Display, Formatter, Result};
+
+use rustc_interface::middle::mir::Location;
+
+use crate::{
+    free_pcs::{CapabilityKind, CapabilityLocal, CapabilitySummary},
+    rustc_interface,
+    utils::Place,
+};
+
+use super::{CombinedPcsCursor, PcsAction};
+
+/// The capability of a place before and after a statement. `None` means that
+/// the place has no capability of its own, e.g. because its local is
+/// unallocated or because it was expanded into its fields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapabilityChange<'tcx> {
+    pub place: Place<'tcx>,
+    pub before: Option<CapabilityKind>,
+    pub after: Option<CapabilityKind>,
+}
+
+impl Display for CapabilityChange<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let kind = |kind: Option<CapabilityKind>| match kind {
+            Some(kind) => kind.to_string(),
+            None => "-".to_string(),
+        };
+        write!(
+            f,
+            "{:?}: {} -> {}",
+            self.place,
+            kind(self.before),
+            kind(self.after)
+        )
+    }
+}
+
+/// How a statement (or terminator) changed the owned capabilities, together
+/// with the actions the analysis took at it, see
+/// [`CombinedPcsCursor::explain_statement`].
+#[derive(Clone, Debug)]
+pub struct StatementEffect<'tcx> {
+    pub location: Location,
+    /// The places whose capability differs before and after the statement,
+    /// ordered by local, e.g. `_1: E -> W` after `_2 = move _1`.
+    pub changes: Vec<CapabilityChange<'tcx>>,
+    pub actions: Vec<PcsAction<'tcx>>,
+}
+
+impl<'tcx> CapabilitySummary<'tcx> {
+    /// The places whose capability in `self` differs from that in `before`.
+    pub fn changes_from(&self, before: &Self) -> Vec<CapabilityChange<'tcx>> {
+        let capabilities = |local: &CapabilityLocal<'tcx>| match local {
+            CapabilityLocal::Unallocated => Vec::new(),
+            CapabilityLocal::Allocated(projections) => projections
+                .iter()
+                .map(|(&place, &kind)| (place, kind))
+                .collect(),
+        };
+        let mut changes = Vec::new();
+        for (before, after) in before.iter().zip(self.iter()) {
+            let before = capabilities(before);
+            let after = capabilities(after);
+            let get = |capabilities: &[(Place<'tcx>, CapabilityKind)], place| {
+                capabilities
+                    .iter()
+                    .find(|&&(other, _)| other == place)
+                    .map(|&(_, kind)| kind)
+            };
+            let mut local_changes: Vec<_> = before
+                .iter()
+                .chain(after.iter())
+                .map(|&(place, _)| CapabilityChange {
+                    place,
+                    before: get(&before, place),
+                    after: get(&after, place),
+                })
+                .filter(|change| change.before != change.after)
+                .collect();
+            local_changes.sort_by_key(|change| format!("{:?}", change.place));
+            local_changes.dedup();
+            changes.extend(local_changes);
+        }
+        changes
+    }
+}
+
+impl<'mir, 'tcx> CombinedPcsCursor<'mir, 'tcx> {
+    /// Explains the effect of the statement (or terminator) at `location` on
+    /// the owned capabilities. Moves the cursor to `location`.
+    pub fn explain_statement(&mut self, location: Location) -> StatementEffect<'tcx> {
+        self.seek(location);
+        StatementEffect {
+            location,
+            changes: self.owned().changes_from(self.owned_before()),
+            actions: self.actions().to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_interface::middle::mir::START_BLOCK;
+
+    use super::*;
+    use crate::{run_combined_pcs, test_utils::with_body};
+
+    #[test]
+    fn move_leaves_write_capability() {
+        let source = "pub fn f(x: String) -> String { let y = x; y }";
+        with_body(source, "f", |tcx, mir| {
+            let mut cursor = run_combined_pcs(mir, tcx);
+            let mut changes = |statement_index| {
+                let location = Location {
+                    block: START_BLOCK,
+                    statement_index,
+                };
+                let effect = cursor.explain_statement(location);
+                assert_eq!(effect.location, location);
+                effect
+                    .changes
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+            };
+            // `_2 = move _1`
+            assert_eq!(changes(1), ["_1: E -> W", "_2: W -> E"]);
+            // `FakeRead(ForLet(None), _2)`
+            assert!(changes(2).is_empty());
+        });
+    }
+}
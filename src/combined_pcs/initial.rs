@@ -0,0 +1,89 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    borrows::{domain::BorrowsState, latest::Latest},
+    free_pcs::CapabilitySummary,
+    utils::PlaceRepacker,
+};
+
+/// Computes the state at the entry of the function, see
+/// [`PcsEngine::set_initial_state_hook`](super::PcsEngine::set_initial_state_hook).
+pub type InitialStateHook<'a, 'tcx> =
+    Box<dyn Fn(PlaceRepacker<'a, 'tcx>) -> (CapabilitySummary<'tcx>, BorrowsState<'tcx>) + 'a>;
+
+/// The state at the entry of the function, used unless a different one is
+/// set with [`PcsEngine::set_initial_state_hook`](super::PcsEngine::set_initial_state_hook).
+///
+/// All arguments are owned: by-value arguments (including `self`) as well as
+/// reference arguments (including `&mut self`) have exclusive capability, see
+/// [`CapabilitySummary::initial`]. The memory that reference arguments point
+/// to belongs to the caller and is not blocked by any borrow in the state;
+/// instead, the places reachable from an argument are those of
+/// [`BorrowsState::places_blocked_by_remote`]. No borrows exist yet and no
+/// place has been written.
+pub fn initial_state<'tcx>(
+    repacker: PlaceRepacker<'_, 'tcx>,
+) -> (CapabilitySummary<'tcx>, BorrowsState<'tcx>) {
+    let borrows = BorrowsState {
+        latest: Latest::new(),
+        ..BorrowsState::new()
+    };
+    (CapabilitySummary::initial(repacker), borrows)
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_interface::{dataflow::Analysis, middle::mir::START_BLOCK};
+
+    use super::*;
+    use crate::{
+        combined_pcs::{PcsContext, PcsEngine},
+        free_pcs::{CapabilityKind, CapabilityLocal},
+        rustc_interface,
+        test_utils::{place, with_body},
+    };
+
+    const ARGS: &str = "pub fn f<T, U>(x: &mut T, y: T, z: &&U) -> T { y }";
+
+    #[test]
+    fn arguments_are_exclusive_and_nothing_is_borrowed() {
+        with_body(ARGS, "f", |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            let (owned, borrows) = initial_state(rp);
+            assert_eq!(owned.to_short_string(rp), "RETURN: W, x: E, y: E, z: E");
+            assert!(borrows.borrows.is_empty());
+            assert!(borrows.region_abstractions.is_empty());
+            for arg in ["x", "y", "z"] {
+                let arg = place(rp, arg);
+                assert_eq!(borrows.latest_of(arg), None);
+                assert!(borrows.places_blocked_by_remote(arg.local, rp).is_empty());
+            }
+        });
+    }
+
+    #[test]
+    fn hook_replaces_the_initial_state() {
+        with_body(ARGS, "f", |tcx, mir| {
+            let mut engine = PcsEngine::new(PcsContext::new(tcx, mir));
+            // `x` is not initialized on entry
+            engine.set_initial_state_hook(Box::new(|rp| {
+                let (mut owned, borrows) = initial_state(rp);
+                let x = place(rp, "x").local;
+                owned[x] = CapabilityLocal::new(x, CapabilityKind::Write);
+                (owned, borrows)
+            }));
+            let rp = PcsContext::new(tcx, mir).rp;
+            let mut cursor = engine
+                .into_engine(tcx, &mir.body)
+                .iterate_to_fixpoint()
+                .into_results_cursor(&mir.body);
+            cursor.seek_to_block_start(START_BLOCK);
+            let owned = &cursor.get().fpcs.after;
+            assert_eq!(owned.to_short_string(rp), "RETURN: W, x: W, y: E, z: E");
+        });
+    }
+}
@@ -4,10 +4,26 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+mod cache;
+mod cursor;
 mod engine;
+mod errors;
+mod exit;
+mod explain;
 mod domain;
+mod initial;
 mod remove;
+mod signature;
+mod validity;
 
+pub use cache::*;
+pub use cursor::*;
 pub use engine::*;
+pub use errors::*;
+pub use exit::*;
+pub use explain::*;
 pub use domain::*;
+pub use initial::*;
 pub use remove::*;
+pub use signature::*;
+pub use validity::*;
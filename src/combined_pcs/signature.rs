@@ -0,0 +1,74 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The lifetimes of the signature of the analyzed function, i.e. its free
+//! regions, as the region variables used by the borrow checker.
+
+use rustc_interface::{
+    hir::def::DefKind,
+    middle::ty::{self, RegionVid, TyCtxt},
+};
+
+use crate::{rustc_interface, visualization::schema};
+
+use super::{BodyWithBorrowckFacts, CombinedPcsCursor};
+
+/// The free regions of the argument and return types of the function of
+/// `mir`, in order of first appearance, together with the region variable
+/// that stands for each in the body. Regions bound inside the signature, e.g.
+/// of `for<'x> fn(&'x u8)` arguments, are not free and are left out.
+///
+/// Only functions and methods have a signature of their own: the lifetimes of
+/// closures and generators are those of the enclosing function, so none are
+/// returned for them.
+pub fn signature_regions<'tcx>(
+    mir: &BodyWithBorrowckFacts<'tcx>,
+    tcx: TyCtxt<'tcx>,
+) -> Vec<(ty::Region<'tcx>, RegionVid)> {
+    let def_id = mir.body.source.def_id();
+    if !matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn) {
+        return vec![];
+    }
+    // Late-bound regions are liberated in the scope of the function, which is
+    // how the borrow checker registers them as universal regions
+    let sig = tcx.liberate_late_bound_regions(def_id, tcx.fn_sig(def_id).instantiate_identity());
+    let mut regions: Vec<ty::Region<'tcx>> = vec![];
+    for ty in sig.inputs_and_output {
+        tcx.for_each_free_region(&ty, |region| {
+            if !regions.contains(&region) {
+                regions.push(region);
+            }
+        });
+    }
+    regions
+        .into_iter()
+        .map(|region| (region, mir.region_inference_context.to_region_vid(region)))
+        .collect()
+}
+
+pub fn signature_regions_to_schema(
+    regions: &[(ty::Region<'_>, RegionVid)],
+) -> schema::SignatureRegions {
+    regions
+        .iter()
+        .map(|(region, vid)| schema::SignatureRegion {
+            region: format!("{vid:?}"),
+            name: region.get_name().map(|name| name.to_string()),
+        })
+        .collect()
+}
+
+impl<'mir, 'tcx> CombinedPcsCursor<'mir, 'tcx> {
+    /// The region variables of the lifetimes in the signature of the analyzed
+    /// function, see [`signature_regions`].
+    pub fn signature_regions(&self) -> Vec<RegionVid> {
+        let cgx = &self.analysis().cgx;
+        signature_regions(cgx.mir, cgx.rp.tcx())
+            .into_iter()
+            .map(|(_, vid)| vid)
+            .collect()
+    }
+}
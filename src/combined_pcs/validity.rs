@@ -0,0 +1,126 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Checks that the owned capabilities and the borrows agree with each other,
+//! so that divergences between the two are reported where they arise.
+
+use rustc_interface::{dataflow::ResultsCursor, middle::mir::Location};
+
+use crate::{
+    borrows::{domain::BorrowsState, loan_check::LoanChecker},
+    free_pcs::{CapabilityKind, CapabilityLocal, CapabilitySummary},
+    rustc_interface,
+    utils::{Place, PlaceRepacker},
+};
+
+use super::{replay_effects, CombinedPcsCursor, PcsEngine};
+
+/// Whether the checks are enabled with `PCS_VALIDITY_CHECKS=1`.
+pub fn validity_checks_enabled() -> bool {
+    std::env::var("PCS_VALIDITY_CHECKS").as_deref() == Ok("1")
+}
+
+impl<'mir, 'tcx> CombinedPcsCursor<'mir, 'tcx> {
+    /// Checks, after every statement and terminator, that the place borrowed
+    /// by each live borrow is initialized: its local is allocated and no
+    /// place conflicting with it (see [`Place::conflicts_with`]) has only
+    /// write capability. Only borrows of places not behind a dereference are
+    /// checked, and only those created from a loan the borrow checker
+    /// considers in scope: the edges of loans that went out of scope are
+    /// only removed once the reference is dead, which may never happen on an
+    /// unwind path, e.g. when the borrowed place is dropped in a cleanup
+    /// block.
+    ///
+    /// Note that the free PCS does not take capabilities away from borrowed
+    /// places, so a borrowed place may well have exclusive capability.
+    pub fn check_validity(&mut self) {
+        let cgx = &self.analysis().cgx;
+        let rp = cgx.rp;
+        let mut loans = LoanChecker::new(rp.tcx(), cgx.mir);
+        let body = self.body();
+        for (block, data) in body.basic_blocks.iter_enumerated() {
+            for statement_index in 0..=data.statements.len() {
+                let location = Location {
+                    block,
+                    statement_index,
+                };
+                self.seek(location);
+                check_validity_at(location, self.owned(), self.borrows(), rp, &mut loans);
+            }
+        }
+    }
+}
+
+/// Runs the checks of [`CombinedPcsCursor::check_validity`] on the results
+/// of an analysis that reached its fixpoint, see [`replay_effects`].
+pub(crate) fn check_validity_of_results<'mir, 'tcx>(
+    cursor: &mut ResultsCursor<'mir, 'tcx, PcsEngine<'mir, 'tcx>>,
+) {
+    let cgx = &cursor.analysis().cgx;
+    let rp = cgx.rp;
+    let mut loans = LoanChecker::new(rp.tcx(), cgx.mir);
+    replay_effects(
+        cursor,
+        Box::new(move |location, state| {
+            check_validity_at(
+                location,
+                &state.fpcs.after,
+                &state.borrows.after,
+                rp,
+                &mut loans,
+            )
+        }),
+    );
+}
+
+/// Panics if a borrow in `borrows` violates the checks of
+/// [`CombinedPcsCursor::check_validity`] at `location`.
+fn check_validity_at<'tcx>(
+    location: Location,
+    owned: &CapabilitySummary<'tcx>,
+    borrows: &BorrowsState<'tcx>,
+    rp: PlaceRepacker<'_, 'tcx>,
+    loans: &mut LoanChecker<'_, 'tcx>,
+) {
+    for borrow in borrows.live_borrows() {
+        let place = borrow.borrowed_place.place();
+        // Memory behind a dereference is not owned by the local, e.g. a
+        // reborrow of `*r` may outlive `r`
+        if place.last_deref_index().is_some() {
+            continue;
+        }
+        if !borrow
+            .loans()
+            .any(|loan| loans.in_scope_after(loan, location))
+        {
+            continue;
+        }
+        let violation = match &owned[place.local] {
+            CapabilityLocal::Unallocated => Some("is unallocated".to_string()),
+            CapabilityLocal::Allocated(projections) => projections
+                .iter()
+                .find(|&(&other, &kind)| {
+                    kind == CapabilityKind::Write && other.conflicts_with(place, rp)
+                })
+                .map(|(other, _)| format!("overlaps {other:?}, which is uninitialized")),
+        };
+        if let Some(violation) = violation {
+            panic!(
+                "At {location:?}, {place:?} borrowed by {borrow} {violation}.\n\
+                Capabilities of {:?}: {:?}\n\
+                Borrows of {:?}: {:?}{}",
+                place.local,
+                owned[place.local],
+                place.local,
+                borrows
+                    .borrows_filtered(|borrow| borrow.borrowed_place.place().local == place.local)
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+                borrow.debug_info.panic_note()
+            );
+        }
+    }
+}
@@ -142,7 +142,8 @@ impl<'tcx> RepackOp<'tcx> {
                     "{self:?} ({curr_state:?})"
                 );
 
-                let (p, others, _) = place.expand_one_level(guide, rp);
+                let (p, others, _) = place.expand_one_level(guide, rp)
+                    .expect("repacks are only emitted for sized places");
                 curr_state.insert(p, kind);
                 curr_state.extend(others.into_iter().map(|p| (p, kind)));
             }
@@ -154,7 +155,8 @@ impl<'tcx> RepackOp<'tcx> {
                     .extract_if(|p, _| place.related_to(*p))
                     .collect::<FxHashMap<_, _>>();
 
-                let (p, mut others, _) = place.expand_one_level(guide, rp);
+                let (p, mut others, _) = place.expand_one_level(guide, rp)
+                    .expect("repacks are only emitted for sized places");
                 others.push(p);
                 for other in others {
                     assert_eq!(removed.remove(&other), Some(kind), "{self:?}");
@@ -173,7 +175,8 @@ impl<'tcx> RepackOp<'tcx> {
                     "{self:?} ({curr_state:?})"
                 );
 
-                let (p, others, pkind) = place.expand_one_level(guide, rp);
+                let (p, others, pkind) = place.expand_one_level(guide, rp)
+                    .expect("repacks are only emitted for sized places");
                 assert!(pkind.is_box());
                 curr_state.insert(p, CapabilityKind::Write);
                 assert!(others.is_empty());
@@ -181,3 +184,50 @@ impl<'tcx> RepackOp<'tcx> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        combined_pcs::PcsContext,
+        free_pcs::CapabilityProjections,
+        run_free_pcs_no_output,
+        test_utils::{place, with_body},
+        utils::ExpansionError,
+    };
+
+    const UNSIZED: &str = "
+        pub trait Shape { fn area(&self) -> u32; }
+        pub fn call_closure(f: &mut dyn FnMut()) { f() }
+        pub fn slice_str(s: &str) -> &str { &s[..1] }
+        pub fn call_method(shape: &dyn Shape) -> u32 { shape.area() }
+        pub fn index_slice(s: &mut [u32]) { s[0] = 1; }
+    ";
+
+    /// `check` replays the repacks of the analysis, and panics if one of
+    /// them expands an unsized place.
+    #[test]
+    fn unsized_places_are_not_expanded() {
+        for function in ["call_closure", "slice_str", "call_method", "index_slice"] {
+            with_body(UNSIZED, function, |tcx, mir| {
+                check(run_free_pcs_no_output(mir, tcx));
+            });
+        }
+    }
+
+    #[test]
+    fn unsized_place_holds_the_capability_of_its_projections() {
+        with_body(UNSIZED, "index_slice", |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            let (s, slice, element) = (place(rp, "s"), place(rp, "*s"), place(rp, "(*s)[_]"));
+            let expanded = slice.expand_one_level(element, rp);
+            assert_eq!(expanded.err(), Some(ExpansionError::Unsized(slice)));
+            let mut projections = CapabilityProjections::new(s.local, CapabilityKind::Exclusive);
+            assert_eq!(projections.capability_place(element, rp), element);
+            projections.remove(&s);
+            projections.insert(slice, CapabilityKind::Exclusive);
+            assert_eq!(projections.capability_place(element, rp), slice);
+            assert_eq!(projections.capability_place(slice, rp), slice);
+        });
+    }
+}
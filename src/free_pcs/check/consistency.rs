@@ -36,7 +36,12 @@ impl<'tcx> CapabilityConsistency<'tcx> for CapabilityProjections<'tcx> {
         let keys = self.keys().copied().collect::<Vec<_>>();
         for (i, p1) in keys.iter().enumerate() {
             for p2 in keys[i + 1..].iter() {
-                assert!(!p1.related_to(*p2), "{p1:?} {p2:?}",);
+                assert!(
+                    !p1.related_to(*p2),
+                    "{p1:?} {p2:?}{}{}",
+                    p1.debug_info().panic_note(),
+                    p2.debug_info().panic_note()
+                );
             }
             // Cannot be inside of uninitialized pointers.
             if !p1.can_deinit(repacker) {
@@ -46,7 +51,7 @@ impl<'tcx> CapabilityConsistency<'tcx> for CapabilityProjections<'tcx> {
             assert!(self[p1] >= CapabilityKind::Write);
             // Can only have `ShallowExclusive` for box typed places
             if self[p1].is_shallow_exclusive() {
-                assert!(p1.ty(repacker).ty.is_box());
+                assert!(repacker.place_ty(*p1).ty.is_box());
             }
         }
         // Can always pack up to the root
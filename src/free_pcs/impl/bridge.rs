@@ -6,7 +6,8 @@
 
 use crate::{
     free_pcs::{
-        CapabilityKind, CapabilityLocal, CapabilityProjections, CapabilitySummary, RepackOp,
+        can_repack, CapabilityKind, CapabilityLocal, CapabilityProjections, CapabilitySummary,
+        RepackOp,
     },
     utils::{PlaceOrdering, PlaceRepacker},
 };
@@ -86,7 +87,10 @@ impl<'tcx> RepackingBridgeSemiLattice<'tcx> for CapabilityProjections<'tcx> {
             // Downgrade the permission if needed
             let curr = from[&place];
             if curr != kind {
-                assert!(curr > kind);
+                assert!(
+                    can_repack(curr, kind),
+                    "Cannot weaken {place:?} from {curr:?} to {kind:?}"
+                );
                 from.insert(place, kind);
                 repacks.push(RepackOp::Weaken(place, curr, kind));
             }
@@ -17,7 +17,7 @@ use rustc_interface::{
 };
 
 use crate::{
-    rustc_interface, utils::PlaceRepacker
+    rustc_interface, utils::{real_edges, PlaceRepacker}
 };
 
 use super::{triple::{Stage, TripleWalker}, FreePlaceCapabilitySummary};
@@ -80,7 +80,7 @@ impl<'a, 'tcx> Analysis<'tcx> for FpcsEngine<'a, 'tcx> {
         TripleWalker::prepare(&mut state.after, self.0, Stage::Main).visit_terminator(terminator, location);
         state.start = state.after.clone();
         TripleWalker::apply(&mut state.after, self.0, Stage::Main).visit_terminator(terminator, location);
-        terminator.edges()
+        real_edges(terminator)
     }
 
     fn apply_call_return_effect(
@@ -15,7 +15,7 @@ use rustc_interface::{
 use crate::{
     free_pcs::{
         CapabilityLocal, CapabilityProjections, RepackOp,
-    }, rustc_interface, utils::PlaceRepacker
+    }, rustc_interface, utils::PlaceRepacker, visualization::schema
 };
 
 use super::{CapabilityKind, RepackingBridgeSemiLattice, engine::FpcsEngine};
@@ -39,25 +39,7 @@ impl<'a, 'tcx> FreePlaceCapabilitySummary<'a, 'tcx> {
         }
     }
     pub fn initialize_as_start_block(&mut self) {
-        let always_live = self.repacker.always_live_locals();
-        let return_local = RETURN_PLACE;
-        let last_arg = Local::new(self.repacker.body().arg_count);
-        for (local, cap) in self.after.iter_enumerated_mut() {
-            let new_cap = if local == return_local {
-                // Return local is allocated but uninitialized
-                CapabilityLocal::new(local, CapabilityKind::Write)
-            } else if local <= last_arg {
-                // Arguments are allocated and initialized
-                CapabilityLocal::new(local, CapabilityKind::Exclusive)
-            } else if always_live.contains(local) {
-                // Always live locals start allocated but uninitialized
-                CapabilityLocal::new(local, CapabilityKind::Write)
-            } else {
-                // Other locals are unallocated
-                CapabilityLocal::Unallocated
-            };
-            *cap = new_cap;
-        }
+        self.after = CapabilitySummary::initial(self.repacker);
     }
 
     pub fn repack_ops(&self, previous: &CapabilitySummary<'tcx>) -> (Vec<RepackOp<'tcx>>, Vec<RepackOp<'tcx>>) {
@@ -130,6 +112,66 @@ impl<'tcx> CapabilitySummary<'tcx> {
     pub fn empty() -> Self {
         Self(IndexVec::new())
     }
+
+    /// The capabilities at the entry of the function: arguments are owned
+    /// (exclusive), the return place and the always live locals are
+    /// allocated but uninitialized (write), and all other locals are
+    /// unallocated.
+    pub fn initial(repacker: PlaceRepacker<'_, 'tcx>) -> Self {
+        let mut summary = Self::default(repacker.local_count());
+        let always_live = repacker.always_live_locals();
+        let return_local = RETURN_PLACE;
+        let last_arg = Local::new(repacker.body().arg_count);
+        for (local, cap) in summary.iter_enumerated_mut() {
+            let new_cap = if local == return_local {
+                // Return local is allocated but uninitialized
+                CapabilityLocal::new(local, CapabilityKind::Write)
+            } else if local <= last_arg {
+                // Arguments are allocated and initialized
+                CapabilityLocal::new(local, CapabilityKind::Exclusive)
+            } else if always_live.contains(local) {
+                // Always live locals start allocated but uninitialized
+                CapabilityLocal::new(local, CapabilityKind::Write)
+            } else {
+                // Other locals are unallocated
+                CapabilityLocal::Unallocated
+            };
+            *cap = new_cap;
+        }
+        summary
+    }
+
+    /// Renders the capabilities of all allocated places, ordered by local,
+    /// e.g. `x: E, _2.0: W`.
+    pub fn to_short_string(&self, repacker: PlaceRepacker<'_, 'tcx>) -> String {
+        self.iter()
+            .flat_map(|local| {
+                let mut places: Vec<_> = match local {
+                    CapabilityLocal::Unallocated => vec![],
+                    CapabilityLocal::Allocated(projections) => projections
+                        .iter()
+                        .map(|(place, kind)| {
+                            format!("{}: {}", place.to_short_string(repacker), kind)
+                        })
+                        .collect(),
+                };
+                places.sort();
+                places
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn to_schema(&self, repacker: PlaceRepacker<'_, 'tcx>) -> schema::Capabilities {
+        self.iter()
+            .flat_map(|local| match local {
+                CapabilityLocal::Unallocated => None,
+                CapabilityLocal::Allocated(projections) => Some(projections.iter()),
+            })
+            .flatten()
+            .map(|(place, kind)| (place.to_short_string(repacker), kind.to_string()))
+            .collect()
+    }
 }
 
 struct CapabilitySummaryCompare<'a, 'tcx>(&'a CapabilitySummary<'tcx>, &'a CapabilitySummary<'tcx>, &'a str);
@@ -194,3 +236,27 @@ impl Debug for CapabilitySummaryCompare<'_, '_> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        combined_pcs::PcsContext,
+        test_utils::{place, with_body},
+    };
+
+    #[test]
+    fn short_string_lists_allocated_places_by_local() {
+        let source = "pub fn f(x: (u32, u32), y: &mut u32) -> u32 { *y = x.1; x.0 }";
+        let rendered = with_body(source, "f", |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            let mut summary = CapabilitySummary::initial(rp);
+            let x = summary[place(rp, "x").local].get_allocated_mut();
+            x.clear();
+            x.insert(place(rp, "x.1"), CapabilityKind::Write);
+            x.insert(place(rp, "x.0"), CapabilityKind::ShallowExclusive);
+            summary.to_short_string(rp)
+        });
+        assert_eq!(rendered, "RETURN: W, x.0: e, x.1: W, y: E");
+    }
+}
@@ -13,7 +13,7 @@ use rustc_interface::{
 };
 
 use crate::{
-    free_pcs::{CapabilityKind, RelatedSet, RepackOp}, rustc_interface, utils::{Place, PlaceOrdering, PlaceRepacker}
+    free_pcs::{can_repack, CapabilityKind, RelatedSet, RepackOp}, rustc_interface, utils::{Place, PlaceOrdering, PlaceRepacker}
 };
 
 #[derive(Clone, PartialEq, Eq)]
@@ -81,6 +81,24 @@ impl<'tcx> CapabilityProjections<'tcx> {
         self.iter().next().unwrap().0.local
     }
 
+    /// The place holding the capability for `place` after `place` has been
+    /// repacked to: either `place` itself or, if `place` is a projection of an
+    /// unsized place that is kept packed, that unsized place.
+    pub(crate) fn capability_place(
+        &self,
+        place: Place<'tcx>,
+        repacker: PlaceRepacker<'_, 'tcx>,
+    ) -> Place<'tcx> {
+        if self.contains_key(&place) {
+            return place;
+        }
+        (0..place.projection.len())
+            .rev()
+            .map(|len| Place::new(place.local, &place.projection[..len]))
+            .find(|prefix| self.contains_key(prefix) && prefix.is_unsized(repacker))
+            .unwrap_or(place)
+    }
+
     pub(crate) fn update_cap(&mut self, place: Place<'tcx>, cap: CapabilityKind) {
         let old = self.insert(place, cap);
         // assert!(old.is_some());
@@ -139,13 +157,15 @@ impl<'tcx> CapabilityProjections<'tcx> {
         debug_assert!(!self.contains_key(&to));
         let (expanded, mut others) = from.expand(to, repacker);
         let mut perm = self.remove(&from).unwrap();
-        others.push(to);
+        // If the expansion stopped at an unsized place, the capability stays there
+        others.push(expanded.last().map_or(from, |(_, to, _)| *to));
         let mut ops = Vec::new();
         for (from, to, kind) in expanded {
             let others = others.extract_if(|other| !to.is_prefix(*other));
             self.extend(others.map(|p| (p, perm)));
             if kind.is_box() && perm.is_shallow_exclusive() {
                 ops.push(RepackOp::DerefShallowInit(from, to));
+                debug_assert!(can_repack(perm, CapabilityKind::Write));
                 perm = CapabilityKind::Write;
             } else {
                 ops.push(RepackOp::Expand(from, to, perm));
@@ -201,7 +221,7 @@ impl<'tcx> CapabilityProjections<'tcx> {
                 });
             for (from, from_perm) in removed_perms {
                 if perm != from_perm {
-                    assert!(from_perm > perm);
+                    assert!(can_repack(from_perm, perm));
                     ops.push(RepackOp::Weaken(from, from_perm, perm));
                 }
             }
@@ -6,7 +6,7 @@
 
 use std::{
     cmp::Ordering,
-    fmt::{Debug, Formatter, Result},
+    fmt::{Debug, Display, Formatter, Result},
 };
 
 use rustc_interface::data_structures::fx::FxHashSet;
@@ -55,6 +55,12 @@ impl Debug for CapabilityKind {
     }
 }
 
+impl Display for CapabilityKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        Debug::fmt(self, f)
+    }
+}
+
 impl PartialOrd for CapabilityKind {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         if *self == *other {
@@ -89,3 +95,43 @@ impl CapabilityKind {
         }
     }
 }
+
+/// Whether a place holding capability `from` may be repacked such that the
+/// resulting place(s) hold capability `to`. Repacking can only weaken a
+/// capability (`W < e < E`): e.g. a `Write` cannot be unpacked into fields
+/// with `Exclusive` capability, since their values may be uninitialized.
+pub fn can_repack(from: CapabilityKind, to: CapabilityKind) -> bool {
+    from >= to
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CapabilityKind::*;
+
+    #[test]
+    fn kinds_are_displayed_as_letters() {
+        assert_eq!(format!("{Exclusive} {ShallowExclusive} {Write}"), "E e W");
+    }
+
+    #[test]
+    fn repacking_only_weakens() {
+        for (from, to) in [
+            (Exclusive, Exclusive),
+            (Exclusive, ShallowExclusive),
+            (Exclusive, Write),
+            (ShallowExclusive, ShallowExclusive),
+            (ShallowExclusive, Write),
+            (Write, Write),
+        ] {
+            assert!(can_repack(from, to), "{from} -> {to} should be permitted");
+        }
+        for (from, to) in [
+            (Write, Exclusive),
+            (Write, ShallowExclusive),
+            (ShallowExclusive, Exclusive),
+        ] {
+            assert!(!can_repack(from, to), "{from} -> {to} should be forbidden");
+        }
+    }
+}
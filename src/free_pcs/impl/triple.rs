@@ -5,14 +5,14 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use rustc_interface::middle::mir::{
-    visit::Visitor, Local, Location, Operand, Rvalue, Statement, StatementKind, Terminator,
-    TerminatorKind, RETURN_PLACE,
+    visit::Visitor, BorrowKind, Local, Location, Operand, Rvalue, Statement, StatementKind,
+    Terminator, TerminatorKind, RETURN_PLACE,
 };
 
 use crate::{
     free_pcs::CapabilityKind,
-    rustc_interface,
-    utils::{Place, PlaceRepacker},
+    rustc_interface, unsupported,
+    utils::{operand_effect, Place, PlaceRepacker, UseKind},
 };
 
 use super::CapabilitySummary;
@@ -90,30 +90,44 @@ impl<'a, 'b, 'tcx> TripleWalker<'a, 'b, 'tcx> {
         if stage != self.stage {
             return;
         }
+        let t = self.untracked_as_pointer_read(t);
         if self.preparing {
             self.summary.requires(t.pre, self.repacker);
         } else {
             self.summary.ensures(t, self.repacker);
         }
     }
+    /// Accessing a place behind a raw pointer (see
+    /// [`Place::raw_pointer_base`]) only reads the pointer: the place itself
+    /// is untracked, so it is neither expanded to nor given a capability.
+    fn untracked_as_pointer_read(&self, t: Triple<'tcx>) -> Triple<'tcx> {
+        if let Condition::Capability(place, _) = t.pre {
+            if let Some(pointer) = place.raw_pointer_base(self.repacker) {
+                return Triple {
+                    pre: Condition::Capability(pointer, CapabilityKind::Exclusive),
+                    post: Condition::Unchanged,
+                };
+            }
+        }
+        t
+    }
 }
 
 impl<'tcx> Visitor<'tcx> for TripleWalker<'_, '_, 'tcx> {
     fn visit_operand(&mut self, operand: &Operand<'tcx>, location: Location) {
         self.super_operand(operand, location);
-        let t = match *operand {
-            Operand::Copy(place) => {
-                let place: Place<'tcx> = place.into();
-                Triple {
-                    pre: Condition::Capability(place, CapabilityKind::Exclusive),
-                    post: Condition::Unchanged
-                }
-            }
-            Operand::Move(place) => Triple {
-                pre: Condition::Capability(place.into(), CapabilityKind::Exclusive),
-                post: Condition::Capability(place.into(), CapabilityKind::Write),
+        let Some((place, kind)) = operand_effect(operand, self.repacker) else {
+            return;
+        };
+        let t = match kind {
+            UseKind::Copy => Triple {
+                pre: Condition::Capability(place, CapabilityKind::Exclusive),
+                post: Condition::Unchanged,
+            },
+            UseKind::Move => Triple {
+                pre: Condition::Capability(place, CapabilityKind::Exclusive),
+                post: Condition::Capability(place, CapabilityKind::Write),
             },
-            Operand::Constant(..) => return,
         };
         self.triple(Stage::Before, t)
     }
@@ -133,6 +147,12 @@ impl<'tcx> Visitor<'tcx> for TripleWalker<'_, '_, 'tcx> {
             | Aggregate(_, _)
             | ShallowInitBox(_, _) => {}
 
+            // A shallow borrow of a match scrutinee only prevents it from
+            // being moved or mutated while the guard is evaluated, which is
+            // already ruled out by the borrow checker: it does not need the
+            // place to be collapsed.
+            Ref(_, BorrowKind::Shallow, _) => {}
+
             &Ref(_, _, place)
             | &AddressOf(_, place)
             | &Len(place)
@@ -229,6 +249,8 @@ impl<'tcx> Visitor<'tcx> for TripleWalker<'_, '_, 'tcx> {
                 pre: Condition::Capability(place.into(), CapabilityKind::Write),
                 post: Condition::Capability(place.into(), CapabilityKind::Write),
             },
+            // A diverging call never writes its destination
+            Call { target: None, .. } => return,
             &Call { destination, .. } => Triple {
                 pre: Condition::Capability(destination.into(), CapabilityKind::Write),
                 post: Condition::Capability(destination.into(), CapabilityKind::Exclusive),
@@ -237,7 +259,10 @@ impl<'tcx> Visitor<'tcx> for TripleWalker<'_, '_, 'tcx> {
                 pre: Condition::Capability(resume_arg.into(), CapabilityKind::Write),
                 post: Condition::Capability(resume_arg.into(), CapabilityKind::Exclusive),
             },
-            InlineAsm { .. } => todo!("{terminator:?}"),
+            InlineAsm { .. } => {
+                unsupported!("TerminatorKind::InlineAsm");
+                return;
+            }
         };
         self.triple(Stage::Main, t);
     }
@@ -32,6 +32,7 @@ impl<'tcx> CapabilitySummary<'tcx> {
             Condition::Capability(place, cap) => {
                 let cp = self[place.local].get_allocated_mut();
                 cp.repack(place, repacker);
+                let place = cp.capability_place(place, repacker);
                 if cp[&place] > cap {
                     // Requires write should deinit an exclusive
                     cp.insert(place, cap);
@@ -76,7 +77,8 @@ impl<'tcx> CapabilitySummary<'tcx> {
                 self[*local] = CapabilityLocal::Allocated(CapabilityProjections::new_uninit(*local));
             }
             Condition::Capability(place, cap) => {
-                self[place.local].get_allocated_mut().update_cap(*place, *cap);
+                let cp = self[place.local].get_allocated_mut();
+                cp.update_cap(cp.capability_place(*place, repacker), *cap);
             }
         }
     }
@@ -9,14 +9,15 @@ use rustc_interface::{
     dataflow::ResultsCursor,
     middle::{
         ty::RegionVid,
-        mir::{BasicBlock, Body, Location, Local},
+        mir::{BasicBlock, Body, Location, Local, Mutability, TerminatorKind},
     },
 };
 
 use crate::{
+    borrows::{domain::Borrow, engine::BorrowsDomain},
     combined_pcs::{PcsContext, PcsEngine, PlaceCapabilitySummary}, free_pcs::{
-        engine::FpcsEngine, CapabilitySummary, FreePlaceCapabilitySummary, RepackOp, RepackingBridgeSemiLattice
-    }, rustc_interface, utils::PlaceRepacker
+        engine::FpcsEngine, CapabilityKind, CapabilityLocal, CapabilitySummary, FreePlaceCapabilitySummary, RepackOp, RepackingBridgeSemiLattice
+    }, rustc_interface, utils::{real_successors, Place, PlaceRepacker}
 };
 
 pub trait HasFpcs<'mir, 'tcx> {
@@ -123,9 +124,7 @@ impl<'mir, 'tcx, T, D: HasFpcs<'mir, 'tcx>+ HasExtra<T>, E: Analysis<'tcx, Domai
         let rp: PlaceRepacker = self.repacker();
         let state = self.cursor.get().get_curr_fpcs().clone();
         let block = &self.body()[location.block];
-        let succs = block
-            .terminator()
-            .successors()
+        let succs = real_successors(block.terminator())
             .map(|succ| {
                 // Get repacks
                 let entry_set = self.cursor.results().entry_set_for_block(succ);
@@ -162,6 +161,126 @@ impl<'mir, 'tcx, T, D: HasFpcs<'mir, 'tcx>+ HasExtra<T>, E: Analysis<'tcx, Domai
     }
 }
 
+impl<'mir, 'tcx, D: HasFpcs<'mir, 'tcx> + HasExtra<BorrowsDomain<'tcx>>, E: Analysis<'tcx, Domain = D>>
+    FreePcsAnalysis<'mir, 'tcx, BorrowsDomain<'tcx>, D, E>
+{
+    /// The borrows whose borrowed and assigned places are both current after
+    /// the statement (or terminator) at `location` has been executed.
+    /// Interrupts any iteration started with `analysis_for_bb`.
+    pub fn borrows_live_at(&mut self, location: Location) -> Vec<Borrow<'tcx>> {
+        self.curr_stmt = None;
+        self.end_stmt = None;
+        self.cursor.seek_after_primary_effect(location);
+        self.live_borrows()
+    }
+
+    /// Like [`Self::borrows_live_at`], but at the entry of `block`, before
+    /// its first statement is executed.
+    pub fn borrows_live_at_block_start(&mut self, block: BasicBlock) -> Vec<Borrow<'tcx>> {
+        self.curr_stmt = None;
+        self.end_stmt = None;
+        self.cursor.seek_to_block_start(block);
+        self.live_borrows()
+    }
+
+    /// The borrows (current or not) that remain in the state at any `Return`
+    /// of the function, i.e. that are never terminated. These either escape
+    /// through the result or arguments, or indicate imprecision of the
+    /// analysis. Interrupts any iteration started with `analysis_for_bb`.
+    pub fn unterminated_at_exit(&mut self) -> Vec<Borrow<'tcx>> {
+        self.curr_stmt = None;
+        self.end_stmt = None;
+        let body = self.cursor.body();
+        let returns: Vec<_> = body
+            .basic_blocks
+            .iter_enumerated()
+            .filter(|(_, data)| matches!(data.terminator().kind, TerminatorKind::Return))
+            .map(|(block, _)| body.terminator_loc(block))
+            .collect();
+        let mut unterminated = Vec::new();
+        for location in returns {
+            self.cursor.seek_after_primary_effect(location);
+            for borrow in &self.cursor.get().get_extra().after.borrows {
+                if !unterminated.contains(borrow) {
+                    unterminated.push(borrow.clone());
+                }
+            }
+        }
+        unterminated
+    }
+
+    fn live_borrows(&self) -> Vec<Borrow<'tcx>> {
+        self.cursor.get().get_extra().after.live_borrows().cloned().collect()
+    }
+
+    /// The places that can be read after the statement (or terminator) at
+    /// `location`, see [`can_access`]. Interrupts any iteration started with
+    /// `analysis_for_bb`.
+    pub fn readable_places(&mut self, location: Location) -> Vec<Place<'tcx>> {
+        self.places_with_access(location, Access::Read)
+    }
+
+    /// The places that can be written after the statement (or terminator)
+    /// at `location`, see [`can_access`]. Interrupts any iteration started
+    /// with `analysis_for_bb`.
+    pub fn writable_places(&mut self, location: Location) -> Vec<Place<'tcx>> {
+        self.places_with_access(location, Access::Write)
+    }
+
+    fn places_with_access(&mut self, location: Location, access: Access) -> Vec<Place<'tcx>> {
+        self.curr_stmt = None;
+        self.end_stmt = None;
+        self.cursor.seek_after_primary_effect(location);
+        let repacker = self.repacker();
+        let borrows = self.live_borrows();
+        let mut places = vec![];
+        for local in self.cursor.get().get_curr_fpcs().after.iter() {
+            let CapabilityLocal::Allocated(projections) = local else {
+                continue;
+            };
+            for (&place, &kind) in projections.iter() {
+                let borrowed = borrows
+                    .iter()
+                    .filter(|borrow| borrow.borrowed_place.place().conflicts_with(place, repacker))
+                    .map(|borrow| {
+                        if borrow.is_mut {
+                            Mutability::Mut
+                        } else {
+                            Mutability::Not
+                        }
+                    })
+                    .max();
+                if can_access(kind, borrowed, access) {
+                    places.push(place);
+                }
+            }
+        }
+        places.sort_by_key(|place| place.to_short_string(repacker));
+        places
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// Whether a place with capability `kind` can be accessed, where `borrowed`
+/// is the strongest live borrow of a conflicting place, if any. Reading
+/// requires an initialized place ([`CapabilityKind::Exclusive`] or
+/// [`CapabilityKind::ShallowExclusive`]), writing any capability. A shared
+/// borrow leaves the place readable but not writable; a mutable borrow makes
+/// it neither.
+pub fn can_access(kind: CapabilityKind, borrowed: Option<Mutability>, access: Access) -> bool {
+    match (access, borrowed) {
+        (_, Some(Mutability::Mut)) => false,
+        (Access::Write, Some(Mutability::Not)) => false,
+        (Access::Write, None) => true,
+        (Access::Read, _) => kind != CapabilityKind::Write,
+    }
+}
+
 pub struct FreePcsBasicBlock<'tcx, T> {
     pub statements: Vec<FreePcsLocation<'tcx, T>>,
     pub terminator: FreePcsTerminator<'tcx, T>,
@@ -183,3 +302,132 @@ pub struct FreePcsLocation<'tcx, T> {
 pub struct FreePcsTerminator<'tcx, T> {
     pub succs: Vec<FreePcsLocation<'tcx, T>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use rustc_interface::middle::mir::{Rvalue, StatementKind};
+
+    use super::*;
+    use crate::{run_free_pcs_no_output, test_utils::with_body};
+
+    const BORROW_IN_BRANCH: &str = "
+        pub fn f(b: bool) -> u32 {
+            let mut x = 1;
+            let r = &mut x;
+            *r += 1;
+            if b { *r += 2; }
+            x
+        }
+    ";
+
+    /// Renders each borrow as `assigned -> borrowed`.
+    fn render<'tcx>(borrows: Vec<Borrow<'tcx>>, rp: PlaceRepacker<'_, 'tcx>) -> Vec<String> {
+        borrows
+            .iter()
+            .map(|borrow| {
+                format!(
+                    "{} -> {}",
+                    borrow.assigned_place.place().to_short_string(rp),
+                    borrow.borrowed_place.place().to_short_string(rp)
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn borrow_is_live_across_statements_and_blocks() {
+        with_body(BORROW_IN_BRANCH, "f", |tcx, mir| {
+            let mut analysis = run_free_pcs_no_output(mir, tcx);
+            let rp = analysis.repacker();
+            let live = |borrows| render(borrows, rp);
+            let body = &mir.body;
+            let borrow = body
+                .basic_blocks
+                .iter_enumerated()
+                .find_map(|(block, data)| {
+                    let statement_index = data.statements.iter().position(|stmt| {
+                        matches!(&stmt.kind, StatementKind::Assign(box (_, Rvalue::Ref(..))))
+                    })?;
+                    Some(Location {
+                        block,
+                        statement_index,
+                    })
+                })
+                .unwrap();
+            let next = borrow.successor_within_block();
+            assert_eq!(live(analysis.borrows_live_at(borrow)), ["r -> x"]);
+            assert_eq!(live(analysis.borrows_live_at(next)), ["r -> x"]);
+
+            let then_block = body
+                .basic_blocks
+                .iter()
+                .find_map(|data| match &data.terminator().kind {
+                    TerminatorKind::SwitchInt { targets, .. } => Some(targets.otherwise()),
+                    _ => None,
+                })
+                .unwrap();
+            let entry = analysis.borrows_live_at_block_start(then_block);
+            assert_eq!(live(entry), ["r -> x"]);
+            let return_block = body
+                .basic_blocks
+                .iter_enumerated()
+                .find(|(_, data)| matches!(data.terminator().kind, TerminatorKind::Return))
+                .unwrap()
+                .0;
+            // `x` is read after the last use of `r`
+            let exit = body.terminator_loc(return_block);
+            assert_eq!(live(analysis.borrows_live_at(exit)), Vec::<String>::new());
+        });
+    }
+
+    /// The borrows of `function` in `source` that are never terminated.
+    fn unterminated(source: &str, function: &str) -> Vec<String> {
+        with_body(source, function, |tcx, mir| {
+            let mut analysis = run_free_pcs_no_output(mir, tcx);
+            let rp = analysis.repacker();
+            render(analysis.unterminated_at_exit(), rp)
+        })
+    }
+
+    #[test]
+    fn leaked_borrows_are_unterminated() {
+        let source = "
+            pub fn returns_all(x: &mut u32) -> u32 {
+                let r = &mut *x;
+                *r += 1;
+                *x
+            }
+            pub fn leaks<'a>(x: &'a mut u32, y: &'a mut u32, out: &mut &'a mut u32) -> u32 {
+                *out = &mut *x;
+                *y
+            }
+        ";
+        assert_eq!(unterminated(source, "returns_all"), Vec::<String>::new());
+        // The reborrow of `x` escapes through `out`
+        let leaked = unterminated(source, "leaks");
+        assert_eq!(leaked.len(), 1);
+        assert!(leaked[0].starts_with("*out -> "), "{leaked:?}");
+    }
+
+    #[test]
+    fn shared_borrowed_places_are_only_readable() {
+        // `let x = 1; let r = &x;`: `x` is shared borrowed
+        let shared = Some(Mutability::Not);
+        assert!(can_access(CapabilityKind::Exclusive, shared, Access::Read));
+        assert!(!can_access(CapabilityKind::Exclusive, shared, Access::Write));
+        // `let mut y = 2;`: `y` is exclusive and not borrowed
+        assert!(can_access(CapabilityKind::Exclusive, None, Access::Read));
+        assert!(can_access(CapabilityKind::Exclusive, None, Access::Write));
+    }
+
+    #[test]
+    fn mutably_borrowed_and_uninitialized_places() {
+        let mutable = Some(Mutability::Mut);
+        for access in [Access::Read, Access::Write] {
+            assert!(!can_access(CapabilityKind::Exclusive, mutable, access));
+        }
+        assert!(!can_access(CapabilityKind::Write, None, Access::Read));
+        assert!(can_access(CapabilityKind::Write, None, Access::Write));
+        assert!(can_access(CapabilityKind::ShallowExclusive, None, Access::Read));
+    }
+}
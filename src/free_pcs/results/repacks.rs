@@ -8,7 +8,12 @@ use std::fmt::{Display, Formatter, Result};
 
 use rustc_interface::middle::mir::Local;
 
-use crate::{free_pcs::CapabilityKind, rustc_interface, utils::Place};
+use crate::{
+    free_pcs::CapabilityKind,
+    rustc_interface,
+    utils::{Place, PlaceRepacker},
+    visualization::schema,
+};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum RepackOp<'tcx> {
@@ -74,6 +79,36 @@ impl Display for RepackOp<'_> {
 }
 
 impl<'tcx> RepackOp<'tcx> {
+    pub fn to_schema(&self, repacker: PlaceRepacker<'_, 'tcx>) -> schema::PcsAction {
+        match *self {
+            RepackOp::StorageDead(local) => schema::PcsAction::StorageDead {
+                local: local.as_usize(),
+            },
+            RepackOp::IgnoreStorageDead(local) => schema::PcsAction::IgnoreStorageDead {
+                local: local.as_usize(),
+            },
+            RepackOp::Weaken(place, from, to) => schema::PcsAction::Weaken {
+                place: place.to_schema(repacker),
+                from: from.to_string(),
+                to: to.to_string(),
+            },
+            RepackOp::Expand(place, guide, capability) => schema::PcsAction::Expand {
+                place: place.to_schema(repacker),
+                guide: guide.to_schema(repacker),
+                capability: capability.to_string(),
+            },
+            RepackOp::Collapse(place, guide, capability) => schema::PcsAction::Collapse {
+                place: place.to_schema(repacker),
+                guide: guide.to_schema(repacker),
+                capability: capability.to_string(),
+            },
+            RepackOp::DerefShallowInit(place, guide) => schema::PcsAction::DerefShallowInit {
+                place: place.to_schema(repacker),
+                guide: guide.to_schema(repacker),
+            },
+        }
+    }
+
     pub fn affected_place(&self) -> Place<'tcx> {
         match *self {
             RepackOp::StorageDead(local)
@@ -17,22 +17,41 @@ pub mod rustc_interface;
 pub mod utils;
 pub mod visualization;
 
-use std::{fs::create_dir_all, rc::Rc};
+#[cfg(test)]
+mod test_utils;
 
-use borrows::{domain::BorrowsState, engine::BorrowsDomain};
-use combined_pcs::{BodyWithBorrowckFacts, PcsContext, PcsEngine, PlaceCapabilitySummary};
+use std::{collections::BTreeMap, fs::create_dir_all, rc::Rc};
+
+use borrows::{domain::BorrowsState, engine::BorrowsDomain, loan_check::LoanChecker};
+use combined_pcs::{
+    check_validity_of_results, pcs_actions, signature_regions, signature_regions_to_schema,
+    validity_checks_enabled, BodyWithBorrowckFacts, CombinedPcsCursor, PcsContext, PcsEngine,
+    PlaceCapabilitySummary,
+};
 use free_pcs::HasExtra;
 use rustc_interface::{
-    dataflow::Analysis,
+    dataflow::{Analysis, ResultsCursor},
     index::IndexVec,
     middle::{
         mir::{Body, Promoted, START_BLOCK},
         ty::TyCtxt,
     },
 };
-use visualization::mir_graph::generate_json_from_mir;
+use utils::{places_in_statement, places_in_terminator};
+use visualization::mir_graph::{mir_graph, write_mir_graph};
 
-use crate::visualization::generate_dot_graph;
+use crate::visualization::{
+    fixpoint_debug::{
+        block_visits_to_schema, debug_block_from_env, max_block_visits_from_env,
+        BlockVisitCounter, BlockVisitDumper,
+    },
+    generate_dot_graph,
+    heatmap::block_heat,
+    html::generate_html_report,
+    options::output_options,
+    place_status::place_statuses,
+    schema, write_json,
+};
 
 pub type FpcsOutput<'mir, 'tcx> = free_pcs::FreePcsAnalysis<
     'mir,
@@ -48,37 +67,125 @@ impl<'mir, 'tcx> HasExtra<BorrowsDomain<'tcx>> for PlaceCapabilitySummary<'mir,
     }
 }
 
-pub fn run_free_pcs<'mir, 'tcx>(
+/// Runs only the analysis: unlike [`run_free_pcs`] with no output path,
+/// this neither honours `PCS_DEBUG_BLOCK` nor renders anything, which makes
+/// it suitable for measuring the cost of the analysis itself.
+pub fn run_free_pcs_no_output<'mir, 'tcx>(
     mir: &'mir BodyWithBorrowckFacts<'tcx>,
     tcx: TyCtxt<'tcx>,
-    visualization_output_path: Option<&str>,
 ) -> FpcsOutput<'mir, 'tcx> {
-    let cgx = PcsContext::new(tcx, mir);
-    let fpcs = PcsEngine::new(cgx);
-    let analysis = fpcs
+    let engine = PcsEngine::new(PcsContext::new(tcx, mir));
+    free_pcs::FreePcsAnalysis::new(iterate_to_fixpoint(engine, mir, tcx))
+}
+
+/// Like [`run_free_pcs_no_output`], but returns a cursor giving access to
+/// both the owned places and the borrows at each location.
+pub fn run_combined_pcs<'mir, 'tcx>(
+    mir: &'mir BodyWithBorrowckFacts<'tcx>,
+    tcx: TyCtxt<'tcx>,
+) -> CombinedPcsCursor<'mir, 'tcx> {
+    let engine = PcsEngine::new(PcsContext::new(tcx, mir));
+    CombinedPcsCursor::new(iterate_to_fixpoint(engine, mir, tcx))
+}
+
+/// Runs `engine` on the body of `mir` until its states reach a fixpoint.
+fn iterate_to_fixpoint<'mir, 'tcx>(
+    engine: PcsEngine<'mir, 'tcx>,
+    mir: &'mir BodyWithBorrowckFacts<'tcx>,
+    tcx: TyCtxt<'tcx>,
+) -> ResultsCursor<'mir, 'tcx, PcsEngine<'mir, 'tcx>> {
+    engine
         .into_engine(tcx, &mir.body)
         .pass_name("free_pcs")
-        .iterate_to_fixpoint();
-    let mut fpcs_analysis = free_pcs::FreePcsAnalysis::new(analysis.into_results_cursor(&mir.body));
+        .iterate_to_fixpoint()
+        .into_results_cursor(&mir.body)
+}
 
+pub fn run_free_pcs<'mir, 'tcx>(
+    mir: &'mir BodyWithBorrowckFacts<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    visualization_output_path: Option<&str>,
+) -> FpcsOutput<'mir, 'tcx> {
     if let Some(dir_path) = visualization_output_path {
         if std::path::Path::new(&dir_path).exists() {
             std::fs::remove_dir_all(&dir_path).expect("Failed to delete directory contents");
         }
         create_dir_all(&dir_path).expect("Failed to create directory for DOT files");
-        generate_json_from_mir(&format!("{}/mir.json", dir_path), &mir.body)
-            .expect("Failed to generate JSON from MIR");
+    }
+
+    let cgx = PcsContext::new(tcx, mir);
+    let rp = cgx.rp;
+    let mut fpcs = PcsEngine::new(cgx);
+    let mut counter = BlockVisitCounter::new(mir.body.basic_blocks.len(), max_block_visits_from_env());
+    let visits = counter.visits();
+    let mut dumper = debug_block_from_env().map(|block| {
+        let debug_dir = match visualization_output_path {
+            Some(dir_path) => format!("{}/debug", dir_path),
+            None => "debug".to_string(),
+        };
+        BlockVisitDumper::new(block, debug_dir, rp)
+    });
+    fpcs.set_visit_hook(Box::new(move |block, state| {
+        counter.visit(block);
+        if let Some(dumper) = &mut dumper {
+            dumper.visit(block, state);
+        }
+    }));
+    let mut cursor = iterate_to_fixpoint(fpcs, mir, tcx);
+    cursor.mut_analysis().clear_visit_hook();
+    if validity_checks_enabled() {
+        check_validity_of_results(&mut cursor);
+    }
+    let mut fpcs_analysis = free_pcs::FreePcsAnalysis::new(cursor);
+
+    if let Some(dir_path) = visualization_output_path {
+        let (mut mir_graph, long_stmts) = mir_graph(&mir.body, tcx);
+        let emit_html = output_options().emit_html;
+        let mut loan_checker = output_options()
+            .check_loans
+            .then(|| LoanChecker::new(tcx, mir));
+        let mut warnings = schema::Warnings::new();
+        let mut unsupported = schema::Warnings::new();
+        let mut capabilities = schema::CapabilitiesByLocation::new();
+        let mut statement_states = BTreeMap::new();
 
         let input_facts = mir.input_facts.as_ref().unwrap().clone();
-        let output_facts = mir.output_facts.as_ref().unwrap().clone();
         let location_table = mir.location_table.as_ref().unwrap();
 
-        let rp = PcsContext::new(tcx, mir).rp;
+        let mut heatmap = schema::Heatmap::new();
+        let mut actions = schema::ActionsByLocation::new();
 
         // Iterate over each statement in the MIR
         for (block, data) in mir.body.basic_blocks.iter_enumerated() {
+            if mir_graph.nodes[block.index()].ignored {
+                // Cleanup blocks skipped by the analysis have no state
+                continue;
+            }
             let pcs_block = fpcs_analysis.get_all_for_bb(block);
+            heatmap.insert(format!("{:?}", block), block_heat(&pcs_block));
+            mir_graph.nodes[block.index()].place_statuses = pcs_block
+                .statements
+                .iter()
+                .map(|statement| place_statuses(&statement.state, &statement.extra.after, rp))
+                .collect();
             for (statement_index, statement) in pcs_block.statements.iter().enumerate() {
+                let statement_actions = pcs_actions(
+                    &statement.repacks_start,
+                    &statement.repacks_middle,
+                    &statement.extra,
+                );
+                if !statement_actions.is_empty() {
+                    actions.push(schema::LocationActions {
+                        location: schema::Location {
+                            block: block.index(),
+                            statement_index,
+                        },
+                        actions: statement_actions
+                            .iter()
+                            .map(|action| action.to_schema(rp))
+                            .collect(),
+                    });
+                }
                 let file_path = format!(
                     "{}/block_{}_stmt_{}.dot",
                     &dir_path,
@@ -101,13 +208,181 @@ pub fn run_free_pcs<'mir, 'tcx>(
                     block.index(),
                     statement_index
                 );
-                let borrows_json =
-                    serde_json::to_string_pretty(&statement.extra.to_json(rp)).unwrap();
-                std::fs::write(&borrows_file_path, borrows_json)
+                write_json(&borrows_file_path, &statement.extra.to_schema(rp))
                     .expect("Failed to write borrows to JSON file");
+                let borrows_graph_file_path = format!(
+                    "{}/block_{}_stmt_{}_borrows_graph.json",
+                    &dir_path,
+                    block.index(),
+                    statement_index
+                );
+                write_json(
+                    &borrows_graph_file_path,
+                    &statement.extra.after.to_graph_schema(rp),
+                )
+                .expect("Failed to write borrows graph to JSON file");
+                if let Some(loan_checker) = &mut loan_checker {
+                    warnings.extend(
+                        loan_checker
+                            .discrepancies(&statement.extra.after, statement.location)
+                            .into_iter()
+                            .map(|message| schema::Warning {
+                                location: schema::Location {
+                                    block: block.index(),
+                                    statement_index,
+                                },
+                                message,
+                            }),
+                    );
+                }
+                let places = match data.statements.get(statement_index) {
+                    Some(statement) => places_in_statement(statement),
+                    None => places_in_terminator(data.terminator()),
+                };
+                for place in places {
+                    if let Some(pointer) = place.raw_pointer_base(rp) {
+                        unsupported.push(schema::Warning {
+                            location: schema::Location {
+                                block: block.index(),
+                                statement_index,
+                            },
+                            message: format!(
+                                "{} is not tracked: it is behind the raw pointer {}",
+                                place.to_short_string(rp),
+                                pointer.to_short_string(rp)
+                            ),
+                        });
+                    }
+                }
+                let location = format!("{:?}", statement.location);
+                let statement_capabilities = statement.state.to_schema(rp);
+                if emit_html {
+                    statement_states.insert(
+                        location.clone(),
+                        schema::StatementState {
+                            capabilities: statement_capabilities.clone(),
+                            borrows: statement.extra.after.to_schema(rp),
+                        },
+                    );
+                }
+                capabilities.insert(location, statement_capabilities);
             }
         }
+        write_json(format!("{}/{}", dir_path, schema::HEATMAP_FILE), &heatmap)
+            .expect("Failed to write heatmap to JSON file");
+        write_json(format!("{}/{}", dir_path, schema::ACTIONS_FILE), &actions)
+            .expect("Failed to write actions to JSON file");
+        write_json(
+            format!("{}/{}", dir_path, schema::FIXPOINT_FILE),
+            &block_visits_to_schema(&visits.borrow()),
+        )
+        .expect("Failed to write block visits to JSON file");
+        write_mir_graph(&format!("{}/{}", dir_path, schema::MIR_FILE), &mir_graph, &long_stmts)
+            .expect("Failed to generate JSON from MIR");
+        write_json(format!("{}/{}", dir_path, schema::CAPABILITIES_FILE), &capabilities)
+            .expect("Failed to write capabilities to JSON file");
+        write_json(
+            format!("{}/{}", dir_path, schema::SIGNATURE_FILE),
+            &signature_regions_to_schema(&signature_regions(mir, tcx)),
+        )
+        .expect("Failed to write signature regions to JSON file");
+        write_json(
+            format!("{}/{}", dir_path, schema::UNSUPPORTED_FILE),
+            &unsupported,
+        )
+        .expect("Failed to write unsupported constructs to JSON file");
+        if loan_checker.is_some() {
+            write_json(format!("{}/{}", dir_path, schema::WARNINGS_FILE), &warnings)
+                .expect("Failed to write warnings to JSON file");
+        }
+        if emit_html {
+            generate_html_report(
+                &format!("{}/{}", dir_path, schema::HTML_REPORT_FILE),
+                &tcx.def_path_str(mir.body.source.def_id()),
+                &mir_graph,
+                &statement_states,
+            )
+            .expect("Failed to write HTML report");
+        }
     }
 
     fpcs_analysis
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::BTreeSet, ffi::OsString};
+
+    use rustc_interface::middle::mir::Location;
+
+    use super::*;
+    use crate::test_utils::with_body;
+
+    fn working_dir_entries() -> BTreeSet<OsString> {
+        std::fs::read_dir(".")
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect()
+    }
+
+    #[test]
+    fn no_output_run_writes_nothing_and_can_be_queried() {
+        let before = working_dir_entries();
+        let source = "
+            pub fn f(x: &mut u32) -> u32 {
+                let r = &mut *x;
+                *r += 1;
+                *x
+            }
+        ";
+        with_body(source, "f", |tcx, mir| {
+            let mut analysis = run_free_pcs_no_output(mir, tcx);
+            let block = analysis.get_all_for_bb(START_BLOCK);
+            let statements = &mir.body.basic_blocks[START_BLOCK].statements;
+            // The state after the terminator is included
+            assert_eq!(block.statements.len(), statements.len() + 1);
+            let borrows = block
+                .statements
+                .iter()
+                .map(|statement| statement.extra.after.live_borrows().count())
+                .max();
+            assert_eq!(borrows, Some(1));
+        });
+        assert_eq!(working_dir_entries(), before);
+    }
+
+    #[test]
+    fn replay_applies_each_effect_once() {
+        let source = "
+            pub fn f(b: bool) -> u32 {
+                let mut x = 0;
+                if b {
+                    x = 1;
+                }
+                x
+            }
+        ";
+        with_body(source, "f", |tcx, mir| {
+            let engine = PcsEngine::new(PcsContext::new(tcx, mir));
+            let mut cursor = iterate_to_fixpoint(engine, mir, tcx);
+            let replayed = Rc::new(RefCell::new(vec![]));
+            let hook_replayed = replayed.clone();
+            combined_pcs::replay_effects(
+                &mut cursor,
+                Box::new(move |location, _| hook_replayed.borrow_mut().push(location)),
+            );
+            let locations: Vec<_> = mir
+                .body
+                .basic_blocks
+                .iter_enumerated()
+                .flat_map(|(block, data)| {
+                    (0..=data.statements.len()).map(move |statement_index| Location {
+                        block,
+                        statement_index,
+                    })
+                })
+                .collect();
+            assert_eq!(*replayed.borrow(), locations);
+        });
+    }
+}
@@ -1,14 +1,39 @@
 #![feature(rustc_private)]
 
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::BTreeMap,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+};
 
-use pcs::{combined_pcs::BodyWithBorrowckFacts, run_free_pcs, rustc_interface};
+use pcs::{
+    combined_pcs::BodyWithBorrowckFacts,
+    run_combined_pcs, run_free_pcs, rustc_interface,
+    utils::{unsupported, verbose},
+    visualization::{
+        compare::compare_runs,
+        generate_combined_json, generate_meta_json,
+        options::{set_output_options, OutputOptions},
+        schema, write_json,
+    },
+};
 use rustc_interface::{
     borrowck::consumers,
     data_structures::fx::FxHashMap,
     data_structures::steal::Steal,
     driver::{self, Compilation},
-    hir::{self, def::DefKind, def_id::LocalDefId},
+    hir::{
+        self,
+        def::DefKind,
+        def_id::{DefId, LocalDefId},
+    },
     index::IndexVec,
     interface::{interface::Compiler, Config, Queries},
     middle::{
@@ -21,6 +46,39 @@ use rustc_interface::{
 
 struct PcsCallbacks;
 
+// The flags are global rather than thread-local, here as well as in the
+// library (`enable_verbose`, `enable_strict_mode` and `set_output_options`),
+// because the compiler runs the analysis on a different thread than the one
+// parsing the arguments.
+static ANALYZE_COROUTINES: AtomicBool = AtomicBool::new(false);
+static COMBINED_OUTPUT: Mutex<Option<String>> = Mutex::new(None);
+static MAX_BLOCKS: AtomicUsize = AtomicUsize::new(usize::MAX);
+static VISUALIZATION: AtomicBool = AtomicBool::new(true);
+static CATCH_PANICS: AtomicBool = AtomicBool::new(false);
+static STATS: AtomicBool = AtomicBool::new(false);
+static ONLY_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// Printed before the message of each panic caught with `--pcs-catch-panics`,
+/// e.g. `[pcs-panic] main: not yet implemented: Rvalue::Cast`.
+const PANIC_PREFIX: &str = "[pcs-panic]";
+
+/// Printed before each function reported with `--pcs-only-errors`, e.g.
+/// `[pcs-divergence] f: rejected by the borrow checker, but the analysis
+/// found no error`.
+const DIVERGENCE_PREFIX: &str = "[pcs-divergence]";
+
+/// Printed when Polonius, or the analysis with its facts, fails on a
+/// function, which is then analyzed with the facts of NLL, e.g.
+/// `[pcs-fallback] f: Polonius failed (...), retrying with NLL facts`.
+const FALLBACK_PREFIX: &str = "[pcs-fallback]";
+
+/// Makes a function fail with the facts of Polonius, to test the fallback to
+/// NLL facts: `borrowck:<name>` panics once Polonius has computed the facts
+/// of the function, and `analysis:<name>` once the analysis of the function
+/// with these facts is done. The name is as printed by `def_path_str`, e.g.
+/// `m::f`.
+const INJECT_POLONIUS_FAILURE_VAR: &str = "PCS_INJECT_POLONIUS_FAILURE";
+
 thread_local! {
     pub static BODIES:
         RefCell<FxHashMap<LocalDefId, BodyWithBorrowckFacts<'static>>> =
@@ -28,8 +86,22 @@ thread_local! {
 }
 
 fn mir_borrowck<'tcx>(tcx: TyCtxt<'tcx>, def_id: LocalDefId) -> MirBorrowck<'tcx> {
-    let consumer_opts = consumers::ConsumerOptions::PoloniusOutputFacts;
-    let body_with_facts = consumers::get_body_with_borrowck_facts(tcx, def_id, consumer_opts);
+    // The errors are emitted when the facts are collected, and are not
+    // reflected in `tainted_by_errors` of the result
+    let err_count = tcx.sess.err_count();
+    let body_with_facts = body_with_borrowck_facts(tcx, def_id);
+    if ONLY_ERRORS.load(Ordering::Relaxed) {
+        let result = original_mir_borrowck(tcx, def_id);
+        if should_analyze(tcx, def_id) {
+            report_divergence(
+                tcx,
+                def_id,
+                &body_with_facts.into(),
+                tcx.sess.err_count() > err_count,
+            );
+        }
+        return result;
+    }
     unsafe {
         let body: BodyWithBorrowckFacts<'tcx> = body_with_facts.into();
         let body: BodyWithBorrowckFacts<'static> = std::mem::transmute(body);
@@ -38,31 +110,287 @@ fn mir_borrowck<'tcx>(tcx: TyCtxt<'tcx>, def_id: LocalDefId) -> MirBorrowck<'tcx
             assert!(map.insert(def_id, body).is_none());
         });
     }
+    original_mir_borrowck(tcx, def_id)
+}
+
+/// The body of `def_id` with the facts of the borrow checker, including the
+/// output of Polonius. Polonius occasionally panics on a function, in which
+/// case the body is borrow checked again with NLL only: the analysis only
+/// needs the input facts, which are collected either way. Whether Polonius
+/// succeeded is recorded by the presence of the output facts.
+fn body_with_borrowck_facts(
+    tcx: TyCtxt<'_>,
+    def_id: LocalDefId,
+) -> consumers::BodyWithBorrowckFacts<'_> {
+    let polonius = || {
+        let body = consumers::get_body_with_borrowck_facts(
+            tcx,
+            def_id,
+            consumers::ConsumerOptions::PoloniusOutputFacts,
+        );
+        if polonius_failure_injected(tcx, def_id, "borrowck") {
+            panic!("injected Polonius failure");
+        }
+        body
+    };
+    catch_unwind(AssertUnwindSafe(polonius)).unwrap_or_else(|payload| {
+        eprintln!(
+            "{FALLBACK_PREFIX} {}: Polonius failed ({}), retrying with NLL facts",
+            tcx.def_path_str(def_id.to_def_id()),
+            panic_message(&*payload)
+        );
+        consumers::get_body_with_borrowck_facts(
+            tcx,
+            def_id,
+            consumers::ConsumerOptions::PoloniusInputFacts,
+        )
+    })
+}
+
+/// Whether a failure is injected for `def_id` at `stage` with
+/// `PCS_INJECT_POLONIUS_FAILURE`.
+fn polonius_failure_injected(tcx: TyCtxt<'_>, def_id: LocalDefId, stage: &str) -> bool {
+    std::env::var(INJECT_POLONIUS_FAILURE_VAR).is_ok_and(|value| {
+        value
+            .strip_prefix(stage)
+            .and_then(|name| name.strip_prefix(':'))
+            == Some(&tcx.def_path_str(def_id.to_def_id()))
+    })
+}
+
+fn original_mir_borrowck(tcx: TyCtxt<'_>, def_id: LocalDefId) -> MirBorrowck<'_> {
     let mut providers = Providers::default();
     rustc_interface::borrowck::provide(&mut providers);
-    let original_mir_borrowck = providers.mir_borrowck;
-    original_mir_borrowck(tcx, def_id)
+    (providers.mir_borrowck)(tcx, def_id)
+}
+
+/// Whether the body of `def_id` is analyzed: those of functions, methods and
+/// closures, and of coroutines with `--pcs-coroutines`.
+fn should_analyze(tcx: TyCtxt<'_>, def_id: LocalDefId) -> bool {
+    match tcx.def_kind(def_id) {
+        DefKind::Fn | DefKind::AssocFn | DefKind::Closure => true,
+        DefKind::Generator => ANALYZE_COROUTINES.load(Ordering::Relaxed),
+        _ => false,
+    }
+}
+
+/// With `--pcs-only-errors`, reports the body of `def_id` if the borrow
+/// checker and the analysis disagree on whether it has errors: the analysis
+/// finds an error where an uninitialized place is used (see
+/// `CombinedPcsCursor::use_errors`) or if it panics.
+fn report_divergence<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def_id: LocalDefId,
+    body: &BodyWithBorrowckFacts<'tcx>,
+    rejected: bool,
+) {
+    let name = tcx.def_path_str(def_id.to_def_id());
+    unsupported::set_current_function(Some(name.clone()));
+    let analyze = || run_combined_pcs(body, tcx).use_errors();
+    let errors = catch_unwind(AssertUnwindSafe(analyze)).unwrap_or_else(|payload| {
+        let message = panic_message(&*payload);
+        vec![format!("the analysis panicked: {message}")]
+    });
+    unsupported::set_current_function(None);
+    if rejected && errors.is_empty() {
+        println!("{DIVERGENCE_PREFIX} {name}: rejected by the borrow checker, but the analysis found no error");
+    } else if !rejected && !errors.is_empty() {
+        println!("{DIVERGENCE_PREFIX} {name}: accepted by the borrow checker, but the analysis found errors:");
+        for error in errors {
+            println!("  {error}");
+        }
+    }
+}
+
+/// The first line of the message of a caught panic.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    let message = payload
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| payload.downcast_ref::<&str>().copied())
+        .unwrap_or("<non-string panic payload>");
+    message.lines().next().unwrap_or_default()
+}
+
+/// The name of a function or method as shown in `functions.json`. Methods
+/// are qualified by their trait or type to keep them apart, e.g. the default
+/// body of `area` in `trait Shape` is `Shape::area`, the method overriding it
+/// in an impl for `Circle` is `<Circle as Shape>::area` and an inherent
+/// method of `Circle` is `Circle::area`.
+fn fn_name(tcx: TyCtxt<'_>, def_id: DefId) -> String {
+    let name = tcx.item_name(def_id);
+    if let Some(trait_def_id) = tcx.trait_of_item(def_id) {
+        return format!("{}::{name}", tcx.item_name(trait_def_id));
+    }
+    let Some(impl_def_id) = tcx.impl_of_method(def_id) else {
+        return name.to_string();
+    };
+    let self_ty = tcx.type_of(impl_def_id).instantiate_identity();
+    match tcx.impl_trait_ref(impl_def_id) {
+        Some(trait_ref) => format!(
+            "<{self_ty} as {}>::{name}",
+            tcx.item_name(trait_ref.skip_binder().def_id)
+        ),
+        None => format!("{self_ty}::{name}"),
+    }
+}
+
+/// The directory in which the output for `item_name` is written: its
+/// alphanumeric parts joined by `_`, e.g. `Circle_as_Shape_area` for
+/// `<Circle as Shape>::area`.
+fn output_dir_name(item_name: &str) -> String {
+    item_name
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Runs the analysis on the body of `def_id` and writes the output to
+/// `dir_path/<output_dir_name(item_name)>`. Returns the facts the body was
+/// analyzed with, or `None` if the body was skipped, either
+/// because no borrowck facts were collected for it (e.g. because
+/// `mir_borrowck` was not run through our override) or because it has more
+/// basic blocks than allowed by `--pcs-max-blocks`, or if the analysis
+/// panicked with `--pcs-catch-panics`.
+fn analyze_body<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def_id: LocalDefId,
+    item_name: &str,
+    dir_path: &str,
+) -> Option<schema::FactsMode> {
+    let body = BODIES.with(|state| state.borrow_mut().remove(&def_id));
+    let Some(body) = body else {
+        eprintln!("Skipping {item_name}: no borrowck facts were collected for it");
+        return None;
+    };
+    let mut body: BodyWithBorrowckFacts<'tcx> = unsafe { std::mem::transmute(body) };
+    let num_blocks = body.body.basic_blocks.len();
+    let max_blocks = MAX_BLOCKS.load(Ordering::Relaxed);
+    if num_blocks > max_blocks {
+        eprintln!("Skipping {item_name}: it has {num_blocks} basic blocks, more than {max_blocks}");
+        return None;
+    }
+    unsupported::set_current_function(Some(item_name.to_string()));
+    let output_path = VISUALIZATION
+        .load(Ordering::Relaxed)
+        .then(|| format!("{}/{}", dir_path, output_dir_name(item_name)));
+    let analyze = |body: &BodyWithBorrowckFacts<'tcx>| {
+        run_free_pcs(body, tcx, output_path.as_deref());
+        if body.output_facts.is_some() && polonius_failure_injected(tcx, def_id, "analysis") {
+            panic!("injected Polonius failure");
+        }
+    };
+    // Like borrow checking (see `body_with_borrowck_facts`), the analysis is
+    // retried without the output of Polonius if it fails with it
+    let completed_with_polonius = body.output_facts.is_some()
+        && catch_unwind(AssertUnwindSafe(|| analyze(&body)))
+            .map_err(|payload| {
+                eprintln!(
+                    "{FALLBACK_PREFIX} {item_name}: the analysis failed with Polonius facts ({}), retrying with NLL facts",
+                    panic_message(&*payload)
+                );
+                body.output_facts = None;
+            })
+            .is_ok();
+    let completed = if completed_with_polonius {
+        true
+    } else if CATCH_PANICS.load(Ordering::Relaxed) {
+        catch_unwind(AssertUnwindSafe(|| analyze(&body)))
+            .map_err(|payload| {
+                eprintln!("{PANIC_PREFIX} {item_name}: {}", panic_message(&*payload));
+            })
+            .is_ok()
+    } else {
+        analyze(&body);
+        true
+    };
+    if completed && STATS.load(Ordering::Relaxed) {
+        print_stats(tcx, &body, item_name);
+    }
+    unsupported::set_current_function(None);
+    let facts = if body.output_facts.is_some() {
+        schema::FactsMode::Polonius
+    } else {
+        schema::FactsMode::Nll
+    };
+    completed.then_some(facts)
+}
+
+/// Prints the size of the body and the largest number of borrows at any of
+/// its locations and the number of nodes of the borrows graph (old places
+/// and region abstractions) collected as garbage,
+/// e.g. `[pcs-stats] main: blocks=3 locals=5 peak_borrows=2 collected=1`.
+fn print_stats<'tcx>(tcx: TyCtxt<'tcx>, body: &BodyWithBorrowckFacts<'tcx>, item_name: &str) {
+    let mut cursor = run_combined_pcs(body, tcx);
+    let mut peak_borrows = 0;
+    let mut collected = 0;
+    for (block, data) in body.body.basic_blocks.iter_enumerated() {
+        for statement_index in 0..=data.statements.len() {
+            cursor.seek(mir::Location {
+                block,
+                statement_index,
+            });
+            peak_borrows = peak_borrows.max(cursor.borrows().borrows.len());
+            collected += cursor.collected_garbage();
+        }
+    }
+    eprintln!(
+        "[pcs-stats] {item_name}: blocks={} locals={} peak_borrows={peak_borrows} collected={collected}",
+        body.body.basic_blocks.len(),
+        body.body.local_decls.len()
+    );
 }
 
 fn run_pcs_on_all_fns<'tcx>(tcx: TyCtxt<'tcx>) {
     let mut item_names = vec![];
+    let mut facts = BTreeMap::new();
     let dir_path = "visualization/data";
-    if std::path::Path::new(dir_path).exists() {
-        std::fs::remove_dir_all(dir_path).expect("Failed to delete directory contents");
+    let visualization = VISUALIZATION.load(Ordering::Relaxed);
+    if visualization {
+        if std::path::Path::new(dir_path).exists() {
+            std::fs::remove_dir_all(dir_path).expect("Failed to delete directory contents");
+        }
+        std::fs::create_dir_all(dir_path).expect("Failed to create directory for JSON file");
     }
-    std::fs::create_dir_all(dir_path).expect("Failed to create directory for JSON file");
 
+    let mut closure_counts = FxHashMap::default();
+    let mut coroutine_counts = FxHashMap::default();
     for def_id in tcx.hir().body_owners() {
         let kind = tcx.def_kind(def_id);
         match kind {
+            // Named after the enclosing function and numbered in order of
+            // appearance, e.g. `main_closure0`
+            hir::def::DefKind::Closure => {
+                let parent = tcx.typeck_root_def_id(def_id.to_def_id());
+                let count = closure_counts.entry(parent).or_insert(0);
+                let item_name = format!("{}_closure{}", fn_name(tcx, parent), count);
+                *count += 1;
+                if let Some(body_facts) = analyze_body(tcx, def_id, &item_name, dir_path) {
+                    facts.insert(item_name.clone(), body_facts);
+                    item_names.push(item_name);
+                }
+            }
             hir::def::DefKind::Fn | hir::def::DefKind::AssocFn => {
-                let item_name = format!("{}", tcx.item_name(def_id.to_def_id()));
-                let body = BODIES.with(|state| {
-                    let mut map = state.borrow_mut();
-                    unsafe { std::mem::transmute(map.remove(&def_id).unwrap()) }
-                });
-                run_free_pcs(&body, tcx, Some(&format!("{}/{}", dir_path, item_name)));
-                item_names.push(item_name);
+                let item_name = fn_name(tcx, def_id.to_def_id());
+                if let Some(body_facts) = analyze_body(tcx, def_id, &item_name, dir_path) {
+                    facts.insert(item_name.clone(), body_facts);
+                    item_names.push(item_name);
+                }
+            }
+            // The body of an `async fn` (or block), named after the enclosing
+            // function and numbered like closures, e.g. `f_async0`.
+            // Experimental, since borrows are not yet tracked across
+            // suspension points.
+            hir::def::DefKind::Generator if ANALYZE_COROUTINES.load(Ordering::Relaxed) => {
+                let parent = tcx.typeck_root_def_id(def_id.to_def_id());
+                let count = coroutine_counts.entry(parent).or_insert(0);
+                let item_name = format!("{}_async{}", fn_name(tcx, parent), count);
+                *count += 1;
+                if let Some(body_facts) = analyze_body(tcx, def_id, &item_name, dir_path) {
+                    facts.insert(item_name.clone(), body_facts);
+                    item_names.push(item_name);
+                }
             }
             unsupported_item_kind => {
                 eprintln!("unsupported item: {unsupported_item_kind:?}");
@@ -70,20 +398,24 @@ fn run_pcs_on_all_fns<'tcx>(tcx: TyCtxt<'tcx>) {
         }
     }
 
-    use std::{fs::File, io::Write};
+    if !visualization {
+        return;
+    }
+    let file_path = format!("{}/{}", dir_path, schema::FUNCTIONS_FILE);
 
-    let file_path = format!("{}/functions.json", dir_path);
+    let functions: schema::Functions = item_names
+        .iter()
+        .map(|name| (name.clone(), output_dir_name(name)))
+        .collect();
+    write_json(file_path, &functions).expect("Failed to write item names to JSON file");
 
-    let json_data = serde_json::to_string(
-        &item_names
-            .iter()
-            .map(|name| (name.clone(), name.clone()))
-            .collect::<std::collections::HashMap<_, _>>(),
-    )
-    .expect("Failed to serialize item names to JSON");
-    let mut file = File::create(file_path).expect("Failed to create JSON file");
-    file.write_all(json_data.as_bytes())
-        .expect("Failed to write item names to JSON file");
+    let rustc_version = rustc_interface::interface::util::rustc_version_str().unwrap_or("unknown");
+    generate_meta_json(dir_path, &functions, facts, rustc_version)
+        .expect("Failed to write meta.json");
+    if let Some(out_path) = COMBINED_OUTPUT.lock().unwrap().as_deref() {
+        generate_combined_json(dir_path, &functions, out_path)
+            .expect("Failed to write the combined output file");
+    }
 }
 
 impl driver::Callbacks for PcsCallbacks {
@@ -100,14 +432,145 @@ impl driver::Callbacks for PcsCallbacks {
         compiler: &Compiler,
         queries: &'tcx Queries<'tcx>,
     ) -> Compilation {
-        queries.global_ctxt().unwrap().enter(run_pcs_on_all_fns);
+        // With `--pcs-only-errors`, the bodies were already analyzed when
+        // they were borrow checked
+        if !ONLY_ERRORS.load(Ordering::Relaxed) {
+            queries.global_ctxt().unwrap().enter(run_pcs_on_all_fns);
+        }
+        if unsupported::is_strict_mode() {
+            let encountered = unsupported::take_unsupported();
+            if !encountered.is_empty() {
+                eprintln!("Encountered {} unsupported MIR constructs:", encountered.len());
+                for construct in encountered {
+                    eprintln!("  {construct}");
+                }
+                std::process::exit(1);
+            }
+        }
         Compilation::Stop
     }
 }
 
+/// `pcs_bin pcs-diff <dir_a> <dir_b>`: compares the output of two runs and
+/// exits with a non-zero status if they differ.
+fn run_diff(args: &[String]) -> ! {
+    let [dir_a, dir_b] = args else {
+        eprintln!("Usage: pcs_bin pcs-diff <dir_a> <dir_b>");
+        std::process::exit(2);
+    };
+    let differences = compare_runs(Path::new(dir_a), Path::new(dir_b))
+        .expect("Failed to compare the outputs of the runs");
+    for difference in &differences {
+        println!("{difference}");
+    }
+    std::process::exit(if differences.is_empty() { 0 } else { 1 });
+}
+
 fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("pcs-diff") {
+        run_diff(&args[1..]);
+    }
+    // As `RUSTC_WORKSPACE_WRAPPER`, cargo passes the path of rustc first and
+    // cannot pass our flags, so those are taken from `PCS_FLAGS` instead
+    if args
+        .first()
+        .is_some_and(|arg| Path::new(arg).file_stem().is_some_and(|stem| stem == "rustc"))
+    {
+        args.remove(0);
+    }
+    if let Ok(flags) = std::env::var("PCS_FLAGS") {
+        args.splice(0..0, flags.split_whitespace().map(str::to_string));
+    }
     let mut rustc_args = vec!["-Zpolonius=yes".to_string()];
-    rustc_args.extend(std::env::args().skip(1));
+    let mut output_options = OutputOptions::default();
+    for arg in args {
+        if arg == "--pcs-strict" {
+            unsupported::enable_strict_mode();
+        } else if arg == "--pcs-verbose" {
+            verbose::enable_verbose();
+        } else if arg == "--pcs-no-visualization" {
+            VISUALIZATION.store(false, Ordering::Relaxed);
+        } else if arg == "--pcs-stats" {
+            STATS.store(true, Ordering::Relaxed);
+        } else if arg == "--pcs-catch-panics" {
+            CATCH_PANICS.store(true, Ordering::Relaxed);
+        } else if arg == "--pcs-coroutines" {
+            ANALYZE_COROUTINES.store(true, Ordering::Relaxed);
+        } else if let Some(out_path) = arg.strip_prefix("--pcs-combined-output=") {
+            *COMBINED_OUTPUT.lock().unwrap() = Some(out_path.to_string());
+        } else if let Some(max_blocks) = arg.strip_prefix("--pcs-max-blocks=") {
+            let Ok(max_blocks) = max_blocks.parse() else {
+                eprintln!("Invalid number of blocks for --pcs-max-blocks: {max_blocks}");
+                std::process::exit(1);
+            };
+            MAX_BLOCKS.store(max_blocks, Ordering::Relaxed);
+        } else if arg == "--pcs-json-pretty" {
+            output_options.json_pretty = true;
+        } else if arg == "--pcs-only-errors" {
+            ONLY_ERRORS.store(true, Ordering::Relaxed);
+        } else if arg == "--pcs-check-loans" {
+            output_options.check_loans = true;
+        } else if let Some(formats) = arg.strip_prefix("--pcs-emit=") {
+            for format in formats.split(',') {
+                match format {
+                    "json" => {}
+                    "html" => output_options.emit_html = true,
+                    _ => {
+                        eprintln!("Unknown output format for --pcs-emit: {format}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+        } else {
+            rustc_args.push(arg);
+        }
+    }
+    set_output_options(output_options);
     let mut callbacks = PcsCallbacks;
     driver::RunCompiler::new(&rustc_args, &mut callbacks).run();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Calls `analyze_body` on each body of a crate compiled without the
+    /// `mir_borrowck` override, so that no facts are collected for them.
+    struct WithoutFacts {
+        analyzed: Vec<Option<schema::FactsMode>>,
+    }
+
+    impl driver::Callbacks for WithoutFacts {
+        fn after_analysis<'tcx>(
+            &mut self,
+            _compiler: &Compiler,
+            queries: &'tcx Queries<'tcx>,
+        ) -> Compilation {
+            queries.global_ctxt().unwrap().enter(|tcx| {
+                for def_id in tcx.hir().body_owners() {
+                    let item_name = fn_name(tcx, def_id.to_def_id());
+                    self.analyzed
+                        .push(analyze_body(tcx, def_id, &item_name, "unused"));
+                }
+            });
+            Compilation::Stop
+        }
+    }
+
+    #[test]
+    fn body_without_facts_is_skipped() {
+        let path = std::env::temp_dir().join(format!("pcs-no-facts-{}.rs", std::process::id()));
+        std::fs::write(&path, "pub fn f() {}").unwrap();
+        let args = [
+            "rustc".to_string(),
+            path.display().to_string(),
+            "--crate-type=lib".to_string(),
+        ];
+        let mut callbacks = WithoutFacts { analyzed: vec![] };
+        let compiled = driver::RunCompiler::new(&args, &mut callbacks).run();
+        std::fs::remove_file(&path).unwrap();
+        assert!(compiled.is_ok());
+        assert_eq!(callbacks.analyzed, [None]);
+    }
+}
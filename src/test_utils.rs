@@ -0,0 +1,285 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Builders for the inputs of the graph logic, so that it can be unit tested
+//! without compiling a program. Everything built here is free of types and
+//! regions, and places are plain locals, so no `TyCtxt` is needed.
+//!
+//! Logic that needs types, e.g. a `PlaceRepacker`, is tested on a small
+//! program compiled in-process by [`with_body`], and its places are looked up
+//! by name with [`place`].
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rustc_interface::{
+    borrowck::consumers::{self, BorrowIndex},
+    driver::{self, Compilation},
+    index::IndexVec,
+    interface::{interface::Compiler, Queries},
+    middle::{
+        mir::{
+            self, BasicBlock, BasicBlockData, BasicBlocks, Local, Operand, SourceInfo,
+            SwitchTargets, Terminator, TerminatorKind, RETURN_PLACE,
+        },
+        ty::TyCtxt,
+    },
+    span::DUMMY_SP,
+};
+
+use crate::{
+    borrows::domain::{Borrow, BorrowKind, BorrowsState, Loans, MaybeOldPlace, RegionAbstraction},
+    combined_pcs::BodyWithBorrowckFacts,
+    rustc_interface,
+    utils::{
+        places_in_statement, places_in_terminator, Place, PlaceRepacker, PlaceSnapshot,
+        SnapshotLocation,
+    },
+};
+
+/// Calls `f` on the body of the function `name` in `source`, a library crate
+/// compiled in-process, with the facts of the borrow checker. Closures are
+/// named by their path, e.g. `f::{closure#0}`. Panics if `source` does not
+/// compile.
+///
+/// ```ignore
+/// with_body("fn f(x: &mut u32) { *x = 1; }", "f", |tcx, mir| {
+///     let rp = PcsContext::new(tcx, mir).rp;
+///     // ...
+/// });
+/// ```
+pub(crate) fn with_body<R: Send>(
+    source: &str,
+    name: &str,
+    f: impl for<'tcx> FnOnce(TyCtxt<'tcx>, &BodyWithBorrowckFacts<'tcx>) -> R + Send,
+) -> R {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "pcs-test-{}-{}.rs",
+        std::process::id(),
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&path, source).unwrap();
+    let args = [
+        "rustc".to_string(),
+        path.display().to_string(),
+        "--crate-type=lib".to_string(),
+        "--edition=2021".to_string(),
+        "-Awarnings".to_string(),
+    ];
+    let mut callbacks = WithBody {
+        name,
+        f: Some(f),
+        result: None,
+    };
+    let compiled = driver::RunCompiler::new(&args, &mut callbacks).run();
+    std::fs::remove_file(&path).unwrap();
+    assert!(compiled.is_ok(), "Failed to compile:\n{source}");
+    callbacks
+        .result
+        .unwrap_or_else(|| panic!("No function {name} in:\n{source}"))
+}
+
+struct WithBody<'a, F, R> {
+    name: &'a str,
+    f: Option<F>,
+    result: Option<R>,
+}
+
+impl<F, R> driver::Callbacks for WithBody<'_, F, R>
+where
+    F: for<'tcx> FnOnce(TyCtxt<'tcx>, &BodyWithBorrowckFacts<'tcx>) -> R + Send,
+    R: Send,
+{
+    // Before the analysis, which steals the MIR the borrow checker runs on
+    fn after_expansion<'tcx>(
+        &mut self,
+        _compiler: &Compiler,
+        queries: &'tcx Queries<'tcx>,
+    ) -> Compilation {
+        queries.global_ctxt().unwrap().enter(|tcx| {
+            let def_id = tcx.hir().body_owners().find(|def_id| {
+                let def_id = def_id.to_def_id();
+                tcx.opt_item_name(def_id)
+                    .is_some_and(|item| item.as_str() == self.name)
+                    || tcx.def_path_str(def_id) == self.name
+            });
+            if let Some(def_id) = def_id {
+                let body: BodyWithBorrowckFacts<'_> = consumers::get_body_with_borrowck_facts(
+                    tcx,
+                    def_id,
+                    consumers::ConsumerOptions::PoloniusOutputFacts,
+                )
+                .into();
+                self.result = Some((self.f.take().unwrap())(tcx, &body));
+            }
+        });
+        Compilation::Stop
+    }
+}
+
+/// The place of `rp`'s body written as `name`, e.g. `x.0` or `*r` (see
+/// [`Place::to_short_string`]), among the places of [`places`].
+pub(crate) fn place<'tcx>(rp: PlaceRepacker<'_, 'tcx>, name: &str) -> Place<'tcx> {
+    places(rp)
+        .into_iter()
+        .find(|place| place.to_short_string(rp) == name)
+        .unwrap_or_else(|| panic!("No place {name} in the body"))
+}
+
+/// The locals of `rp`'s body, the places mentioned in it and their prefixes.
+pub(crate) fn places<'tcx>(rp: PlaceRepacker<'_, 'tcx>) -> Vec<Place<'tcx>> {
+    let body = rp.body();
+    let mut places = body
+        .local_decls
+        .indices()
+        .map(Place::from)
+        .collect::<Vec<_>>();
+    for data in body.basic_blocks.iter() {
+        for statement in &data.statements {
+            places.extend(places_in_statement(statement));
+        }
+        places.extend(places_in_terminator(data.terminator()));
+    }
+    places
+        .iter()
+        .flat_map(|place| {
+            (0..=place.projection.len())
+                .map(|len| Place::new(place.local, &place.projection[..len]))
+        })
+        .collect()
+}
+
+/// The blocks of a CFG where block `i` has the successors `successors[i]`:
+/// a block without successors returns, a block with one jumps to it, and a
+/// block with several switches on `_0` between them.
+///
+/// ```ignore
+/// // bb0 -> {bb1, bb2} -> bb3
+/// let blocks = mock_blocks(&[&[1, 2], &[3], &[3], &[]]);
+/// ```
+pub(crate) fn mock_blocks(successors: &[&[usize]]) -> BasicBlocks<'static> {
+    let blocks = successors
+        .iter()
+        .map(|successors| {
+            let kind = match **successors {
+                [] => TerminatorKind::Return,
+                [target] => TerminatorKind::Goto {
+                    target: BasicBlock::from_usize(target),
+                },
+                [ref targets @ .., otherwise] => TerminatorKind::SwitchInt {
+                    discr: Operand::Copy(RETURN_PLACE.into()),
+                    targets: SwitchTargets::new(
+                        targets.iter().enumerate().map(|(value, target)| {
+                            (value as u128, BasicBlock::from_usize(*target))
+                        }),
+                        BasicBlock::from_usize(otherwise),
+                    ),
+                },
+            };
+            BasicBlockData::new(Some(Terminator {
+                source_info: SourceInfo::outermost(DUMMY_SP),
+                kind,
+            }))
+        })
+        .collect::<IndexVec<BasicBlock, _>>();
+    BasicBlocks::new(blocks)
+}
+
+fn local_place<'tcx>(local: usize) -> mir::Place<'tcx> {
+    Local::from_usize(local).into()
+}
+
+/// Builds a [`BorrowsState`] from borrows between locals.
+///
+/// ```ignore
+/// // `_1` is reborrowed into `_2`, which is reborrowed into `_3`
+/// let state = BorrowsStateBuilder::new().borrow(1, 2, true).borrow(2, 3, true).build();
+/// ```
+pub(crate) struct BorrowsStateBuilder<'tcx> {
+    state: BorrowsState<'tcx>,
+}
+
+impl<'tcx> BorrowsStateBuilder<'tcx> {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: BorrowsState::new(),
+        }
+    }
+
+    /// Adds a borrow of `borrowed` assigned to `assigned`.
+    pub(crate) fn borrow(mut self, borrowed: usize, assigned: usize, is_mut: bool) -> Self {
+        self.state.add_borrow(Borrow::new(
+            BorrowKind::PCS,
+            local_place(borrowed).into(),
+            local_place(assigned).into(),
+            is_mut,
+        ));
+        self
+    }
+
+    /// Adds the borrow of `borrowed` assigned to `assigned` created from the
+    /// rustc loan with index `loan`.
+    pub(crate) fn loan(
+        mut self,
+        loan: usize,
+        borrowed: usize,
+        assigned: usize,
+        is_mut: bool,
+    ) -> Self {
+        self.state.add_borrow(Borrow::new(
+            BorrowKind::Rustc(Loans::new(BorrowIndex::from_usize(loan))),
+            local_place(borrowed).into(),
+            local_place(assigned).into(),
+            is_mut,
+        ));
+        self
+    }
+
+    /// Adds a borrow of `borrowed` assigned to `assigned` as it was before
+    /// `assigned` was overwritten at `location`.
+    pub(crate) fn borrow_into_old(
+        mut self,
+        borrowed: usize,
+        assigned: usize,
+        location: SnapshotLocation,
+        is_mut: bool,
+    ) -> Self {
+        let mut borrow = Borrow::new(
+            BorrowKind::PCS,
+            local_place(borrowed).into(),
+            local_place(assigned).into(),
+            is_mut,
+        );
+        borrow.assigned_place =
+            MaybeOldPlace::OldPlace(PlaceSnapshot::at(local_place(assigned).into(), location));
+        self.state.add_borrow(borrow);
+        self
+    }
+
+    /// Adds a region abstraction through which the loans of `loans_in` flow
+    /// into `loan_out`.
+    pub(crate) fn abstraction(mut self, loans_in: &[usize], loan_out: usize) -> Self {
+        let mut abstraction = RegionAbstraction::new();
+        for &loan_in in loans_in {
+            abstraction.add_loan_in(local_place(loan_in));
+        }
+        abstraction.add_loan_out(local_place(loan_out));
+        self.state.add_region_abstraction(abstraction);
+        self
+    }
+
+    /// Records that `local` was last written at `location`.
+    pub(crate) fn written(mut self, local: usize, location: SnapshotLocation) -> Self {
+        self.state
+            .latest
+            .insert(local_place(local).into(), location);
+        self
+    }
+
+    pub(crate) fn build(self) -> BorrowsState<'tcx> {
+        self.state
+    }
+}
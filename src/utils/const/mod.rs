@@ -85,8 +85,7 @@ pub trait ConstEval<'tcx> {
 
 impl<'tcx> ConstEval<'tcx> for Constant<'tcx> {
     fn eval(self, rp: PlaceRepacker<'_, 'tcx>) -> EvaluatedConst<'tcx> {
-        let param_env = rp.tcx().param_env(rp.body().source.def_id());
-        let eval = self.literal.eval(rp.tcx(), param_env, None);
+        let eval = self.literal.eval(rp.tcx(), rp.param_env(), None);
         assert!(!(self.literal.try_to_scalar().is_some() && eval.is_err()));
         // let eval = eval.ok()?;
         // TODO: find a good way to resolve errors here
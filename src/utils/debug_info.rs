@@ -1,5 +1,11 @@
+//! Records where an entity (e.g. a place or a borrow) was created, for
+//! debugging bogus results. Only enabled with the `debug_info` feature;
+//! otherwise no data is allocated and notes are not even formatted.
+
 use std::{backtrace::Backtrace, sync::Mutex};
 
+pub const ENABLED: bool = cfg!(feature = "debug_info");
+
 #[derive(Clone, Copy, Debug)]
 pub struct DebugInfo<'a>(Option<&'a Mutex<DebugInfoData>>);
 
@@ -88,16 +94,18 @@ impl std::fmt::Display for DebugInfoData {
 
 impl<'arena> DebugInfo<'arena> {
     pub fn new_static() -> DebugInfo<'static> {
-        let debug_info = DebugInfo::new(|mutex| {
+        DebugInfo::new(|mutex| {
             let mutex = Box::new(mutex);
             Box::leak(mutex)
-        });
-        debug_info
+        })
     }
 
     pub fn new(
         alloc: impl Fn(Mutex<DebugInfoData>) -> &'arena Mutex<DebugInfoData>,
     ) -> DebugInfo<'arena> {
+        if !ENABLED {
+            return DEBUGINFO_NONE;
+        }
         let debug_info_data = alloc(Mutex::new(DebugInfoData::new()));
         DebugInfo(Some(debug_info_data))
     }
@@ -106,12 +114,25 @@ impl<'arena> DebugInfo<'arena> {
         if let Some(mutex) = self.0 {
             let mut data = mutex.lock().unwrap();
             data.add_debug_note(note);
-        } else {
+        } else if ENABLED {
             eprintln!(
                 "Attempted to add debug note, but the entity was not created with debug info"
             );
         }
     }
+
+    /// The recorded notes and backtrace, if any.
+    pub fn describe(&self) -> Option<String> {
+        self.0.map(|data| data.lock().unwrap().to_string())
+    }
+
+    /// To be appended to panic messages about the entity: empty if no debug
+    /// info was recorded.
+    pub fn panic_note(&self) -> String {
+        self.describe()
+            .map(|description| format!("\n{description}"))
+            .unwrap_or_default()
+    }
 }
 
 impl std::fmt::Display for DebugInfo<'_> {
@@ -126,6 +147,8 @@ impl std::fmt::Display for DebugInfo<'_> {
 #[macro_export]
 macro_rules! add_debug_note {
     ($debug_info:expr, $($arg:tt)*) => {{
-        $debug_info.add_debug_note_never_call_this_function_directly(format!($($arg)*))
+        if $crate::utils::debug_info::ENABLED {
+            $debug_info.add_debug_note_never_call_this_function_directly(format!($($arg)*))
+        }
     }};
 }
@@ -13,14 +13,15 @@ use std::{
 use rustc_interface::{
     middle::{
         mir::{
-            PlaceElem, PlaceRef, ProjectionElem, VarDebugInfo, VarDebugInfoContents, RETURN_PLACE,
+            tcx::PlaceTy, PlaceElem, ProjectionElem, VarDebugInfo, VarDebugInfoContents,
+            RETURN_PLACE,
         },
         ty::{AdtKind, TyKind},
     },
     span::Span,
 };
 
-use crate::rustc_interface;
+use crate::{rustc_interface, unsupported, visualization::schema};
 
 use super::{Place, PlaceRepacker};
 
@@ -46,41 +47,119 @@ impl<'tcx> PlaceDisplay<'tcx> {
 }
 
 impl<'tcx> Place<'tcx> {
+    /// Places in a closure body referring to captured places are rendered in
+    /// terms of the parent body, see [`Place::as_upvar`].
     pub fn to_string(&self, repacker: PlaceRepacker<'_, 'tcx>) -> PlaceDisplay<'tcx> {
-        // Get the local's debug name from the Body's VarDebugInfo
-        let local_name = if self.local == RETURN_PLACE {
-            Cow::Borrowed("RETURN")
-        } else {
-            fn as_local(span: Span, outer_span: Span) -> Option<Span> {
-                // Before we call source_callsite, we check and see if the span is already local.
-                // This is important b/c in print!("{}", y) if the user selects `y`, the source_callsite
-                // of that span is the entire macro.
-                if outer_span.contains(span) {
-                    return Some(span);
-                } else {
-                    let sp = span.source_callsite();
-                    if outer_span.contains(sp) {
-                        return Some(sp);
-                    }
+        if let Some(upvar) = self.render_upvar(repacker) {
+            return PlaceDisplay::User(*self, upvar);
+        }
+        match self.local_name(repacker) {
+            Some(local_name) => PlaceDisplay::User(*self, self.render(local_name, 0, repacker)),
+            None => PlaceDisplay::Temporary(*self),
+        }
+    }
+
+    /// Like [`Self::to_string`], but temporaries are rendered with the MIR
+    /// name of their local (e.g. `_3.f`) rather than falling back to `Debug`.
+    pub fn to_short_string(&self, repacker: PlaceRepacker<'_, 'tcx>) -> String {
+        if let Some(upvar) = self.render_upvar(repacker) {
+            return upvar;
+        }
+        let local_name = self
+            .local_name(repacker)
+            .unwrap_or_else(|| Cow::Owned(format!("{:?}", self.local)));
+        self.render(local_name, 0, repacker)
+    }
+
+    pub fn to_schema(&self, repacker: PlaceRepacker<'_, 'tcx>) -> schema::Place {
+        let projections = self
+            .projection
+            .iter()
+            .map(|elem| match *elem {
+                ProjectionElem::Deref => schema::PlaceElem::Deref,
+                ProjectionElem::Field(field, _) => schema::PlaceElem::Field {
+                    field: field.as_usize(),
+                },
+                ProjectionElem::Index(local) => schema::PlaceElem::Index {
+                    local: local.as_usize(),
+                },
+                ProjectionElem::ConstantIndex {
+                    offset,
+                    min_length,
+                    from_end,
+                } => schema::PlaceElem::ConstantIndex {
+                    offset,
+                    min_length,
+                    from_end,
+                },
+                ProjectionElem::Subslice { from, to, from_end } => {
+                    schema::PlaceElem::Subslice { from, to, from_end }
                 }
+                ProjectionElem::Downcast(name, variant) => schema::PlaceElem::Downcast {
+                    variant: variant.as_usize(),
+                    name: name.map(|name| name.to_string()),
+                },
+                ProjectionElem::OpaqueCast(_) => schema::PlaceElem::OpaqueCast,
+            })
+            .collect();
+        schema::Place {
+            place: self.to_short_string(repacker),
+            local: self.local.as_usize(),
+            projections,
+        }
+    }
 
-                None
-            }
+    fn render_upvar(&self, repacker: PlaceRepacker<'_, 'tcx>) -> Option<String> {
+        let upvar = self.as_upvar(repacker)?;
+        let captured_len = self.projection.len() - upvar.projection.len();
+        Some(self.render(Cow::Owned(upvar.captured), captured_len, repacker))
+    }
 
-            let get_local_name = |info: &VarDebugInfo<'tcx>| match info.value {
-                VarDebugInfoContents::Place(place) if place.local == self.local => {
-                    as_local(info.source_info.span, repacker.mir.span)
-                        .map(|_| info.name.to_string())
+    /// Get the local's debug name from the Body's VarDebugInfo
+    fn local_name(&self, repacker: PlaceRepacker<'_, 'tcx>) -> Option<Cow<'static, str>> {
+        if self.local == RETURN_PLACE {
+            return Some(Cow::Borrowed("RETURN"));
+        }
+        fn as_local(span: Span, outer_span: Span) -> Option<Span> {
+            // Before we call source_callsite, we check and see if the span is already local.
+            // This is important b/c in print!("{}", y) if the user selects `y`, the source_callsite
+            // of that span is the entire macro.
+            if outer_span.contains(span) {
+                return Some(span);
+            } else {
+                let sp = span.source_callsite();
+                if outer_span.contains(sp) {
+                    return Some(sp);
                 }
-                _ => None,
-            };
-            let Some(local_name) = repacker.mir.var_debug_info.iter().find_map(get_local_name)
-            else {
-                return PlaceDisplay::Temporary(*self);
-            };
-            Cow::Owned(local_name)
+            }
+
+            None
+        }
+
+        let get_local_name = |info: &VarDebugInfo<'tcx>| match info.value {
+            VarDebugInfoContents::Place(place) if place.local == self.local => {
+                as_local(info.source_info.span, repacker.mir.span).map(|_| info.name.to_string())
+            }
+            _ => None,
         };
+        repacker
+            .mir
+            .var_debug_info
+            .iter()
+            .find_map(get_local_name)
+            .map(Cow::Owned)
+    }
 
+    /// Renders the projections of the place after the first `base_len` ones
+    /// on top of `local_name`, the name of the place they project from. The
+    /// type of each projection step is computed from the previous one, such
+    /// that fields and variants can be printed with their names.
+    fn render(
+        &self,
+        local_name: Cow<'static, str>,
+        base_len: usize,
+        repacker: PlaceRepacker<'_, 'tcx>,
+    ) -> String {
         #[derive(Copy, Clone)]
         enum ElemPosition {
             Prefix,
@@ -89,37 +168,24 @@ impl<'tcx> Place<'tcx> {
 
         // Turn each PlaceElem into a prefix (e.g. * for deref) or a suffix
         // (e.g. .field for projection).
-        let elem_to_string = |(index, (place, elem)): (
-            usize,
-            (PlaceRef<'tcx>, PlaceElem<'tcx>),
-        )|
-         -> (ElemPosition, Cow<'static, str>) {
+        let elem_to_string = |base: PlaceTy<'tcx>, elem: PlaceElem<'tcx>| -> (ElemPosition, Cow<'static, str>) {
             match elem {
                 ProjectionElem::Deref => (ElemPosition::Prefix, "*".into()),
 
                 ProjectionElem::Field(field, _) => {
-                    let ty = place.ty(&repacker.mir.local_decls, repacker.tcx).ty;
-
-                    let field_name = match ty.kind() {
+                    let field_name = match base.ty.kind() {
                         TyKind::Adt(def, _substs) => {
-                            let fields = match def.adt_kind() {
-                                AdtKind::Struct => &def.non_enum_variant().fields,
-                                AdtKind::Enum => {
-                                    let Some(PlaceElem::Downcast(_, variant_idx)) =
-                                        self.projection.get(index - 1)
-                                    else {
-                                        unimplemented!()
-                                    };
-                                    &def.variant(*variant_idx).fields
+                            let variant = match (def.adt_kind(), base.variant_index) {
+                                (AdtKind::Enum, Some(variant_idx)) => def.variant(variant_idx),
+                                (AdtKind::Enum, None) => {
+                                    let field = unsupported!("enum field {field:?} without a downcast");
+                                    return (ElemPosition::Suffix, format!(".{field}").into());
                                 }
-                                kind => unimplemented!("{kind:?}"),
+                                (AdtKind::Struct | AdtKind::Union, _) => def.non_enum_variant(),
                             };
-
-                            fields[field].ident(repacker.tcx).to_string()
+                            variant.fields[field].ident(repacker.tcx).to_string()
                         }
 
-                        TyKind::Tuple(_) => field.as_usize().to_string(),
-
                         TyKind::Closure(def_id, _substs) => match def_id.as_local() {
                             Some(local_def_id) => {
                                 let captures = repacker.tcx.closure_captures(local_def_id);
@@ -128,26 +194,48 @@ impl<'tcx> Place<'tcx> {
                             None => field.as_usize().to_string(),
                         },
 
-                        kind => unimplemented!("{kind:?}"),
+                        // Tuples, coroutines, ...
+                        _ => field.as_usize().to_string(),
                     };
 
                     (ElemPosition::Suffix, format!(".{field_name}").into())
                 }
-                ProjectionElem::Downcast(sym, _) => {
-                    let variant = sym.map(|s| s.to_string()).unwrap_or_else(|| "??".into());
+                ProjectionElem::Downcast(sym, variant_idx) => {
+                    let variant = match (sym, base.ty.kind()) {
+                        (Some(sym), _) => sym.to_string(),
+                        (None, TyKind::Adt(def, _)) => def.variant(variant_idx).name.to_string(),
+                        (None, _) => format!("{variant_idx:?}"),
+                    };
                     (ElemPosition::Suffix, format!("@{variant}",).into())
                 }
 
                 ProjectionElem::Index(_) => (ElemPosition::Suffix, "[_]".into()),
-                kind => unimplemented!("{kind:?}"),
+                ProjectionElem::ConstantIndex { offset, from_end: false, .. } => {
+                    (ElemPosition::Suffix, format!("[{offset}]").into())
+                }
+                ProjectionElem::ConstantIndex { offset, from_end: true, .. } => {
+                    (ElemPosition::Suffix, format!("[-{offset}]").into())
+                }
+                ProjectionElem::Subslice { from, to, from_end: false } => {
+                    (ElemPosition::Suffix, format!("[{from}..{to}]").into())
+                }
+                ProjectionElem::Subslice { from, to, from_end: true } => {
+                    (ElemPosition::Suffix, format!("[{from}..-{to}]").into())
+                }
+                kind => (ElemPosition::Suffix, unsupported!("ProjectionElem {kind:?}").into()),
             }
         };
 
-        let (positions, contents): (Vec<_>, Vec<_>) = self
-            .iter_projections()
-            .enumerate()
-            .map(elem_to_string)
-            .unzip();
+        let projection = &self.projection[base_len..];
+        let mut ty = repacker.place_ty(Place::new(self.local, &self.projection[..base_len]));
+        let mut positions = Vec::with_capacity(projection.len());
+        let mut contents = Vec::with_capacity(projection.len());
+        for &elem in projection {
+            let (position, content) = elem_to_string(ty, elem);
+            positions.push(position);
+            contents.push(content);
+            ty = ty.projection_ty(repacker.tcx, elem);
+        }
 
         // Combine the prefixes and suffixes into a corresponding sequence
         let mut parts = VecDeque::from([local_name]);
@@ -164,7 +252,57 @@ impl<'tcx> Place<'tcx> {
             }
         }
 
-        let full = parts.make_contiguous().join("");
-        PlaceDisplay::User(*self, full)
+        parts.make_contiguous().join("")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        combined_pcs::PcsContext,
+        test_utils::{places, with_body},
+    };
+
+    const NESTED: &str = "
+        pub struct Inner { pub g: u32 }
+        pub struct Outer { pub f: Inner, pub t: (u32, Inner) }
+        pub enum E { V { x: u32 }, W(Outer) }
+        pub fn f(o: &mut Outer, e: E) -> u32 {
+            o.f.g = 1;
+            o.t.1.g = 2;
+            let mut c = 0;
+            let mut add = || c += o.t.0;
+            add();
+            match e { E::V { x } => x, E::W(w) => w.f.g + c }
+        }
+    ";
+
+    /// The short strings of the places of the body `name` of [`NESTED`].
+    fn rendered(name: &str) -> Vec<String> {
+        with_body(NESTED, name, |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            places(rp)
+                .iter()
+                .map(|place| place.to_short_string(rp))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn nested_places_are_rendered_with_names() {
+        let rendered = rendered("f");
+        for expected in ["(*o).f.g", "(*o).t.1.g", "(*o).t.0", "e@V.x", "e@W.0"] {
+            assert!(rendered.iter().any(|place| place == expected), "{expected}");
+        }
+    }
+
+    #[test]
+    fn closure_environment_is_rendered_with_captures() {
+        let rendered = rendered("f::{closure#0}");
+        // The fields of the environment are named after the captured
+        // variables, and the captured places are rendered as in `f`
+        for expected in ["(*_1).o", "(*_1).c", "c"] {
+            assert!(rendered.iter().any(|place| place == expected), "{expected}");
+        }
     }
 }
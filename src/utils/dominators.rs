@@ -0,0 +1,143 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use rustc_interface::{
+    data_structures::graph::dominators::Dominators,
+    index::{bit_set::BitSet, IndexVec},
+    middle::mir::{BasicBlock, BasicBlocks, Location, TerminatorKind},
+};
+
+use crate::rustc_interface;
+
+use super::PlaceRepacker;
+
+/// The postdominators of the blocks of a body, computed on the reversed CFG
+/// with a synthetic exit that all blocks leaving the function (`Return`,
+/// `UnwindResume`, `UnwindTerminate` and `GeneratorDrop`) flow into. Unwind
+/// edges are regular edges, so e.g. a block after a call that may unwind does
+/// not postdominate the call. Blocks from which no exit is reachable (e.g.
+/// infinite loops) are vacuously postdominated by every block.
+#[derive(Clone, Debug)]
+pub struct Postdominators(IndexVec<BasicBlock, BitSet<BasicBlock>>);
+
+impl Postdominators {
+    pub fn new(blocks: &BasicBlocks<'_>) -> Self {
+        let mut pdoms = IndexVec::from_elem_n(BitSet::new_filled(blocks.len()), blocks.len());
+        // Successors are visited before their predecessors (other than along
+        // back edges), so that acyclic CFGs converge in one iteration.
+        let postorder = blocks.reverse_postorder().iter().rev().copied().collect::<Vec<_>>();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in &postorder {
+                let data = &blocks[block];
+                let mut pdom = BitSet::new_empty(blocks.len());
+                if !Self::is_exit(&data.terminator().kind) {
+                    pdom.insert_all();
+                    for succ in data.terminator().successors() {
+                        pdom.intersect(&pdoms[succ]);
+                    }
+                }
+                pdom.insert(block);
+                if pdom != pdoms[block] {
+                    pdoms[block] = pdom;
+                    changed = true;
+                }
+            }
+        }
+        Self(pdoms)
+    }
+
+    fn is_exit(kind: &TerminatorKind<'_>) -> bool {
+        matches!(
+            kind,
+            TerminatorKind::Return
+                | TerminatorKind::UnwindResume
+                | TerminatorKind::UnwindTerminate(_)
+                | TerminatorKind::GeneratorDrop
+        )
+    }
+
+    /// Whether every path from `b` to the exit of the function goes through
+    /// `a`. Every block postdominates itself.
+    pub fn postdominates(&self, a: BasicBlock, b: BasicBlock) -> bool {
+        self.0[b].contains(a)
+    }
+}
+
+impl<'a, 'tcx> PlaceRepacker<'a, 'tcx> {
+    /// The dominators of the blocks of the body, cached by rustc.
+    pub fn dominators(self) -> &'a Dominators<BasicBlock> {
+        self.mir.basic_blocks.dominators()
+    }
+
+    /// The postdominators of the blocks of the body, computed on first use.
+    pub fn postdominators(self) -> &'a Postdominators {
+        self.cache
+            .postdominators
+            .get_or_init(|| Postdominators::new(&self.mir.basic_blocks))
+    }
+
+    /// Whether every path from the start of the function to `b` goes through
+    /// `a`. Within a block, earlier statements dominate later ones (and a
+    /// location dominates itself). `b` must be reachable.
+    pub fn location_dominates(self, a: Location, b: Location) -> bool {
+        if a.block == b.block {
+            a.statement_index <= b.statement_index
+        } else {
+            self.dominators().dominates(a.block, b.block)
+        }
+    }
+
+    /// Whether `a` is executed on every path from `b` to the exit of the
+    /// function, i.e. `a` is guaranteed to execute after `b`. Within a block,
+    /// later statements postdominate earlier ones, since only the terminator
+    /// can unwind.
+    pub fn location_postdominates(self, a: Location, b: Location) -> bool {
+        if a.block == b.block {
+            a.statement_index >= b.statement_index
+        } else {
+            self.postdominators().postdominates(a.block, b.block)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::mock_blocks;
+
+    fn bb(block: usize) -> BasicBlock {
+        BasicBlock::from_usize(block)
+    }
+
+    #[test]
+    fn join_postdominates_branches() {
+        // bb0 -> {bb1, bb2} -> bb3
+        let pdoms = Postdominators::new(&mock_blocks(&[&[1, 2], &[3], &[3], &[]]));
+        assert!(pdoms.postdominates(bb(3), bb(0)));
+        assert!(pdoms.postdominates(bb(3), bb(1)));
+        assert!(!pdoms.postdominates(bb(1), bb(0)));
+        assert!(!pdoms.postdominates(bb(2), bb(0)));
+        assert!(pdoms.postdominates(bb(0), bb(0)));
+    }
+
+    #[test]
+    fn loop_exit_postdominates_loop() {
+        // bb0 -> bb1 -> {bb2 -> bb1, bb3}
+        let pdoms = Postdominators::new(&mock_blocks(&[&[1], &[2, 3], &[1], &[]]));
+        assert!(pdoms.postdominates(bb(1), bb(2)));
+        assert!(pdoms.postdominates(bb(3), bb(2)));
+        assert!(!pdoms.postdominates(bb(2), bb(1)));
+    }
+
+    #[test]
+    fn infinite_loop_is_postdominated_by_every_block() {
+        // bb0 -> bb1 -> bb1
+        let pdoms = Postdominators::new(&mock_blocks(&[&[1], &[1]]));
+        assert!(pdoms.postdominates(bb(0), bb(1)));
+    }
+}
@@ -0,0 +1,164 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use rustc_interface::{
+    middle::{
+        mir::ProjectionElem,
+        ty::{Ty, TyKind},
+    },
+    target::abi::VariantIdx,
+};
+
+use crate::rustc_interface;
+
+use super::{Place, PlaceRepacker};
+
+/// Controls how far [`Place::leaves`] expands a place.
+#[derive(Clone, Copy, Debug)]
+pub struct LeafConfig {
+    /// Whether to expand `b` of type `Box<T>` into `*b`.
+    pub through_box: bool,
+    /// Whether to expand `e` of an enum type with a single variant `V` into
+    /// the fields of `(e as V)`.
+    pub single_variant_enums: bool,
+    /// The maximum number of projections added to the expanded place.
+    pub max_depth: usize,
+}
+
+impl Default for LeafConfig {
+    fn default() -> Self {
+        Self {
+            through_box: false,
+            single_variant_enums: false,
+            max_depth: usize::MAX,
+        }
+    }
+}
+
+impl<'tcx> Place<'tcx> {
+    /// The places obtained by fully expanding `self` according to its type:
+    /// structs, tuples and closures are expanded into their fields, whereas
+    /// references, raw pointers, unions, arrays, enums with several variants
+    /// and unsized places are leaves. A place whose type already occurs
+    /// among the places it was expanded from (e.g. `*l.next` for a recursive
+    /// `struct List { next: Box<List> }` with `through_box`) is a leaf too,
+    /// so that expansion terminates. The leaves are returned in field order.
+    pub fn leaves(self, repacker: PlaceRepacker<'_, 'tcx>, config: LeafConfig) -> Vec<Self> {
+        let mut leaves = Vec::new();
+        self.collect_leaves(repacker, config, 0, &mut Vec::new(), &mut leaves);
+        leaves
+    }
+
+    fn collect_leaves(
+        self,
+        repacker: PlaceRepacker<'_, 'tcx>,
+        config: LeafConfig,
+        depth: usize,
+        expanded_tys: &mut Vec<Ty<'tcx>>,
+        leaves: &mut Vec<Self>,
+    ) {
+        let place_ty = repacker.place_ty(self);
+        let children = if depth >= config.max_depth
+            || place_ty.variant_index.is_none() && expanded_tys.contains(&place_ty.ty)
+        {
+            Vec::new()
+        } else {
+            self.leaf_children(repacker, config)
+        };
+        if children.is_empty() {
+            leaves.push(self);
+            return;
+        }
+        expanded_tys.push(place_ty.ty);
+        for child in children {
+            let depth = depth + child.projection.len() - self.projection.len();
+            child.collect_leaves(repacker, config, depth, expanded_tys, leaves);
+        }
+        expanded_tys.pop();
+    }
+
+    /// The places `self` is expanded into by [`Place::leaves`], or nothing if
+    /// `self` is a leaf.
+    fn leaf_children(self, repacker: PlaceRepacker<'_, 'tcx>, config: LeafConfig) -> Vec<Self> {
+        let place_ty = repacker.place_ty(self);
+        if place_ty.variant_index.is_some() {
+            return self.expand_field(None, repacker);
+        }
+        match place_ty.ty.kind() {
+            TyKind::Adt(..) if place_ty.ty.is_box() => {
+                if config.through_box {
+                    vec![self.mk_place_elem(ProjectionElem::Deref, repacker)]
+                } else {
+                    Vec::new()
+                }
+            }
+            TyKind::Adt(def, _) if def.is_struct() => self.expand_field(None, repacker),
+            TyKind::Adt(def, _)
+                if def.is_enum() && def.variants().len() == 1 && config.single_variant_enums =>
+            {
+                let variant = VariantIdx::from_usize(0);
+                let downcast = ProjectionElem::Downcast(Some(def.variant(variant).name), variant);
+                self.mk_place_elem(downcast, repacker)
+                    .expand_field(None, repacker)
+            }
+            TyKind::Tuple(..) | TyKind::Closure(..) => self.expand_field(None, repacker),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        combined_pcs::PcsContext,
+        test_utils::{place, with_body},
+    };
+
+    const TYPES: &str = "
+        pub struct List { pub val: u32, pub next: Box<List> }
+        pub enum Wrap { W(u32, (u32, u32)) }
+        pub fn f(l: List, w: Wrap) {}
+    ";
+
+    /// The leaves of the argument `name` of `f` in [`TYPES`].
+    fn leaves(name: &'static str, config: LeafConfig) -> Vec<String> {
+        with_body(TYPES, "f", move |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            place(rp, name)
+                .leaves(rp, config)
+                .iter()
+                .map(|leaf| leaf.to_short_string(rp))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn recursive_list_is_expanded_once() {
+        let config = LeafConfig {
+            through_box: true,
+            ..LeafConfig::default()
+        };
+        // `*l.next` is a `List` again
+        assert_eq!(leaves("l", config), ["l.val", "*l.next"]);
+        assert_eq!(leaves("l", LeafConfig::default()), ["l.val", "l.next"]);
+        let shallow = LeafConfig {
+            max_depth: 1,
+            ..config
+        };
+        assert_eq!(leaves("l", shallow), ["l.val", "l.next"]);
+    }
+
+    #[test]
+    fn single_variant_enum_is_expanded_on_request() {
+        assert_eq!(leaves("w", LeafConfig::default()), ["w"]);
+        let config = LeafConfig {
+            single_variant_enums: true,
+            ..LeafConfig::default()
+        };
+        assert_eq!(leaves("w", config), ["w@W.0", "w@W.1.0", "w@W.1.1"]);
+    }
+}
@@ -0,0 +1,140 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The places a statement or terminator mentions, whatever it does with
+//! them: read, written, borrowed, allocated or deallocated. The locals used
+//! as indices in a projection are mentioned as well, e.g. `x[i]` mentions
+//! both `x[i]` and `i`.
+//!
+//! For operands, what is done with the place decides its capabilities: a
+//! `move` operand consumes the place, a copy only reads it, see
+//! [`operand_effect`].
+
+use rustc_interface::middle::mir::{
+    self,
+    visit::{PlaceContext, Visitor},
+    Local, Location, Operand, ProjectionElem, Statement, Terminator,
+};
+
+use super::{Place, PlaceRepacker};
+use crate::rustc_interface;
+
+/// How an operand uses its place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UseKind {
+    /// The place is read and keeps its value.
+    Copy,
+    /// The place is read and left uninitialized.
+    Move,
+}
+
+/// The place `op` uses and how, or `None` for a constant. An operand behind
+/// a raw pointer (see [`Place::raw_pointer_base`]) only copies the pointer:
+/// the place itself is untracked.
+pub fn operand_effect<'tcx>(
+    op: &Operand<'tcx>,
+    repacker: PlaceRepacker<'_, 'tcx>,
+) -> Option<(Place<'tcx>, UseKind)> {
+    let (place, kind) = match *op {
+        Operand::Copy(place) => (place.into(), UseKind::Copy),
+        Operand::Move(place) => (place.into(), UseKind::Move),
+        Operand::Constant(..) => return None,
+    };
+    match Place::raw_pointer_base(place, repacker) {
+        Some(pointer) => Some((pointer, UseKind::Copy)),
+        None => Some((place, kind)),
+    }
+}
+
+struct MentionedPlaces<'tcx>(Vec<Place<'tcx>>);
+
+impl<'tcx> MentionedPlaces<'tcx> {
+    fn push(&mut self, place: Place<'tcx>) {
+        if !self.0.contains(&place) {
+            self.0.push(place);
+        }
+    }
+}
+
+impl<'tcx> Visitor<'tcx> for MentionedPlaces<'tcx> {
+    fn visit_place(&mut self, place: &mir::Place<'tcx>, _: PlaceContext, _: Location) {
+        self.push((*place).into());
+        for elem in place.projection {
+            if let ProjectionElem::Index(index) = elem {
+                self.push(index.into());
+            }
+        }
+    }
+
+    // Locals that are not part of a place, e.g. in `StorageLive`
+    fn visit_local(&mut self, local: Local, _: PlaceContext, _: Location) {
+        self.push(local.into());
+    }
+}
+
+/// The places mentioned by `stmt`, in the order of their first mention.
+pub fn places_in_statement<'tcx>(stmt: &Statement<'tcx>) -> Vec<Place<'tcx>> {
+    let mut places = MentionedPlaces(vec![]);
+    places.visit_statement(stmt, Location::START);
+    places.0
+}
+
+/// The places mentioned by `terminator`, in the order of their first
+/// mention. For a call, these are the arguments and the destination.
+pub fn places_in_terminator<'tcx>(terminator: &Terminator<'tcx>) -> Vec<Place<'tcx>> {
+    let mut places = MentionedPlaces(vec![]);
+    places.visit_terminator(terminator, Location::START);
+    places.0
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_interface::{
+        middle::mir::{
+            BasicBlock, CallSource, Operand, Rvalue, SourceInfo, StatementKind, TerminatorKind,
+            UnwindAction,
+        },
+        span::DUMMY_SP,
+    };
+
+    use super::*;
+
+    fn local(local: usize) -> mir::Place<'static> {
+        Local::from_usize(local).into()
+    }
+
+    fn locals(places: &[Place<'_>]) -> Vec<usize> {
+        places.iter().map(|place| place.local.as_usize()).collect()
+    }
+
+    #[test]
+    fn assignment_mentions_target_and_operand() {
+        // `_1 = move _2`
+        let stmt = Statement {
+            source_info: SourceInfo::outermost(DUMMY_SP),
+            kind: StatementKind::Assign(Box::new((local(1), Rvalue::Use(Operand::Move(local(2)))))),
+        };
+        assert_eq!(locals(&places_in_statement(&stmt)), [1, 2]);
+    }
+
+    #[test]
+    fn call_mentions_function_arguments_and_destination() {
+        // `_0 = _3(move _4, _5) -> bb1`
+        let terminator = Terminator {
+            source_info: SourceInfo::outermost(DUMMY_SP),
+            kind: TerminatorKind::Call {
+                func: Operand::Copy(local(3)),
+                args: vec![Operand::Move(local(4)), Operand::Copy(local(5))],
+                destination: local(0),
+                target: Some(BasicBlock::from_usize(1)),
+                unwind: UnwindAction::Continue,
+                call_source: CallSource::Normal,
+                fn_span: DUMMY_SP,
+            },
+        };
+        assert_eq!(locals(&places_in_terminator(&terminator)), [3, 4, 5, 0]);
+    }
+}
@@ -5,14 +5,32 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 pub mod place;
+mod place_map;
 pub(crate) mod repacker;
 pub mod display;
+mod dominators;
+mod leaves;
+mod mentioned_places;
 mod mutable;
+mod persistent_set;
+mod real_edges;
 mod root_place;
+mod snapshot;
+mod upvar;
 // pub mod ty;
 pub mod r#const;
 pub mod debug_info;
+pub mod unsupported;
+pub mod verbose;
 
+pub use dominators::*;
+pub use leaves::*;
+pub use mentioned_places::*;
 pub use mutable::*;
+pub use persistent_set::*;
 pub use place::*;
+pub use place_map::*;
+pub use real_edges::*;
 pub use repacker::*;
+pub use snapshot::*;
+pub use upvar::*;
@@ -22,6 +22,23 @@ struct Upvar<'tcx> {
     pub(crate) by_ref: bool,
 }
 
+/// Whether a place can be mutated through its projection path, see
+/// [`Place::mutability`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaceMutability {
+    Mutable,
+    /// The path dereferences a shared reference, e.g. `*x.f` with `x.f: &T`.
+    BehindSharedRef,
+    /// The path dereferences a `*const` pointer.
+    BehindConstPtr,
+}
+
+impl PlaceMutability {
+    pub fn allows_mutation(self) -> bool {
+        self == PlaceMutability::Mutable
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LocalMutationIsAllowed {
     Yes,
@@ -51,6 +68,23 @@ impl<'a, 'tcx: 'a> PlaceRepacker<'a, 'tcx> {
 }
 
 impl<'tcx> Place<'tcx> {
+    /// Whether the place can be mutated through its projection path. MIR
+    /// locals are all mutable (the binding mode of user variables is only
+    /// checked by [`Place::is_mutable`]); fields and downcasts inherit from
+    /// their base, and dereferencing a `&T` or `*const T` anywhere on the path
+    /// makes the place immutable. For example, with `x: &mut &T`, `*x` is
+    /// mutable but `**x` is not; with `y: &&mut T`, `**y` is not mutable.
+    pub fn mutability(self, repacker: PlaceRepacker<'_, 'tcx>) -> PlaceMutability {
+        self.iter_projections_with_ty(repacker)
+            .filter(|(_, elem, _)| matches!(elem, ProjectionElem::Deref))
+            .find_map(|(_, _, typ)| match typ.ty.kind() {
+                TyKind::Ref(_, _, Mutability::Not) => Some(PlaceMutability::BehindSharedRef),
+                TyKind::RawPtr(ptr) if ptr.mutbl.is_not() => Some(PlaceMutability::BehindConstPtr),
+                _ => None,
+            })
+            .unwrap_or(PlaceMutability::Mutable)
+    }
+
     pub fn is_mutable(
         self,
         is_local_mutation_allowed: LocalMutationIsAllowed,
@@ -92,7 +126,7 @@ impl<'tcx> Place<'tcx> {
             Some((place_base, elem)) => {
                 match elem {
                     ProjectionElem::Deref => {
-                        let base_ty = place_base.ty(repacker).ty;
+                        let base_ty = repacker.place_ty(place_base).ty;
 
                         // Check the kind of deref to decide
                         match base_ty.kind() {
@@ -227,7 +261,7 @@ impl<'tcx> Place<'tcx> {
 
         match place_ref.last_projection() {
             Some((place_base, ProjectionElem::Field(field, _ty))) => {
-                let base_ty = place_base.ty(repacker.body(), repacker.tcx).ty;
+                let base_ty = repacker.place_ty(place_base.into()).ty;
                 if (base_ty.is_closure() || base_ty.is_generator())
                     && (!by_ref || upvars[field.index()].by_ref)
                 {
@@ -240,3 +274,46 @@ impl<'tcx> Place<'tcx> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::PlaceMutability::{self, *};
+    use crate::{
+        combined_pcs::PcsContext,
+        test_utils::{place, with_body},
+    };
+
+    const REFS: &str = "
+        pub fn refs(x: &mut &u32, y: &&mut u32, p: *const u32, q: *mut u32) {}
+    ";
+
+    /// The mutability of the argument `name` of `refs` dereferenced
+    /// `derefs` times.
+    fn mutability(name: &'static str, derefs: usize) -> PlaceMutability {
+        with_body(REFS, "refs", move |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            (0..derefs)
+                .fold(place(rp, name), |place, _| place.mk_deref(rp))
+                .mutability(rp)
+        })
+    }
+
+    #[test]
+    fn mutable_ref_to_shared_ref() {
+        assert_eq!(mutability("x", 0), Mutable);
+        assert_eq!(mutability("x", 1), Mutable);
+        assert_eq!(mutability("x", 2), BehindSharedRef);
+    }
+
+    #[test]
+    fn shared_ref_to_mutable_ref() {
+        assert_eq!(mutability("y", 1), BehindSharedRef);
+        assert_eq!(mutability("y", 2), BehindSharedRef);
+    }
+
+    #[test]
+    fn raw_pointers() {
+        assert_eq!(mutability("p", 1), BehindConstPtr);
+        assert_eq!(mutability("q", 1), Mutable);
+    }
+}
@@ -0,0 +1,171 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The dataflow engine clones the borrows state several times per statement
+//! (for the snapshots before and after each effect) and at every block
+//! visit, while most statements change at most a few of its edges. The
+//! containers of the state are therefore persistent: a clone shares the
+//! structure of the original, and a modification only copies the path to
+//! the modified entry.
+
+use std::{
+    fmt,
+    hash::{BuildHasherDefault, Hash},
+};
+
+use rustc_interface::data_structures::fx::FxHasher;
+
+use crate::rustc_interface;
+
+pub(crate) type FxBuildHasher = BuildHasherDefault<FxHasher>;
+
+/// A persistent hash set (a HAMT, see [`im_rc::HashSet`]). Cloning is O(1),
+/// and comparing two clones that were not modified since is too.
+///
+/// Unlike those of [`im_rc::HashSet`], the modifications leave the set
+/// untouched if they do not change it, so that it is still shared with its
+/// clones afterwards.
+#[derive(Clone)]
+pub struct PersistentSet<T>(im_rc::HashSet<T, FxBuildHasher>);
+
+impl<T> Default for PersistentSet<T> {
+    fn default() -> Self {
+        Self(im_rc::HashSet::default())
+    }
+}
+
+impl<T: Hash + Eq + Clone> PersistentSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `self` and `other` share their structure, in which case they
+    /// are equal.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.0.ptr_eq(&other.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.0.contains(value)
+    }
+
+    pub fn iter(&self) -> im_rc::hashset::Iter<'_, T> {
+        self.0.iter()
+    }
+
+    /// Whether `value` was not in the set yet.
+    pub fn insert(&mut self, value: T) -> bool {
+        if self.0.contains(&value) {
+            return false;
+        }
+        self.0.insert(value);
+        true
+    }
+
+    /// Whether `value` was in the set.
+    pub fn remove(&mut self, value: &T) -> bool {
+        if !self.0.contains(value) {
+            return false;
+        }
+        self.0.remove(value);
+        true
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        let removed: Vec<T> = self.0.iter().filter(|value| !f(value)).cloned().collect();
+        for value in &removed {
+            self.0.remove(value);
+        }
+    }
+
+    /// The values in `self` that are not in `other`.
+    pub fn difference<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = &'a T> {
+        let shared = self.ptr_eq(other);
+        self.0
+            .iter()
+            .filter(move |value| !shared && !other.contains(value))
+    }
+
+    /// Whether all values of `other` are in `self`.
+    pub fn is_superset(&self, other: &Self) -> bool {
+        self.ptr_eq(other) || other.iter().all(|value| self.contains(value))
+    }
+}
+
+impl<T: Hash + Eq + Clone> PartialEq for PersistentSet<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other) || (self.len() == other.len() && self.is_superset(other))
+    }
+}
+
+impl<T: Hash + Eq + Clone> Eq for PersistentSet<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for PersistentSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.iter()).finish()
+    }
+}
+
+impl<T: Hash + Eq + Clone> Extend<T> for PersistentSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone> FromIterator<T> for PersistentSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<'a, T: Hash + Eq> IntoIterator for &'a PersistentSet<T> {
+    type Item = &'a T;
+    type IntoIter = im_rc::hashset::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_until_modified() {
+        let a: PersistentSet<_> = [1, 2].into_iter().collect();
+        let mut b = a.clone();
+        assert!(a.ptr_eq(&b));
+        assert!(!b.insert(2));
+        assert!(!b.remove(&3));
+        b.retain(|_| true);
+        assert!(a.ptr_eq(&b));
+        assert!(b.insert(3));
+        assert!(!a.ptr_eq(&b));
+        assert_eq!(a.len(), 2);
+        assert_eq!(b.difference(&a).collect::<Vec<_>>(), [&3]);
+    }
+
+    #[test]
+    fn equal_without_sharing() {
+        let a: PersistentSet<_> = [1].into_iter().collect();
+        let b: PersistentSet<_> = [1].into_iter().collect();
+        assert!(!a.ptr_eq(&b));
+        assert_eq!(a, b);
+    }
+}
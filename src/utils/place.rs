@@ -17,13 +17,16 @@ use rustc_interface::{
     abi::VariantIdx,
     middle::{
         mir::{Local, Place as MirPlace, PlaceElem, PlaceRef, ProjectionElem},
-        ty::Ty,
+        ty::{Ty, TyKind},
     },
 };
 
 use crate::rustc_interface;
 
-use super::debug_info::{self, DebugInfo};
+use super::{
+    debug_info::{self, DebugInfo},
+    PlaceRepacker,
+};
 #[derive(Clone, Copy, Deref, DerefMut)]
 pub struct Place<'tcx>(
     #[deref]
@@ -124,6 +127,15 @@ impl<'tcx> Place<'tcx> {
                 .unwrap_or(false)
     }
 
+    /// Check if the place `self` is a prefix of `place` but not equal to it.
+    /// For example:
+    ///
+    /// +   `is_strict_prefix_of(x.f, x.f) == false`
+    /// +   `is_strict_prefix_of(x.f, x.f.g.h) == true`
+    pub fn is_strict_prefix_of(self, place: Self) -> bool {
+        self.projection.len() < place.projection.len() && self.is_prefix(place)
+    }
+
     /// Check if the place `self` is a prefix of `place` or vice versa. For example:
     ///
     /// +   `is_prefix(x.f, x.f) == None`
@@ -150,6 +162,8 @@ impl<'tcx> Place<'tcx> {
         self.partial_cmp(right).is_some()
     }
 
+    /// The longest place that is a prefix of both `self` and `other`, which
+    /// must have the same local. For example `common_prefix(x.f.g, x.f.h) == x.f`.
     pub fn common_prefix(self, other: Self) -> Self {
         assert_eq!(self.local, other.local);
 
@@ -178,6 +192,21 @@ impl<'tcx> Place<'tcx> {
             .map(|(place, proj)| (place.into(), proj))
     }
 
+    /// The last projection element of `self`, e.g. `Deref` for `*x.f`.
+    pub fn last_projection_elem(self) -> Option<PlaceElem<'tcx>> {
+        self.projection.last().copied()
+    }
+
+    /// `self` without its last projection element, e.g. `x.f` for `*x.f`.
+    pub fn without_last_projection(self) -> Option<Self> {
+        self.last_projection().map(|(base, _)| base)
+    }
+
+    /// Whether the last projection element of `self` is a `Deref`.
+    pub fn is_deref(self) -> bool {
+        self.last_projection_elem() == Some(ProjectionElem::Deref)
+    }
+
     pub fn last_projection_ty(self) -> Option<Ty<'tcx>> {
         self.last_projection().and_then(|(_, proj)| match proj {
             ProjectionElem::Field(_, ty) | ProjectionElem::OpaqueCast(ty) => Some(ty),
@@ -186,9 +215,16 @@ impl<'tcx> Place<'tcx> {
     }
 
     pub fn is_deref_of(self, other: Self) -> bool {
-        self.projection.last() == Some(&ProjectionElem::Deref)
+        self.is_deref() && self.without_last_projection() == Some(other)
+    }
+
+    /// Whether `self` is reached from `other` through a `Deref` of `other`,
+    /// e.g. `*x`, `**x` and `(*x).f` are all through a deref of `x`, but
+    /// `x.f` is not.
+    pub fn is_through_deref_of(self, other: Self) -> bool {
+        other.projection.len() < self.projection.len()
             && other.is_prefix(self)
-            && other.projection.len() == self.projection.len() - 1
+            && self.projection[other.projection.len()] == ProjectionElem::Deref
     }
 
     pub fn is_downcast_of(self, other: Self) -> Option<VariantIdx> {
@@ -204,19 +240,167 @@ impl<'tcx> Place<'tcx> {
     }
 
     pub fn target_place(self) -> Option<Self> {
-        if let Some(ProjectionElem::Deref) = self.projection.last() {
-            Some(Place::new(
-                self.local,
-                &self.projection[..self.projection.len() - 1],
-            ))
+        if self.is_deref() {
+            self.without_last_projection()
         } else {
             None
         }
     }
 
+    /// The index of the last `Deref` in the projection of `self`, e.g.
+    /// `last_deref_index((*(*x).f).g) == Some(2)`.
+    pub fn last_deref_index(self) -> Option<usize> {
+        self.projection
+            .iter()
+            .rposition(|elem| matches!(elem, ProjectionElem::Deref))
+    }
+
+    /// Removes the projections after the last `Deref` of `self`, e.g.
+    /// `strip_after_deref((*x.f).g) == *x.f` and
+    /// `strip_after_deref((x as Some).0) == x`.
+    pub fn strip_after_deref(self) -> Self {
+        let len = self.last_deref_index().map_or(0, |idx| idx + 1);
+        Place::new(self.local, &self.projection[..len])
+    }
+
     pub fn debug_info(&self) -> DebugInfo<'static> {
         self.1
     }
+
+    /// `self` with its provably redundant projections simplified, for
+    /// comparison and display. The simplifications, which all preserve the
+    /// type of the place, are:
+    ///
+    /// +   an `OpaqueCast` to the type the place already has is removed;
+    /// +   an index or subslice counted from the end of an array is counted
+    ///     from its start instead, e.g. `a[-1 of 4]` becomes `a[3 of 4]` for
+    ///     `a: [T; 4]`;
+    /// +   a subslice of the whole slice or array is removed, e.g. `(*s)[0:]`
+    ///     becomes `*s`;
+    /// +   an index into, or a subslice of, a subslice is one of the sliced
+    ///     place, e.g. `(*s)[1:-1][0 of 1]` becomes `(*s)[1 of 3]`.
+    ///
+    /// Derefs are never removed, even when they follow each other: each one
+    /// crosses a reference, box or pointer, which is meaningful for the
+    /// analysis. Downcasts and indices by a local are kept as well.
+    pub fn normalize(self, repacker: PlaceRepacker<'_, 'tcx>) -> Self {
+        let mut elems = vec![];
+        for ((typ, _), &elem) in self.projection_tys(repacker).zip(self.projection) {
+            match (elem, typ.ty.kind()) {
+                (ProjectionElem::OpaqueCast(ty), _) if ty == typ.ty => {}
+                (_, TyKind::Array(_, len)) => {
+                    let len = len.try_eval_target_usize(repacker.tcx(), repacker.param_env());
+                    push_normalized(&mut elems, elem, len);
+                }
+                _ => push_normalized(&mut elems, elem, None),
+            }
+        }
+        Self::new(self.local, repacker.tcx().mk_place_elems(&elems))
+    }
+}
+
+/// Pushes `elem` onto the normalized projection `elems`, simplifying it with
+/// the last element of `elems` where possible (see [`Place::normalize`]).
+/// `array_len` is the length of the projected place if it is an array.
+fn push_normalized<'tcx>(
+    elems: &mut Vec<PlaceElem<'tcx>>,
+    elem: PlaceElem<'tcx>,
+    array_len: Option<u64>,
+) {
+    let elem = match (elem, array_len) {
+        (
+            ProjectionElem::ConstantIndex {
+                offset,
+                min_length,
+                from_end: true,
+            },
+            Some(len),
+        ) => ProjectionElem::ConstantIndex {
+            offset: len - offset,
+            min_length,
+            from_end: false,
+        },
+        (
+            ProjectionElem::Subslice {
+                from,
+                to,
+                from_end: true,
+            },
+            Some(len),
+        ) => ProjectionElem::Subslice {
+            from,
+            to: len - to,
+            from_end: false,
+        },
+        _ => elem,
+    };
+    let whole = match elem {
+        ProjectionElem::Subslice {
+            from: 0,
+            to,
+            from_end,
+        } => (from_end && to == 0) || (!from_end && Some(to) == array_len),
+        _ => false,
+    };
+    if whole {
+        return;
+    }
+    let Some(&ProjectionElem::Subslice {
+        from: sliced_from,
+        to: sliced_to,
+        from_end: sliced_from_end,
+    }) = elems.last()
+    else {
+        elems.push(elem);
+        return;
+    };
+    // The number of elements of the sliced place outside of the subslice:
+    // before it, and after it if the subslice is counted from the end
+    let (before, after) = (sliced_from, if sliced_from_end { sliced_to } else { 0 });
+    let elem = match elem {
+        ProjectionElem::ConstantIndex {
+            offset,
+            min_length,
+            from_end: false,
+        } => ProjectionElem::ConstantIndex {
+            offset: before + offset,
+            min_length: before + min_length + after,
+            from_end: false,
+        },
+        ProjectionElem::ConstantIndex {
+            offset,
+            min_length,
+            from_end: true,
+        } if sliced_from_end => ProjectionElem::ConstantIndex {
+            offset: after + offset,
+            min_length: before + min_length + after,
+            from_end: true,
+        },
+        ProjectionElem::Subslice {
+            from,
+            to,
+            from_end: true,
+        } if sliced_from_end => ProjectionElem::Subslice {
+            from: before + from,
+            to: after + to,
+            from_end: true,
+        },
+        ProjectionElem::Subslice {
+            from,
+            to,
+            from_end: false,
+        } if !sliced_from_end => ProjectionElem::Subslice {
+            from: before + from,
+            to: before + to,
+            from_end: false,
+        },
+        _ => {
+            elems.push(elem);
+            return;
+        }
+    };
+    elems.pop();
+    elems.push(elem);
 }
 
 impl Debug for Place<'_> {
@@ -412,3 +596,135 @@ impl From<PlaceOrdering> for Option<Ordering> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(offset: u64, min_length: u64, from_end: bool) -> PlaceElem<'static> {
+        ProjectionElem::ConstantIndex {
+            offset,
+            min_length,
+            from_end,
+        }
+    }
+
+    fn subslice(from: u64, to: u64, from_end: bool) -> PlaceElem<'static> {
+        ProjectionElem::Subslice { from, to, from_end }
+    }
+
+    fn downcast(variant: u32) -> PlaceElem<'static> {
+        ProjectionElem::Downcast(None, VariantIdx::from_u32(variant))
+    }
+
+    /// The place `_1` projected by `projection`.
+    fn place(projection: Vec<PlaceElem<'static>>) -> Place<'static> {
+        Place::new(Local::from_usize(1), projection.leak())
+    }
+
+    /// Normalizes a projection given as each element together with the
+    /// length of the place it projects, if that is an array.
+    fn normalized(projection: &[(PlaceElem<'static>, Option<u64>)]) -> Vec<PlaceElem<'static>> {
+        let mut elems = vec![];
+        for &(elem, array_len) in projection {
+            push_normalized(&mut elems, elem, array_len);
+        }
+        elems
+    }
+
+    #[test]
+    fn array_indices_are_counted_from_the_start() {
+        // `a[-1 of 4]` is `a[3 of 4]`, but `(*s)[-1 of 4]` has no known length
+        assert_eq!(
+            normalized(&[(index(1, 4, true), Some(4))]),
+            [index(3, 4, false)]
+        );
+        assert_eq!(
+            normalized(&[(index(1, 4, true), None)]),
+            [index(1, 4, true)]
+        );
+        // `a[1:-1]` is `a[1..3]`
+        assert_eq!(
+            normalized(&[(subslice(1, 1, true), Some(4))]),
+            [subslice(1, 3, false)]
+        );
+    }
+
+    #[test]
+    fn whole_subslices_are_removed() {
+        assert_eq!(normalized(&[(subslice(0, 0, true), None)]), []);
+        assert_eq!(normalized(&[(subslice(0, 4, false), Some(4))]), []);
+        assert_eq!(
+            normalized(&[(subslice(0, 3, false), Some(4))]),
+            [subslice(0, 3, false)]
+        );
+    }
+
+    #[test]
+    fn subslices_are_flattened() {
+        // `(*s)[1:-1][0 of 1]` is `(*s)[1 of 3]`
+        assert_eq!(
+            normalized(&[(subslice(1, 1, true), None), (index(0, 1, false), None)]),
+            [index(1, 3, false)]
+        );
+        // `(*s)[1:-1][-1 of 1]` is `(*s)[-2 of 3]`
+        assert_eq!(
+            normalized(&[(subslice(1, 1, true), None), (index(1, 1, true), None)]),
+            [index(2, 3, true)]
+        );
+        // `a[1..3][-1 of 2]` is `a[1..3][1 of 2]`, which is `a[2 of 3]`
+        assert_eq!(
+            normalized(&[
+                (subslice(1, 3, false), Some(4)),
+                (index(1, 2, true), Some(2))
+            ]),
+            [index(2, 3, false)]
+        );
+        // `(*s)[1:][2:-1]` is `(*s)[3:-1]`
+        assert_eq!(
+            normalized(&[(subslice(1, 0, true), None), (subslice(2, 1, true), None)]),
+            [subslice(3, 1, true)]
+        );
+    }
+
+    #[test]
+    fn last_deref_through_downcasts() {
+        use ProjectionElem::Deref;
+        // `*((*_1) as 1)[0 of 2]`
+        let base = vec![Deref, downcast(1), index(0, 2, false), Deref];
+        let derefs = place([&base[..], &[Deref]].concat());
+        assert_eq!(derefs.last_deref_index(), Some(4));
+        assert_eq!(derefs.strip_after_deref(), derefs);
+        let downcasts = place([&base[..], &[downcast(0)]].concat());
+        assert_eq!(downcasts.last_deref_index(), Some(3));
+        assert_eq!(downcasts.strip_after_deref(), place(base));
+        // `_1 as 1`
+        let owned = place(vec![downcast(1)]);
+        assert_eq!(owned.last_deref_index(), None);
+        assert_eq!(owned.strip_after_deref(), place(vec![]));
+    }
+
+    #[test]
+    fn last_projection_of_deref_and_downcast() {
+        use ProjectionElem::Deref;
+        let local = place(vec![]);
+        assert_eq!(local.last_projection_elem(), None);
+        assert_eq!(local.without_last_projection(), None);
+        assert!(!local.is_deref());
+        // `*_1`
+        let deref = place(vec![Deref]);
+        assert_eq!(deref.last_projection_elem(), Some(Deref));
+        assert_eq!(deref.without_last_projection(), Some(local));
+        assert!(deref.is_deref());
+        assert!(deref.is_deref_of(local));
+        // `(*_1) as 1`
+        let variant = place(vec![Deref, downcast(1)]);
+        assert_eq!(variant.last_projection_elem(), Some(downcast(1)));
+        assert_eq!(variant.without_last_projection(), Some(deref));
+        assert!(!variant.is_deref());
+        // `*((*_1) as 1)`
+        let deref_variant = place(vec![Deref, downcast(1), Deref]);
+        assert!(deref_variant.is_deref_of(variant));
+        assert!(!deref_variant.is_deref_of(deref));
+    }
+}
@@ -0,0 +1,184 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use super::{FxBuildHasher, Place};
+
+/// A map keyed by places that can also be queried for the entries whose
+/// places are prefixes or extensions of a given place. The map is persistent
+/// (a HAMT, see [`im_rc::HashMap`]), so that cloning it is O(1).
+#[derive(Clone, Debug)]
+pub struct PlaceMap<'tcx, V>(im_rc::HashMap<Place<'tcx>, V, FxBuildHasher>);
+
+impl<'tcx, V> Default for PlaceMap<'tcx, V> {
+    fn default() -> Self {
+        Self(im_rc::HashMap::default())
+    }
+}
+
+impl<'tcx, V: Clone> PlaceMap<'tcx, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `self` and `other` share their structure, in which case they
+    /// are equal.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.0.ptr_eq(&other.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn insert(&mut self, place: Place<'tcx>, value: V) -> Option<V> {
+        self.0.insert(place, value)
+    }
+
+    pub fn get(&self, place: Place<'tcx>) -> Option<&V> {
+        self.0.get(&place)
+    }
+
+    pub fn remove(&mut self, place: Place<'tcx>) -> Option<V> {
+        if !self.0.contains_key(&place) {
+            return None;
+        }
+        self.0.remove(&place)
+    }
+
+    /// Leaves the map shared with its clones if `f` keeps all entries.
+    pub fn retain(&mut self, mut f: impl FnMut(Place<'tcx>, &V) -> bool) {
+        let removed: Vec<_> = self
+            .iter()
+            .filter(|(place, value)| !f(*place, value))
+            .map(|(place, _)| place)
+            .collect();
+        for place in removed {
+            self.0.remove(&place);
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Place<'tcx>, &V)> + '_ {
+        self.0.iter().map(|(place, value)| (*place, value))
+    }
+
+    pub fn places(&self) -> impl Iterator<Item = Place<'tcx>> + '_ {
+        self.0.keys().copied()
+    }
+
+    /// The entries whose place is a prefix of `place` (including `place`
+    /// itself), from the shortest to the longest. For example, for `x.f.g`
+    /// the entries for `x`, `x.f` and `x.f.g`, if present.
+    pub fn iter_prefixes(
+        &self,
+        place: Place<'tcx>,
+    ) -> impl Iterator<Item = (Place<'tcx>, &V)> + '_ {
+        (0..=place.projection.len()).filter_map(move |len| {
+            let prefix = Place::new(place.local, &place.projection[..len]);
+            self.0.get(&prefix).map(|value| (prefix, value))
+        })
+    }
+
+    /// The entries whose place has `place` as a prefix (including `place`
+    /// itself). For example, for `x.f` the entries for `x.f`, `x.f.g` and
+    /// `(*x.f).h`, if present.
+    pub fn iter_extensions(
+        &self,
+        place: Place<'tcx>,
+    ) -> impl Iterator<Item = (Place<'tcx>, &V)> + '_ {
+        self.iter()
+            .filter(move |(other, _)| place.is_prefix(*other))
+    }
+}
+
+impl<'tcx, V: Clone + PartialEq> PartialEq for PlaceMap<'tcx, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other)
+            || (self.len() == other.len()
+                && self
+                    .iter()
+                    .all(|(place, value)| other.get(place) == Some(value)))
+    }
+}
+
+impl<'tcx, V: Clone + Eq> Eq for PlaceMap<'tcx, V> {}
+
+impl<'tcx, V: Clone> FromIterator<(Place<'tcx>, V)> for PlaceMap<'tcx, V> {
+    fn from_iter<I: IntoIterator<Item = (Place<'tcx>, V)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        combined_pcs::PcsContext,
+        test_utils::{place, with_body},
+    };
+
+    const NESTED: &str = "
+        pub struct Inner<'a> { pub g: u32, pub h: &'a mut u32 }
+        pub struct Outer<'a> { pub f: Inner<'a>, pub k: u32 }
+        pub fn f(x: &mut Outer<'_>) {
+            x.f.g = 1;
+            *x.f.h = 2;
+            x.k = 3;
+        }
+    ";
+
+    /// The values of the entries found by `query` on a map from each place of
+    /// `NESTED` below `x` to its position in the list.
+    fn query(
+        query: impl for<'a, 'tcx> Fn(&'a PlaceMap<'tcx, usize>, Place<'tcx>) -> Vec<usize> + Send,
+        name: &'static str,
+    ) -> Vec<usize> {
+        with_body(NESTED, "f", move |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            let map: PlaceMap<'_, usize> = ["x", "*x", "(*x).f", "(*x).f.g", "*(*x).f.h", "(*x).k"]
+                .into_iter()
+                .enumerate()
+                .map(|(i, name)| (place(rp, name), i))
+                .collect();
+            query(&map, place(rp, name))
+        })
+    }
+
+    fn prefixes(name: &'static str) -> Vec<usize> {
+        query(
+            |map, place| map.iter_prefixes(place).map(|(_, &i)| i).collect(),
+            name,
+        )
+    }
+
+    fn extensions(name: &'static str) -> Vec<usize> {
+        let mut extensions = query(
+            |map, place| map.iter_extensions(place).map(|(_, &i)| i).collect(),
+            name,
+        );
+        extensions.sort();
+        extensions
+    }
+
+    #[test]
+    fn prefixes_from_shortest_to_longest() {
+        assert_eq!(prefixes("(*x).f.g"), [0, 1, 2, 3]);
+        // `(*x).f.h` itself has no entry
+        assert_eq!(prefixes("*(*x).f.h"), [0, 1, 2, 4]);
+        assert_eq!(prefixes("x"), [0]);
+    }
+
+    #[test]
+    fn extensions_through_fields_and_derefs() {
+        assert_eq!(extensions("x"), [0, 1, 2, 3, 4, 5]);
+        assert_eq!(extensions("(*x).f"), [2, 3, 4]);
+        assert_eq!(extensions("(*x).f.h"), [4]);
+        assert_eq!(extensions("(*x).k"), [5]);
+    }
+}
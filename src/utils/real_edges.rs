@@ -0,0 +1,72 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The imaginary target of a `FalseEdge` is only there to make the borrow
+//! checker consider a match arm as possibly falling through to the next
+//! candidate, and the unwind target of a `FalseUnwind` to make it consider a
+//! loop as possibly unwinding; control never flows along either. The
+//! analysis only follows the real target, the MIR graph still shows both.
+//!
+//! With `PCS_IGNORE_CLEANUP=1`, the edges to cleanup blocks are not followed
+//! either: the analysis then only covers the executions that do not panic.
+
+use rustc_interface::middle::mir::{
+    BasicBlock, Terminator, TerminatorEdges, TerminatorKind, UnwindAction,
+};
+
+use crate::rustc_interface;
+
+/// Whether the edges to cleanup blocks are ignored, with
+/// `PCS_IGNORE_CLEANUP=1`. The states of cleanup blocks are then not
+/// computed, and the results are only valid on the happy path.
+pub fn ignore_cleanup_enabled() -> bool {
+    std::env::var("PCS_IGNORE_CLEANUP").as_deref() == Ok("1")
+}
+
+/// The cleanup block `terminator` unwinds to, if it is not followed.
+fn ignored_cleanup(terminator: &Terminator<'_>) -> Option<BasicBlock> {
+    match terminator.unwind() {
+        Some(&UnwindAction::Cleanup(cleanup)) if ignore_cleanup_enabled() => Some(cleanup),
+        _ => None,
+    }
+}
+
+/// The edges of `terminator` along which control can flow.
+pub fn real_edges<'mir, 'tcx>(terminator: &'mir Terminator<'tcx>) -> TerminatorEdges<'mir, 'tcx> {
+    let edges = match terminator.kind {
+        TerminatorKind::FalseEdge { real_target, .. }
+        | TerminatorKind::FalseUnwind { real_target, .. } => TerminatorEdges::Single(real_target),
+        _ => terminator.edges(),
+    };
+    if ignored_cleanup(terminator).is_none() {
+        return edges;
+    }
+    match edges {
+        TerminatorEdges::Double(target, _) => TerminatorEdges::Single(target),
+        TerminatorEdges::AssignOnReturn { return_, place, .. } => TerminatorEdges::AssignOnReturn {
+            return_,
+            cleanup: None,
+            place,
+        },
+        edges => edges,
+    }
+}
+
+/// The successors of `terminator` along which control can flow.
+pub fn real_successors<'a>(
+    terminator: &'a Terminator<'_>,
+) -> impl Iterator<Item = BasicBlock> + 'a {
+    let real_target = match terminator.kind {
+        TerminatorKind::FalseEdge { real_target, .. }
+        | TerminatorKind::FalseUnwind { real_target, .. } => Some(real_target),
+        _ => None,
+    };
+    let ignored_cleanup = ignored_cleanup(terminator);
+    terminator
+        .successors()
+        .filter(move |succ| real_target.map_or(true, |real_target| *succ == real_target))
+        .filter(move |succ| Some(*succ) != ignored_cleanup)
+}
@@ -4,8 +4,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::cell::{OnceCell, RefCell};
+
 use rustc_interface::{
-    data_structures::fx::FxHashSet,
+    data_structures::fx::{FxHashMap, FxHashSet},
     dataflow::storage,
     index::{bit_set::BitSet, Idx, IndexVec},
     middle::{
@@ -13,7 +15,7 @@ use rustc_interface::{
             tcx::PlaceTy, Body, HasLocalDecls, Local, Mutability, Place as MirPlace, PlaceElem,
             ProjectionElem, Promoted,
         },
-        ty::{Region, RegionVid, Ty, TyCtxt, TyKind},
+        ty::{ParamEnv, Region, RegionVid, Ty, TyCtxt, TyKind, TypeVisitableExt},
     },
     target::abi::FieldIdx,
 };
@@ -22,7 +24,14 @@ use rustc_interface::{
 
 use crate::rustc_interface;
 
-use super::Place;
+use super::{Place, Postdominators};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpansionError<'tcx> {
+    /// The place has an unsized type (`[T]`, `str` or `dyn Trait`), so the
+    /// places it could be expanded into cannot be enumerated.
+    Unsized(Place<'tcx>),
+}
 
 #[derive(Debug, Clone, Copy)]
 pub enum ProjectionRefKind {
@@ -49,12 +58,23 @@ impl ProjectionRefKind {
     }
 }
 
+/// Memoizes per-body information computed by the repacker, such as the types
+/// of places (see [`PlaceRepacker::place_ty`]) and the postdominators. Lives
+/// alongside the body so that the repacker can stay `Copy`.
+#[derive(Default)]
+pub struct RepackerCache<'tcx> {
+    place_tys: RefCell<FxHashMap<Place<'tcx>, PlaceTy<'tcx>>>,
+    pub(super) postdominators: OnceCell<Postdominators>,
+}
+
 #[derive(Copy, Clone)]
 // TODO: modified version of fns taken from `prusti-interface/src/utils.rs`; deduplicate
 pub struct PlaceRepacker<'a, 'tcx: 'a> {
     pub(super) mir: &'a Body<'tcx>,
     pub(super) promoted: &'a IndexVec<Promoted, Body<'tcx>>,
     pub(super) tcx: TyCtxt<'tcx>,
+    param_env: ParamEnv<'tcx>,
+    pub(super) cache: &'a RepackerCache<'tcx>,
 }
 
 impl<'a, 'tcx: 'a> PlaceRepacker<'a, 'tcx> {
@@ -62,8 +82,62 @@ impl<'a, 'tcx: 'a> PlaceRepacker<'a, 'tcx> {
         mir: &'a Body<'tcx>,
         promoted: &'a IndexVec<Promoted, Body<'tcx>>,
         tcx: TyCtxt<'tcx>,
+        cache: &'a RepackerCache<'tcx>,
     ) -> Self {
-        Self { mir, promoted, tcx }
+        Self {
+            mir,
+            promoted,
+            tcx,
+            param_env: tcx.param_env(mir.source.def_id()),
+            cache,
+        }
+    }
+
+    /// The environment of the analysed function, i.e. its where clauses.
+    pub fn param_env(self) -> ParamEnv<'tcx> {
+        self.param_env
+    }
+
+    pub fn ty_is_copy(self, ty: Ty<'tcx>) -> bool {
+        ty.is_copy_modulo_regions(self.tcx, self.param_env)
+    }
+
+    pub fn ty_needs_drop(self, ty: Ty<'tcx>) -> bool {
+        ty.needs_drop(self.tcx, self.param_env)
+    }
+
+    /// Normalizes the associated types in `ty` using the bounds of the
+    /// analysed function. Note that this erases the regions of `ty`; if `ty`
+    /// cannot be normalized it is returned unchanged.
+    pub fn normalize(self, ty: Ty<'tcx>) -> Ty<'tcx> {
+        self.tcx
+            .try_normalize_erasing_regions(self.param_env, ty)
+            .unwrap_or(ty)
+    }
+
+    /// The type of a field declared with type `ty`, which may mention
+    /// associated types, e.g. `I::Item`. Only such types are normalized, so
+    /// that the regions of the others are kept.
+    pub fn field_ty(self, ty: Ty<'tcx>) -> Ty<'tcx> {
+        if ty.has_projections() {
+            self.normalize(ty)
+        } else {
+            ty
+        }
+    }
+
+    /// The type of `place`. Computed from the type of its parent place (which
+    /// is cached in turn), rather than by walking the whole projection.
+    pub fn place_ty(self, place: Place<'tcx>) -> PlaceTy<'tcx> {
+        if let Some(typ) = self.cache.place_tys.borrow().get(&place) {
+            return *typ;
+        }
+        let typ = match place.last_projection() {
+            Some((base, elem)) => self.place_ty(base).projection_ty(self.tcx, elem),
+            None => PlaceTy::from_ty(self.mir.local_decls()[place.local].ty),
+        };
+        self.cache.place_tys.borrow_mut().insert(place, typ);
+        typ
     }
 
     pub fn local_count(self) -> usize {
@@ -112,6 +186,8 @@ impl<'tcx> Place<'tcx> {
     /// subtracting `{x.f.g.h}` from it, which results into (`{x.f, x.f.g}`, `{x.g, x.h,
     /// x.f.f, x.f.h, x.f.g.f, x.f.g.g}`). The first vector contains the chain of
     /// places that were expanded along with the target to of each expansion.
+    /// The expansion stops early at places with an unsized type, see
+    /// [`ExpansionError::Unsized`].
     pub fn expand(
         mut self,
         to: Self,
@@ -124,10 +200,16 @@ impl<'tcx> Place<'tcx> {
         let mut place_set = Vec::new();
         let mut expanded = Vec::new();
         while self.projection.len() < to.projection.len() {
-            let (new_minuend, places, kind) = self.expand_one_level(to, repacker);
-            expanded.push((self, new_minuend, kind));
-            place_set.extend(places);
-            self = new_minuend;
+            match self.expand_one_level(to, repacker) {
+                Ok((new_minuend, places, kind)) => {
+                    expanded.push((self, new_minuend, kind));
+                    place_set.extend(places);
+                    self = new_minuend;
+                }
+                // Unsized places are kept packed, in which case the last
+                // expanded place is a prefix of `to` rather than `to` itself
+                Err(ExpansionError::Unsized(_)) => break,
+            }
         }
         (expanded, place_set)
     }
@@ -176,15 +258,12 @@ impl<'tcx> Place<'tcx> {
         self,
         guide_place: Self,
         repacker: PlaceRepacker<'_, 'tcx>,
-    ) -> (Self, Vec<Self>, ProjectionRefKind) {
+    ) -> Result<(Self, Vec<Self>, ProjectionRefKind), ExpansionError<'tcx>> {
+        if self.is_unsized(repacker) {
+            return Err(ExpansionError::Unsized(self));
+        }
         let index = self.projection.len();
-        let new_projection = repacker.tcx.mk_place_elems_from_iter(
-            self.projection
-                .iter()
-                .copied()
-                .chain([guide_place.projection[index]]),
-        );
-        let new_current_place = Place::new(self.local, new_projection);
+        let new_current_place = self.mk_place_elem(guide_place.projection[index], repacker);
         let (other_places, kind) = match guide_place.projection[index] {
             ProjectionElem::Field(projected_field, _field_ty) => {
                 let other_places = self.expand_field(Some(projected_field.index()), repacker);
@@ -220,7 +299,7 @@ impl<'tcx> Place<'tcx> {
                 (other_places, ProjectionRefKind::Other)
             }
             ProjectionElem::Deref => {
-                let typ = self.ty(repacker);
+                let typ = repacker.place_ty(self);
                 let kind = match typ.ty.kind() {
                     TyKind::Ref(_, _, mutbl) => ProjectionRefKind::Ref(*mutbl),
                     TyKind::RawPtr(ptr) => ProjectionRefKind::RawPtr(ptr.mutbl),
@@ -234,7 +313,17 @@ impl<'tcx> Place<'tcx> {
             | ProjectionElem::Downcast(..)
             | ProjectionElem::OpaqueCast(..) => (Vec::new(), ProjectionRefKind::Other),
         };
-        (new_current_place, other_places, kind)
+        Ok((new_current_place, other_places, kind))
+    }
+
+    /// Whether the type of the place is `[T]`, `str` or `dyn Trait`. Places
+    /// whose type merely ends in one of these (e.g. a struct with an unsized
+    /// last field) can still be expanded.
+    pub fn is_unsized(self, repacker: PlaceRepacker<'_, 'tcx>) -> bool {
+        matches!(
+            repacker.place_ty(self).ty.kind(),
+            TyKind::Slice(_) | TyKind::Str | TyKind::Dynamic(..)
+        )
     }
 
     /// Expands a place `x.f.g` of type struct into a vector of places for
@@ -247,7 +336,7 @@ impl<'tcx> Place<'tcx> {
         repacker: PlaceRepacker<'_, 'tcx>,
     ) -> Vec<Self> {
         let mut places = Vec::new();
-        let typ = self.ty(repacker);
+        let typ = repacker.place_ty(self);
         if !matches!(typ.ty.kind(), TyKind::Adt(..)) {
             assert!(
                 typ.variant_index.is_none(),
@@ -266,7 +355,7 @@ impl<'tcx> Place<'tcx> {
                         let field_place = repacker.tcx.mk_place_field(
                             self.to_rust_place(repacker),
                             field,
-                            field_def.ty(repacker.tcx, substs),
+                            repacker.field_ty(field_def.ty(repacker.tcx, substs)),
                         );
                         places.push(field_place.into());
                     }
@@ -356,13 +445,54 @@ impl<'tcx> Place<'tcx> {
     //     }
     // }
 
-    pub fn ty(self, repacker: PlaceRepacker<'_, 'tcx>) -> PlaceTy<'tcx> {
-        (*self).ty(repacker.mir, repacker.tcx)
+    /// Check whether `self` and `other` may refer to overlapping memory,
+    /// following MIR's places-conflict rules. Unlike [`Place::related_to`],
+    /// which asks whether one place can be reached from the other by repacking,
+    /// this is about aliasing. For example:
+    ///  - `x.f` and `x.f.g` conflict, as do `x` and `x.f`
+    ///  - `x.f` and `x.g` do not, unless `x` is a union
+    ///  - `(x as Ok).0` and `(x as Err).0` do not
+    ///  - `x[2 of 4]` and `x[3 of 4]` do not, but `x[_1]` and `x[2 of 4]` do
+    pub fn conflicts_with(self, other: Self, repacker: PlaceRepacker<'_, 'tcx>) -> bool {
+        if self.local != other.local {
+            return false;
+        }
+        let diff = self
+            .compare_projections(other)
+            .enumerate()
+            .find(|(_, (eq, _, _))| !eq);
+        let Some((idx, (_, left, right))) = diff else {
+            // One place is a prefix of the other
+            return true;
+        };
+        use ProjectionElem::*;
+        match (left, right) {
+            (Field(..), Field(..)) => {
+                let base = Place::new(self.local, &self.projection[..idx]);
+                repacker.place_ty(base).ty.is_union()
+            }
+            (Downcast(..), Downcast(..)) => false,
+            (
+                ConstantIndex {
+                    offset: l,
+                    from_end: lfe,
+                    ..
+                },
+                ConstantIndex {
+                    offset: r,
+                    from_end: rfe,
+                    ..
+                },
+            ) if lfe == rfe => l == r,
+            // `Index`, `Subslice` and constant indices counted from different
+            // ends may overlap
+            _ => true,
+        }
     }
 
     /// Should only be called on a `Place` obtained from `RootPlace::get_parent`.
     pub fn get_ref_mutability(self, repacker: PlaceRepacker<'_, 'tcx>) -> Mutability {
-        let typ = self.ty(repacker);
+        let typ = repacker.place_ty(self);
         if let TyKind::Ref(_, _, mutability) = typ.ty.kind() {
             *mutability
         } else {
@@ -402,6 +532,23 @@ impl<'tcx> Place<'tcx> {
         .is_some()
     }
 
+    /// Returns `true` if any reference that `self` projects through is a
+    /// shared reference, e.g. for `(*(*x).f)` with `x: &mut S` and
+    /// `S { f: &T }`.
+    pub fn is_behind_shared_ref(self, repacker: PlaceRepacker<'_, 'tcx>) -> bool {
+        self.projection_refs(repacker)
+            .any(|(ref_info, _)| matches!(ref_info, Some((_, _, Mutability::Not))))
+    }
+
+    /// The mutability of the outermost reference that `self` projects
+    /// through, or `None` if it does not go through a reference. Raw
+    /// pointers are skipped.
+    pub fn ref_mutability_at(self, repacker: PlaceRepacker<'_, 'tcx>) -> Option<Mutability> {
+        self.projection_refs(repacker)
+            .find_map(|(ref_info, _)| ref_info)
+            .map(|(_, _, mutability)| mutability)
+    }
+
     pub fn projects_ptr(self, repacker: PlaceRepacker<'_, 'tcx>) -> Option<Place<'tcx>> {
         self.projects_ty(
             |typ| typ.ty.is_ref() || typ.ty.is_box() || typ.ty.is_unsafe_ptr(),
@@ -452,6 +599,19 @@ impl<'tcx> Place<'tcx> {
         })
     }
 
+    /// The raw pointer through whose dereference `self` is reached, if any,
+    /// e.g. `p` for `(*p).f` with `p: *mut T`. Places behind a raw pointer
+    /// are untracked: the pointer may alias any place, so they are given no
+    /// capabilities and no borrows.
+    pub fn raw_pointer_base(self, repacker: PlaceRepacker<'_, 'tcx>) -> Option<Self> {
+        self.projection_tys(repacker)
+            .zip(self.projection)
+            .find_map(|((typ, base), elem)| {
+                (*elem == ProjectionElem::Deref && typ.ty.is_unsafe_ptr())
+                    .then(|| Place::new(self.local, base))
+            })
+    }
+
     // pub fn all_behind_region(self, r: RegionVid, repacker: PlaceRepacker<'_, 'tcx>) -> Vec<Self> {
     //     struct AllBehindWalker<'tcx>(Place<'tcx>, Vec<Place<'tcx>>, TyCtxt<'tcx>);
     //     impl<'tcx> DeepTypeVisitor<'tcx> for AllBehindWalker<'tcx> {
@@ -471,10 +631,38 @@ impl<'tcx> Place<'tcx> {
     }
 
     pub fn mk_place_elem(self, elem: PlaceElem<'tcx>, repacker: PlaceRepacker<'_, 'tcx>) -> Self {
-        let elems = repacker
+        self.project_deeper(&[elem], repacker)
+    }
+
+    /// The place `self` further projected by `elems`, e.g.
+    /// `project_deeper(x.f, [Deref, g]) == (*x.f).g`.
+    pub fn project_deeper(
+        self,
+        elems: &[PlaceElem<'tcx>],
+        repacker: PlaceRepacker<'_, 'tcx>,
+    ) -> Self {
+        let projection = repacker
             .tcx
-            .mk_place_elems_from_iter(self.projection.iter().copied().chain([elem]));
-        Self::new(self.local, elems)
+            .mk_place_elems_from_iter(self.projection.iter().chain(elems).copied());
+        Self::new(self.local, projection)
+    }
+
+    /// Re-roots the projection of `self` onto `new_base`, e.g.
+    /// `replace_base(_1.f, (*_2).g) == (*_2).g.f`.
+    pub fn replace_base(self, new_base: Self, repacker: PlaceRepacker<'_, 'tcx>) -> Self {
+        new_base.project_deeper(self.projection, repacker)
+    }
+
+    /// Each projection element of `self` together with the place it projects
+    /// from and the type of that place, e.g. for `(*x).f` the tuples
+    /// `(x, Deref, type of x)` and `(*x, f, type of *x)`.
+    pub fn iter_projections_with_ty(
+        self,
+        repacker: PlaceRepacker<'_, 'tcx>,
+    ) -> impl Iterator<Item = (Place<'tcx>, PlaceElem<'tcx>, PlaceTy<'tcx>)> {
+        self.projection_tys(repacker)
+            .zip(self.projection.iter())
+            .map(move |((typ, base), elem)| (Place::new(self.local, base), *elem, typ))
     }
 
     pub fn deref_to_region(
@@ -482,7 +670,7 @@ impl<'tcx> Place<'tcx> {
         r: RegionVid,
         repacker: PlaceRepacker<'_, 'tcx>,
     ) -> Option<Self> {
-        let mut ty = self.ty(repacker).ty;
+        let mut ty = repacker.place_ty(self).ty;
         while let TyKind::Ref(rr, inner_ty, _) = *ty.kind() {
             ty = inner_ty;
             self = self.mk_deref(repacker);
@@ -501,3 +689,191 @@ impl<'tcx> Place<'tcx> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        combined_pcs::PcsContext,
+        test_utils::{place, with_body},
+    };
+
+    const BOUNDS: &str = "
+        pub fn copy<T: Copy>(x: T) {}
+        pub fn any<T>(x: T) {}
+        pub fn item<I: Iterator<Item = u32>>(x: I::Item) {}
+        pub fn any_item<I: Iterator>(x: I::Item) {}
+    ";
+
+    /// Whether the type of the argument `x` of `function` in [`BOUNDS`] is
+    /// `Copy`, needs to be dropped, and normalizes to `u32`.
+    fn arg_ty(function: &str) -> (bool, bool, bool) {
+        with_body(BOUNDS, function, |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            let ty = rp.place_ty(place(rp, "x")).ty;
+            (
+                rp.ty_is_copy(ty),
+                rp.ty_needs_drop(ty),
+                rp.normalize(ty) == tcx.types.u32,
+            )
+        })
+    }
+
+    const REF_CHAINS: &str = "
+        pub struct S<'a> { f: &'a u32, g: &'a mut u32 }
+        pub fn chains(x: &mut S<'_>, y: &S<'_>, z: &mut &mut u32) {
+            let a = *x.f;
+            *x.g += a;
+            let b = *y.f + *y.g;
+            **z += b;
+        }
+    ";
+
+    /// Whether the place named `name` in [`REF_CHAINS`] projects a shared
+    /// reference, and whether it is behind one.
+    fn behind_shared_ref(name: &'static str) -> (bool, bool) {
+        with_body(REF_CHAINS, "chains", move |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            let place = place(rp, name);
+            (
+                place.projects_shared_ref(rp),
+                place.is_behind_shared_ref(rp),
+            )
+        })
+    }
+
+    #[test]
+    fn mutable_ref_chains_are_not_shared() {
+        assert_eq!(behind_shared_ref("*x"), (false, false));
+        assert_eq!(behind_shared_ref("(*x).f"), (false, false));
+        assert_eq!(behind_shared_ref("*(*x).g"), (false, false));
+        assert_eq!(behind_shared_ref("**z"), (false, false));
+    }
+
+    #[test]
+    fn shared_ref_anywhere_in_the_chain() {
+        assert_eq!(behind_shared_ref("*(*x).f"), (true, true));
+        assert_eq!(behind_shared_ref("*y"), (true, true));
+        assert_eq!(behind_shared_ref("*(*y).g"), (true, true));
+    }
+
+    #[test]
+    fn bounds_of_the_function_are_used() {
+        assert_eq!(arg_ty("copy"), (true, false, false));
+        assert_eq!(arg_ty("any"), (false, true, false));
+        assert_eq!(arg_ty("item"), (true, false, true));
+        assert_eq!(arg_ty("any_item"), (false, true, false));
+    }
+
+    const PLACES: &str = "
+        pub struct S { f: u32, g: (u32, u32) }
+        pub union U { a: u32, b: u32 }
+        pub fn places(s: S, u: U, r: Result<u32, u32>, a: [u32; 4], i: usize) -> u32 {
+            let [first, second, ..] = a;
+            let x = match r { Ok(x) => x, Err(y) => y };
+            s.f + s.g.0 + unsafe { u.a + u.b } + x + first + second + a[i]
+        }
+    ";
+
+    /// Whether the places named `left` and `right` in [`PLACES`] conflict,
+    /// checked in both directions.
+    fn conflicts(left: &str, right: &str) -> bool {
+        let (left, right) = (left.to_string(), right.to_string());
+        with_body(PLACES, "places", move |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            let (left, right) = (place(rp, &left), place(rp, &right));
+            let conflicts = left.conflicts_with(right, rp);
+            assert_eq!(conflicts, right.conflicts_with(left, rp));
+            conflicts
+        })
+    }
+
+    #[test]
+    fn different_fields_do_not_conflict() {
+        assert!(!conflicts("s.f", "s.g"));
+        assert!(!conflicts("s.f", "s.g.0"));
+    }
+
+    #[test]
+    fn union_fields_conflict() {
+        assert!(conflicts("u.a", "u.b"));
+    }
+
+    #[test]
+    fn different_variants_do_not_conflict() {
+        assert!(!conflicts("r@Ok.0", "r@Err.0"));
+        assert!(conflicts("r@Ok.0", "r@Ok"));
+    }
+
+    #[test]
+    fn constant_indices_conflict_if_equal() {
+        assert!(conflicts("a[0]", "a[0]"));
+        assert!(!conflicts("a[0]", "a[1]"));
+    }
+
+    #[test]
+    fn index_conflicts_with_constant_index() {
+        assert!(conflicts("a[_]", "a[0]"));
+    }
+
+    #[test]
+    fn prefixes_conflict() {
+        assert!(conflicts("s", "s.g.0"));
+        assert!(conflicts("s.g", "s.g.0"));
+        assert!(!conflicts("s", "u"));
+    }
+
+    const DOWNCASTS: &str = "
+        pub enum E<'a> { A(&'a mut &'a mut u32), B }
+        pub fn derefs<'a>(e: &mut E<'a>, g: &mut &mut E<'a>) {
+            if let E::A(r) = e { ***r = 1; }
+            std::mem::swap(e, *g);
+        }
+    ";
+
+    #[test]
+    fn projections_through_downcasts_and_derefs() {
+        with_body(DOWNCASTS, "derefs", |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            let field = place(rp, "(*e)@A.0");
+            let derefs = field.project_deeper(&[ProjectionElem::Deref; 2], rp);
+            assert_eq!(derefs.to_short_string(rp), "**(*e)@A.0");
+            assert_eq!(
+                field.replace_base(place(rp, "*g"), rp).to_short_string(rp),
+                "(**g)@A.0"
+            );
+            // Re-rooting onto the local itself leaves the place unchanged
+            assert_eq!(derefs.replace_base(place(rp, "e"), rp), derefs);
+
+            let steps: Vec<_> = derefs
+                .iter_projections_with_ty(rp)
+                .map(|(base, elem, ty)| {
+                    (
+                        base.to_short_string(rp),
+                        match elem {
+                            ProjectionElem::Deref => "Deref",
+                            ProjectionElem::Downcast(..) => "Downcast",
+                            ProjectionElem::Field(..) => "Field",
+                            _ => unreachable!(),
+                        },
+                        ty.ty.to_string(),
+                        ty.variant_index.is_some(),
+                    )
+                })
+                .collect();
+            let step = |base: &str, elem, ty: &str, downcast| {
+                (base.to_string(), elem, ty.to_string(), downcast)
+            };
+            assert_eq!(
+                steps,
+                [
+                    step("e", "Deref", "&mut E<'_>", false),
+                    step("*e", "Downcast", "E<'_>", false),
+                    step("(*e)@A", "Field", "E<'_>", true),
+                    step("(*e)@A.0", "Deref", "&mut &mut u32", false),
+                    step("*(*e)@A.0", "Deref", "&mut u32", false),
+                ]
+            );
+        });
+    }
+}
@@ -0,0 +1,93 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::{Display, Formatter, Result};
+
+use rustc_interface::middle::mir::{BasicBlock, Body, Location};
+
+use crate::rustc_interface;
+
+use super::{debug_info::DebugInfo, Place};
+
+/// The program point at which a snapshot is taken.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum SnapshotLocation {
+    /// Just before the statement at the location is executed.
+    Before(Location),
+    /// Only meaningful for call terminators: after the arguments of the call
+    /// at the location have been consumed, but before its destination is
+    /// written.
+    Mid(Location),
+    /// Just after the statement at the location is executed.
+    After(Location),
+    /// At the entry of the block, where the states of its predecessors are
+    /// joined.
+    Join(BasicBlock),
+}
+
+impl SnapshotLocation {
+    /// The location of the statement at (or before) which the snapshot is
+    /// taken.
+    pub fn location(self) -> Location {
+        match self {
+            SnapshotLocation::Before(location)
+            | SnapshotLocation::Mid(location)
+            | SnapshotLocation::After(location) => location,
+            SnapshotLocation::Join(block) => block.start_location(),
+        }
+    }
+}
+
+impl Display for SnapshotLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            SnapshotLocation::Before(location) => write!(f, "before {:?}", location),
+            SnapshotLocation::Mid(location) => write!(f, "mid {:?}", location),
+            SnapshotLocation::After(location) => write!(f, "after {:?}", location),
+            SnapshotLocation::Join(block) => write!(f, "join {:?}", block),
+        }
+    }
+}
+
+/// The value of a place at some earlier point of the program, for example the
+/// target of a reference before that reference was overwritten.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct PlaceSnapshot<'tcx> {
+    pub place: Place<'tcx>,
+    pub location: SnapshotLocation,
+    /// Ignored by equality and hashing.
+    pub debug_info: DebugInfo<'static>,
+}
+
+impl<'tcx> PlaceSnapshot<'tcx> {
+    pub fn at(place: Place<'tcx>, location: SnapshotLocation) -> Self {
+        Self {
+            place,
+            location,
+            debug_info: DebugInfo::new_static(),
+        }
+    }
+
+    /// Is the snapshot guaranteed to have been taken before `location` is
+    /// reached, i.e. does the point of the snapshot strictly dominate it?
+    pub fn is_before(&self, location: Location, body: &Body<'tcx>) -> bool {
+        let dominators = body.basic_blocks.dominators();
+        match self.location {
+            SnapshotLocation::Before(at)
+            | SnapshotLocation::Mid(at)
+            | SnapshotLocation::After(at) => {
+                at != location && at.dominates(location, dominators)
+            }
+            SnapshotLocation::Join(block) => dominators.dominates(block, location.block),
+        }
+    }
+}
+
+impl Display for PlaceSnapshot<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{:?}@{}", self.place, self.location)
+    }
+}
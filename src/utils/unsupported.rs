@@ -0,0 +1,82 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Tracks the MIR constructs that are not supported yet. By default
+//! encountering one panics (as a `todo!()` would); in strict mode they are
+//! instead recorded, so that all of them can be reported at the end.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnsupportedConstruct {
+    pub construct: String,
+    /// The function being analysed when the construct was encountered.
+    pub function: Option<String>,
+    /// The source location in this crate that does not handle the construct.
+    pub handler: &'static str,
+}
+
+impl std::fmt::Display for UnsupportedConstruct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.function {
+            Some(function) => write!(f, "{} in `{}` ({})", self.construct, function, self.handler),
+            None => write!(f, "{} ({})", self.construct, self.handler),
+        }
+    }
+}
+
+static STRICT: AtomicBool = AtomicBool::new(false);
+static CURRENT_FUNCTION: Mutex<Option<String>> = Mutex::new(None);
+static ENCOUNTERED: Mutex<Vec<UnsupportedConstruct>> = Mutex::new(Vec::new());
+
+pub fn enable_strict_mode() {
+    STRICT.store(true, Ordering::Relaxed);
+}
+
+pub fn is_strict_mode() -> bool {
+    STRICT.load(Ordering::Relaxed)
+}
+
+pub fn set_current_function(function: Option<String>) {
+    *CURRENT_FUNCTION.lock().unwrap() = function;
+}
+
+/// Returns all constructs recorded so far, sorted and without duplicates.
+pub fn take_unsupported() -> Vec<UnsupportedConstruct> {
+    let mut encountered = std::mem::take(&mut *ENCOUNTERED.lock().unwrap());
+    encountered.sort();
+    encountered.dedup();
+    encountered
+}
+
+/// Use the [`unsupported!`](crate::unsupported) macro instead. Returns a
+/// placeholder that can be used in place of a rendering of the construct.
+pub fn report_unsupported(construct: String, handler: &'static str) -> String {
+    if !is_strict_mode() {
+        todo!("{construct}");
+    }
+    let placeholder = format!("<unsupported {construct}>");
+    let function = CURRENT_FUNCTION.lock().unwrap().clone();
+    ENCOUNTERED.lock().unwrap().push(UnsupportedConstruct {
+        construct,
+        function,
+        handler,
+    });
+    placeholder
+}
+
+#[macro_export]
+macro_rules! unsupported {
+    ($($arg:tt)*) => {{
+        $crate::utils::unsupported::report_unsupported(
+            format!($($arg)*),
+            concat!(file!(), ":", line!()),
+        )
+    }};
+}
@@ -0,0 +1,69 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use rustc_interface::middle::{
+    mir::{PlaceElem, ProjectionElem},
+    ty::{TyKind, UpvarCapture},
+};
+
+use crate::rustc_interface;
+
+use super::{Place, PlaceRepacker};
+
+/// A place in a closure body that refers to (a projection of) a place
+/// captured from the parent body, rather than to the closure environment `_1`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UpvarPlace<'tcx> {
+    /// The captured place, rendered in terms of the parent body (e.g. `p.0`).
+    pub captured: String,
+    pub by_ref: bool,
+    /// The projections applied on top of the captured place.
+    pub projection: &'tcx [PlaceElem<'tcx>],
+}
+
+impl<'tcx> Place<'tcx> {
+    /// Whether `self` is rooted at the environment `_1` of a closure body.
+    pub fn is_closure_env(self, repacker: PlaceRepacker<'_, 'tcx>) -> bool {
+        self.local.as_usize() == 1 && repacker.tcx().is_closure(repacker.body().source.def_id())
+    }
+
+    /// If `self` is a place in a closure body that refers to a captured place,
+    /// its representation in terms of the parent body. For example, if `n` is
+    /// captured by reference, `*(*_1).0` becomes `n`. The fields holding the
+    /// references of by-ref captures (e.g. `(*_1).0`) are not places of the
+    /// parent body and are not normalized.
+    pub fn as_upvar(self, repacker: PlaceRepacker<'_, 'tcx>) -> Option<UpvarPlace<'tcx>> {
+        if !self.is_closure_env(repacker) {
+            return None;
+        }
+        let tcx = repacker.tcx();
+        let def_id = repacker.body().source.def_id().as_local()?;
+        // `_1` is the closure itself for `FnOnce` closures, and a reference to
+        // it otherwise
+        let env_len = match repacker.body().local_decls[self.local].ty.kind() {
+            TyKind::Ref(..) => 1,
+            _ => 0,
+        };
+        let Some(ProjectionElem::Field(field, _)) = self.projection.get(env_len) else {
+            return None;
+        };
+        let captured = tcx.closure_captures(def_id).get(field.as_usize())?;
+        let by_ref = matches!(captured.info.capture_kind, UpvarCapture::ByRef(..));
+        let captured_len = if by_ref {
+            if self.projection.get(env_len + 1) != Some(&ProjectionElem::Deref) {
+                return None;
+            }
+            env_len + 2
+        } else {
+            env_len + 1
+        };
+        Some(UpvarPlace {
+            captured: captured.to_string(tcx),
+            by_ref,
+            projection: &self.projection[captured_len..],
+        })
+    }
+}
@@ -0,0 +1,30 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Tracing of the decisions taken by the analysis, printed to stderr with
+//! `--pcs-verbose`. When disabled, the [`pcs_trace!`](crate::pcs_trace)
+//! arguments are not even formatted.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+pub fn enable_verbose() {
+    VERBOSE.store(true, Ordering::Relaxed);
+}
+
+pub fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+#[macro_export]
+macro_rules! pcs_trace {
+    ($($arg:tt)*) => {{
+        if $crate::utils::verbose::is_verbose() {
+            eprintln!("[pcs] {}", format!($($arg)*));
+        }
+    }};
+}
@@ -0,0 +1,331 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Compares the output directories of two runs, e.g. to check which
+//! functions are affected by a change to the analysis.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Display, Formatter, Result},
+    io,
+    path::Path,
+};
+
+use serde::de::DeserializeOwned;
+
+use super::schema::{
+    ActionsByLocation, Borrow, BorrowsDomain, Capabilities, CapabilitiesByLocation, Functions,
+    Location, MaybeOldPlace, ACTIONS_FILE, CAPABILITIES_FILE, FUNCTIONS_FILE,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RunDifference {
+    /// The function was only analysed in the run with output in `dir`.
+    OnlyIn { function: String, dir: String },
+    /// The capabilities first differ after the statement at `location`.
+    Capabilities {
+        function: String,
+        location: String,
+        diff: Vec<String>,
+    },
+    /// The borrows first differ after the statement at `location`.
+    Borrows {
+        function: String,
+        location: String,
+        diff: Vec<String>,
+    },
+    /// The actions (see `actions.json`) first differ at `location`.
+    Actions {
+        function: String,
+        location: String,
+        diff: Vec<String>,
+    },
+}
+
+impl Display for RunDifference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let (kind, function, preposition, location, diff) = match self {
+            RunDifference::OnlyIn { function, dir } => {
+                return write!(f, "{function}: only analysed in {dir}");
+            }
+            RunDifference::Capabilities {
+                function,
+                location,
+                diff,
+            } => ("capabilities", function, "after", location, diff),
+            RunDifference::Borrows {
+                function,
+                location,
+                diff,
+            } => ("borrows", function, "after", location, diff),
+            RunDifference::Actions {
+                function,
+                location,
+                diff,
+            } => ("actions", function, "at", location, diff),
+        };
+        write!(f, "{function}: {kind} differ {preposition} {location}")?;
+        for line in diff {
+            write!(f, "\n  {line}")?;
+        }
+        Ok(())
+    }
+}
+
+fn read_json<T: DeserializeOwned>(path: impl AsRef<Path>) -> io::Result<T> {
+    let file = std::fs::File::open(path)?;
+    Ok(serde_json::from_reader(io::BufReader::new(file))?)
+}
+
+/// Parses a location of the form `bb2[3]`, such that locations can be
+/// visited in program order.
+fn parse_location(location: &str) -> Option<(usize, usize)> {
+    let (block, statement) = location.strip_prefix("bb")?.split_once('[')?;
+    Some((
+        block.parse().ok()?,
+        statement.strip_suffix(']')?.parse().ok()?,
+    ))
+}
+
+/// Renders the lines only in `a` prefixed with `-` and those only in `b`
+/// prefixed with `+`.
+fn diff_lines(a: BTreeSet<String>, b: BTreeSet<String>) -> Vec<String> {
+    let removed = a.difference(&b).map(|line| format!("- {line}"));
+    let added = b.difference(&a).map(|line| format!("+ {line}"));
+    removed.chain(added).collect()
+}
+
+fn capability_lines(capabilities: &Capabilities) -> BTreeSet<String> {
+    capabilities
+        .iter()
+        .map(|(place, kind)| format!("{place}: {kind}"))
+        .collect()
+}
+
+fn borrow_lines(borrows: &[Borrow]) -> BTreeSet<String> {
+    let place = |place: &MaybeOldPlace| match &place.at {
+        Some(at) => format!("{}@{}", place.place, at),
+        None => place.place.clone(),
+    };
+    borrows
+        .iter()
+        .map(|borrow| {
+            format!(
+                "{} -> {} ({}{})",
+                place(&borrow.assigned_place),
+                place(&borrow.borrowed_place),
+                borrow.kind,
+                if borrow.is_mut { ", mut" } else { "" }
+            )
+        })
+        .collect()
+}
+
+/// The actions at each location, each rendered as its JSON. Older runs
+/// without `actions.json` have no actions.
+fn action_lines(dir: &Path) -> io::Result<BTreeMap<Location, BTreeSet<String>>> {
+    let actions: ActionsByLocation = match read_json(dir.join(ACTIONS_FILE)) {
+        Ok(actions) => actions,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(err) => return Err(err),
+    };
+    Ok(actions
+        .into_iter()
+        .map(|location_actions| {
+            let lines = location_actions
+                .actions
+                .iter()
+                .map(|action| serde_json::to_string(action).unwrap())
+                .collect();
+            (location_actions.location, lines)
+        })
+        .collect())
+}
+
+fn compare_function(function: &str, dir_a: &Path, dir_b: &Path) -> io::Result<Vec<RunDifference>> {
+    let capabilities_a: CapabilitiesByLocation = read_json(dir_a.join(CAPABILITIES_FILE))?;
+    let capabilities_b: CapabilitiesByLocation = read_json(dir_b.join(CAPABILITIES_FILE))?;
+    let mut locations: Vec<_> = capabilities_a
+        .keys()
+        .chain(capabilities_b.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|location| Some((parse_location(location)?, location)))
+        .collect();
+    locations.sort();
+
+    let mut differences = vec![];
+    let mut capabilities_differ = false;
+    let mut borrows_differ = false;
+    for ((block, statement), location) in locations {
+        if !capabilities_differ {
+            let lines = |capabilities: &CapabilitiesByLocation| {
+                capabilities
+                    .get(location)
+                    .map(capability_lines)
+                    .unwrap_or_default()
+            };
+            let diff = diff_lines(lines(&capabilities_a), lines(&capabilities_b));
+            if !diff.is_empty() {
+                capabilities_differ = true;
+                differences.push(RunDifference::Capabilities {
+                    function: function.to_string(),
+                    location: location.clone(),
+                    diff,
+                });
+            }
+        }
+        if !borrows_differ {
+            let file = format!("block_{block}_stmt_{statement}_borrows.json");
+            let lines = |dir: &Path| -> io::Result<BTreeSet<String>> {
+                match read_json::<BorrowsDomain>(dir.join(&file)) {
+                    Ok(borrows) => Ok(borrow_lines(&borrows.after.borrows)),
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(BTreeSet::new()),
+                    Err(err) => Err(err),
+                }
+            };
+            let diff = diff_lines(lines(dir_a)?, lines(dir_b)?);
+            if !diff.is_empty() {
+                borrows_differ = true;
+                differences.push(RunDifference::Borrows {
+                    function: function.to_string(),
+                    location: location.clone(),
+                    diff,
+                });
+            }
+        }
+    }
+
+    let actions_a = action_lines(dir_a)?;
+    let actions_b = action_lines(dir_b)?;
+    let locations: BTreeSet<_> = actions_a.keys().chain(actions_b.keys()).collect();
+    for location in locations {
+        let lines = |actions: &BTreeMap<Location, BTreeSet<String>>| {
+            actions.get(location).cloned().unwrap_or_default()
+        };
+        let diff = diff_lines(lines(&actions_a), lines(&actions_b));
+        if !diff.is_empty() {
+            differences.push(RunDifference::Actions {
+                function: function.to_string(),
+                location: format!("bb{}[{}]", location.block, location.statement_index),
+                diff,
+            });
+            break;
+        }
+    }
+    Ok(differences)
+}
+
+/// Compares the output of two runs written to `dir_a` and `dir_b` (e.g.
+/// `visualization/data`), matching functions by name. For each function, only
+/// the first location at which the capabilities or the borrows differ is
+/// reported.
+pub fn compare_runs(dir_a: &Path, dir_b: &Path) -> io::Result<Vec<RunDifference>> {
+    let functions_a: Functions = read_json(dir_a.join(FUNCTIONS_FILE))?;
+    let functions_b: Functions = read_json(dir_b.join(FUNCTIONS_FILE))?;
+    let mut names: Vec<_> = functions_a.keys().chain(functions_b.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    let mut differences = vec![];
+    for name in names {
+        match (functions_a.get(name), functions_b.get(name)) {
+            (Some(function_a), Some(function_b)) => differences.extend(compare_function(
+                name,
+                &dir_a.join(function_a),
+                &dir_b.join(function_b),
+            )?),
+            (Some(_), None) => differences.push(RunDifference::OnlyIn {
+                function: name.clone(),
+                dir: dir_a.display().to_string(),
+            }),
+            (None, _) => differences.push(RunDifference::OnlyIn {
+                function: name.clone(),
+                dir: dir_b.display().to_string(),
+            }),
+        }
+    }
+    Ok(differences)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::visualization::{schema::BorrowsState, write_json};
+
+    fn state(borrows: Vec<Borrow>) -> BorrowsState {
+        BorrowsState {
+            borrows,
+            latest: Default::default(),
+            blocked_sets: vec![],
+        }
+    }
+
+    /// Writes the output of a run analysing `f` into `dir`: `x` has
+    /// `capability` after `bb0[1]`, when it is borrowed into `y`.
+    fn write_run(dir: &Path, capability: &str, is_mut: bool) {
+        let function_dir = dir.join("f");
+        std::fs::create_dir_all(&function_dir).unwrap();
+        let functions = Functions::from([("f".to_string(), "f".to_string())]);
+        write_json(dir.join(FUNCTIONS_FILE), &functions).unwrap();
+        let capabilities = CapabilitiesByLocation::from([
+            (
+                "bb0[0]".to_string(),
+                Capabilities::from([("x".into(), "E".into())]),
+            ),
+            (
+                "bb0[1]".to_string(),
+                Capabilities::from([("x".into(), capability.into())]),
+            ),
+        ]);
+        write_json(function_dir.join(CAPABILITIES_FILE), &capabilities).unwrap();
+        let place = |place: &str| MaybeOldPlace {
+            place: place.to_string(),
+            at: None,
+        };
+        let borrow = Borrow {
+            kind: "PCS".to_string(),
+            borrowed_place: place("x"),
+            assigned_place: place("y"),
+            is_mut,
+            two_phase: None,
+            debug_info: None,
+        };
+        let borrows = BorrowsDomain {
+            before_start: state(vec![]),
+            before_after: state(vec![]),
+            start: state(vec![]),
+            after: state(vec![borrow]),
+        };
+        write_json(function_dir.join("block_0_stmt_1_borrows.json"), &borrows).unwrap();
+    }
+
+    fn run_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pcs-compare-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn first_differences_are_reported() {
+        let (dir_a, dir_b) = (run_dir("a"), run_dir("b"));
+        write_run(&dir_a, "E", true);
+        write_run(&dir_b, "W", false);
+        assert_eq!(compare_runs(&dir_a, &dir_a).unwrap(), []);
+
+        let differences = compare_runs(&dir_a, &dir_b).unwrap();
+        let rendered: Vec<_> = differences.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            rendered,
+            [
+                "f: capabilities differ after bb0[1]\n  - x: E\n  + x: W",
+                "f: borrows differ after bb0[1]\n  - y -> x (PCS, mut)\n  + y -> x (PCS)",
+            ]
+        );
+        std::fs::remove_dir_all(&dir_a).unwrap();
+        std::fs::remove_dir_all(&dir_b).unwrap();
+    }
+}
@@ -0,0 +1,148 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{cell::RefCell, rc::Rc};
+
+use rustc_interface::{
+    index::{Idx, IndexVec},
+    middle::mir::BasicBlock,
+};
+
+use crate::{
+    combined_pcs::PlaceCapabilitySummary,
+    rustc_interface,
+    utils::PlaceRepacker,
+};
+
+use super::schema::{self, BlockVisit, BlockVisitDiff, Capabilities};
+
+/// The maximum number of visits of a block that are dumped.
+const MAX_DUMPED_VISITS: usize = 100;
+
+/// Parses the block to debug from the `PCS_DEBUG_BLOCK` environment variable,
+/// e.g. `PCS_DEBUG_BLOCK=bb7`.
+pub fn debug_block_from_env() -> Option<BasicBlock> {
+    let block = std::env::var("PCS_DEBUG_BLOCK").ok()?;
+    let index = block.strip_prefix("bb").unwrap_or(&block).parse().ok()?;
+    Some(BasicBlock::new(index))
+}
+
+/// Parses the maximum number of visits of any block from the
+/// `PCS_MAX_BLOCK_VISITS` environment variable, e.g. `PCS_MAX_BLOCK_VISITS=50`.
+pub fn max_block_visits_from_env() -> Option<usize> {
+    std::env::var("PCS_MAX_BLOCK_VISITS").ok()?.parse().ok()
+}
+
+/// Counts the visits of each block by the dataflow engine. With a maximum
+/// number of visits, panics once a block is visited more often than that, so
+/// that an analysis that does not converge fails instead of running forever.
+pub struct BlockVisitCounter {
+    visits: Rc<RefCell<IndexVec<BasicBlock, usize>>>,
+    max_visits: Option<usize>,
+}
+
+impl BlockVisitCounter {
+    pub fn new(num_blocks: usize, max_visits: Option<usize>) -> Self {
+        Self {
+            visits: Rc::new(RefCell::new(IndexVec::from_elem_n(0, num_blocks))),
+            max_visits,
+        }
+    }
+
+    /// A handle to the counts, which remains valid once the counter has been
+    /// moved into a visit hook.
+    pub fn visits(&self) -> Rc<RefCell<IndexVec<BasicBlock, usize>>> {
+        self.visits.clone()
+    }
+
+    pub fn visit(&mut self, block: BasicBlock) {
+        let visits = &mut self.visits.borrow_mut()[block];
+        *visits += 1;
+        if let Some(max_visits) = self.max_visits {
+            assert!(
+                *visits <= max_visits,
+                "{block:?} was visited more than {max_visits} times without reaching a fixpoint"
+            );
+        }
+    }
+}
+
+/// The visits of each block, as written to `fixpoint.json`.
+pub fn block_visits_to_schema(visits: &IndexVec<BasicBlock, usize>) -> schema::BlockVisits {
+    visits
+        .iter_enumerated()
+        .map(|(block, &count)| (format!("{:?}", block), count))
+        .collect()
+}
+
+/// Writes the entry state of `block` to `dir` every time the dataflow engine
+/// visits it, to help debug blocks that take long to reach a fixpoint.
+pub struct BlockVisitDumper<'a, 'tcx> {
+    block: BasicBlock,
+    dir: String,
+    repacker: PlaceRepacker<'a, 'tcx>,
+    visits: usize,
+    previous: Option<BlockVisit>,
+}
+
+impl<'a, 'tcx> BlockVisitDumper<'a, 'tcx> {
+    pub fn new(block: BasicBlock, dir: String, repacker: PlaceRepacker<'a, 'tcx>) -> Self {
+        Self {
+            block,
+            dir,
+            repacker,
+            visits: 0,
+            previous: None,
+        }
+    }
+
+    pub fn visit(&mut self, block: BasicBlock, state: &PlaceCapabilitySummary<'a, 'tcx>) {
+        if block != self.block || self.visits >= MAX_DUMPED_VISITS {
+            return;
+        }
+        let mut visit = BlockVisit {
+            block: format!("{:?}", block),
+            iteration: self.visits,
+            capabilities: state.fpcs.after.to_schema(self.repacker),
+            borrows: state.borrows.after.to_schema(self.repacker),
+            diff: None,
+        };
+        visit.diff = self.previous.as_ref().map(|previous| diff(previous, &visit));
+        std::fs::create_dir_all(&self.dir).expect("Failed to create debug directory");
+        let file_path = format!("{}/{:?}_iter{}.json", self.dir, block, self.visits);
+        let json = serde_json::to_string_pretty(&visit).unwrap();
+        std::fs::write(file_path, json).expect("Failed to write block visit to JSON file");
+        self.visits += 1;
+        self.previous = Some(visit);
+    }
+}
+
+fn diff(previous: &BlockVisit, current: &BlockVisit) -> BlockVisitDiff {
+    let map_minus = |left: &Capabilities, right: &Capabilities| {
+        left.iter()
+            .filter(|(place, kind)| right.get(*place) != Some(*kind))
+            .map(|(place, kind)| (place.clone(), kind.clone()))
+            .collect()
+    };
+    BlockVisitDiff {
+        capabilities_added: map_minus(&current.capabilities, &previous.capabilities),
+        capabilities_removed: map_minus(&previous.capabilities, &current.capabilities),
+        borrows_added: current
+            .borrows
+            .borrows
+            .iter()
+            .filter(|borrow| !previous.borrows.borrows.contains(borrow))
+            .cloned()
+            .collect(),
+        borrows_removed: previous
+            .borrows
+            .borrows
+            .iter()
+            .filter(|borrow| !current.borrows.borrows.contains(borrow))
+            .cloned()
+            .collect(),
+    }
+}
@@ -0,0 +1,49 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    borrows::engine::{BorrowAction, BorrowsDomain},
+    free_pcs::{FreePcsBasicBlock, RepackOp},
+};
+
+use super::schema::BlockHeat;
+
+/// Counts the repacks and borrow changes that happen in `block`, including
+/// the repacks on the edges to its successors.
+pub fn block_heat<'tcx>(block: &FreePcsBasicBlock<'tcx, BorrowsDomain<'tcx>>) -> BlockHeat {
+    let mut heat = BlockHeat::default();
+    for statement in &block.statements {
+        record_repacks(&mut heat, &statement.repacks_start);
+        record_repacks(&mut heat, &statement.repacks_middle);
+        for action in statement
+            .extra
+            .actions(true)
+            .into_iter()
+            .chain(statement.extra.actions(false))
+        {
+            match action {
+                BorrowAction::AddBorrow(_) => heat.borrows_added += 1,
+                BorrowAction::RemoveBorrow(_) => heat.borrows_removed += 1,
+            }
+        }
+        heat.old_places_created += statement.extra.new_old_places().count();
+    }
+    for succ in &block.terminator.succs {
+        record_repacks(&mut heat, &succ.repacks_start);
+    }
+    heat
+}
+
+fn record_repacks(heat: &mut BlockHeat, repacks: &[RepackOp<'_>]) {
+    for repack in repacks {
+        match repack {
+            RepackOp::Expand(..) | RepackOp::DerefShallowInit(..) => heat.expands += 1,
+            RepackOp::Collapse(..) => heat.collapses += 1,
+            RepackOp::Weaken(..) => heat.weakens += 1,
+            RepackOp::StorageDead(_) | RepackOp::IgnoreStorageDead(_) => {}
+        }
+    }
+}
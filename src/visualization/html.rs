@@ -0,0 +1,48 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A single-file HTML report of the analysis of a function, which can be
+//! shared and opened without the web viewer. The report embeds the same
+//! schema structs as the JSON files.
+
+use std::{collections::BTreeMap, io};
+
+use serde_derive::Serialize;
+
+use super::{
+    escape_html,
+    schema::{MirGraph, StatementState},
+};
+
+#[derive(Serialize)]
+struct ReportData<'a> {
+    function: &'a str,
+    mir: &'a MirGraph,
+    /// Keyed by location, e.g. `bb2[3]`.
+    states: &'a BTreeMap<String, StatementState>,
+}
+
+const TEMPLATE: &str = include_str!("report.html");
+
+pub fn generate_html_report(
+    path: &str,
+    function: &str,
+    mir: &MirGraph,
+    states: &BTreeMap<String, StatementState>,
+) -> io::Result<()> {
+    let data = ReportData {
+        function,
+        mir,
+        states,
+    };
+    // Prevent the embedded JSON from closing the surrounding script tag
+    let data = serde_json::to_string(&data)?.replace("</", "<\\/");
+    let html = TEMPLATE
+        .replace("{{FUNCTION}}", &escape_html(function))
+        .replace("{{BLOCK_COUNT}}", &mir.nodes.len().to_string())
+        .replace("{{DATA}}", &data);
+    std::fs::write(path, html)
+}
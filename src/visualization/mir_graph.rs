@@ -1,10 +1,9 @@
 use crate::{
     borrows::domain::{Borrow, BorrowsState, RegionAbstraction},
     free_pcs::{CapabilityKind, CapabilityLocal, CapabilitySummary},
-    rustc_interface,
-    utils::{Place, PlaceRepacker},
+    rustc_interface, unsupported,
+    utils::{ignore_cleanup_enabled, Place, PlaceRepacker},
 };
-use serde_derive::Serialize;
 use std::{
     collections::{HashSet, VecDeque},
     fs::File,
@@ -25,35 +24,19 @@ use rustc_interface::{
     index::IndexVec,
     middle::{
         mir::{
-            self, BinOp, Body, Local, Location, Operand, PlaceElem, Promoted, Rvalue, Statement,
-            TerminatorKind, UnwindAction, VarDebugInfo, RETURN_PLACE,
+            self, AggregateKind, BasicBlock, BasicBlockData, BinOp, Body, Local, Location,
+            Operand, PlaceElem, Promoted, Rvalue, Statement, StatementKind, TerminatorKind,
+            UnwindAction, VarDebugInfo, RETURN_PLACE,
         },
         ty::{self, GenericArgsRef, ParamEnv, RegionVid, TyCtxt},
     },
 };
 
-use super::{get_source_name_from_local, get_source_name_from_place};
-
-#[derive(Serialize)]
-struct MirGraph {
-    nodes: Vec<MirNode>,
-    edges: Vec<MirEdge>,
-}
-
-#[derive(Serialize)]
-struct MirNode {
-    id: String,
-    block: usize,
-    stmts: Vec<String>,
-    terminator: String,
-}
-
-#[derive(Serialize)]
-struct MirEdge {
-    source: String,
-    target: String,
-    label: String,
-}
+use super::{
+    get_source_name_from_local, get_source_name_from_place,
+    schema::{LongStmts, MirEdge, MirGraph, MirNode, MIR_LONG_STMTS_FILE},
+    write_json,
+};
 
 fn format_bin_op(op: &BinOp) -> String {
     match op {
@@ -62,12 +45,12 @@ fn format_bin_op(op: &BinOp) -> String {
         BinOp::Mul => "*".to_string(),
         BinOp::Div => "/".to_string(),
         BinOp::Rem => "%".to_string(),
-        BinOp::AddUnchecked => todo!(),
-        BinOp::SubUnchecked => todo!(),
-        BinOp::MulUnchecked => todo!(),
-        BinOp::BitXor => todo!(),
-        BinOp::BitAnd => todo!(),
-        BinOp::BitOr => todo!(),
+        BinOp::AddUnchecked => unsupported!("BinOp::AddUnchecked"),
+        BinOp::SubUnchecked => unsupported!("BinOp::SubUnchecked"),
+        BinOp::MulUnchecked => unsupported!("BinOp::MulUnchecked"),
+        BinOp::BitXor => unsupported!("BinOp::BitXor"),
+        BinOp::BitAnd => unsupported!("BinOp::BitAnd"),
+        BinOp::BitOr => unsupported!("BinOp::BitOr"),
         BinOp::Shl => "<<".to_string(),
         BinOp::ShlUnchecked => "<<".to_string(),
         BinOp::Shr => ">>".to_string(),
@@ -78,7 +61,7 @@ fn format_bin_op(op: &BinOp) -> String {
         BinOp::Ne => "!=".to_string(),
         BinOp::Ge => ">=".to_string(),
         BinOp::Gt => ">".to_string(),
-        BinOp::Offset => todo!(),
+        BinOp::Offset => "offset".to_string(),
     }
 }
 
@@ -102,19 +85,34 @@ fn format_operand<'tcx>(operand: &Operand<'tcx>, debug_info: &[VarDebugInfo]) ->
 fn format_rvalue<'tcx>(rvalue: &Rvalue<'tcx>, debug_info: &[VarDebugInfo]) -> String {
     match rvalue {
         Rvalue::Use(operand) => format_operand(operand, debug_info),
-        Rvalue::Repeat(_, _) => todo!(),
+        Rvalue::Repeat(operand, count) => {
+            format!("[{}; {}]", format_operand(operand, debug_info), count)
+        }
         Rvalue::Ref(region, kind, place) => {
             let kind = match kind {
                 mir::BorrowKind::Shared => "",
-                mir::BorrowKind::Shallow => "",
+                mir::BorrowKind::Shallow => "shallow",
                 mir::BorrowKind::Mut { .. } => "mut",
             };
             format!("&{} {}", kind, format_place(place, debug_info))
         }
-        Rvalue::ThreadLocalRef(_) => todo!(),
-        Rvalue::AddressOf(_, _) => todo!(),
-        Rvalue::Len(_) => todo!(),
-        Rvalue::Cast(_, _, _) => todo!(),
+        Rvalue::ThreadLocalRef(_) => unsupported!("Rvalue::ThreadLocalRef"),
+        Rvalue::AddressOf(mutability, place) => {
+            let mutability = match mutability {
+                mir::Mutability::Not => "const",
+                mir::Mutability::Mut => "mut",
+            };
+            format!("&raw {} {}", mutability, format_place(place, debug_info))
+        }
+        Rvalue::Len(place) => format!("Len({})", format_place(place, debug_info)),
+        Rvalue::Cast(_, operand, ty) => format!("{} as {}", format_operand(operand, debug_info), ty),
+        Rvalue::BinaryOp(BinOp::Offset, box (ptr, count)) => {
+            format!(
+                "{}.offset({})",
+                format_operand(ptr, debug_info),
+                format_operand(count, debug_info)
+            )
+        }
         Rvalue::BinaryOp(op, box (lhs, rhs)) | Rvalue::CheckedBinaryOp(op, box (lhs, rhs)) => {
             format!(
                 "{} {} {}",
@@ -123,9 +121,24 @@ fn format_rvalue<'tcx>(rvalue: &Rvalue<'tcx>, debug_info: &[VarDebugInfo]) -> St
                 format_operand(rhs, debug_info)
             )
         }
-        Rvalue::NullaryOp(_, _) => todo!(),
-        Rvalue::UnaryOp(_, _) => todo!(),
+        Rvalue::NullaryOp(op, ty) => format!("{:?}({})", op, ty),
+        Rvalue::UnaryOp(op, operand) => {
+            let op = match op {
+                mir::UnOp::Not => "!",
+                mir::UnOp::Neg => "-",
+            };
+            format!("{}{}", op, format_operand(operand, debug_info))
+        }
         Rvalue::Discriminant(place) => format!("Discriminant({})", format_place(place, debug_info)),
+        Rvalue::Aggregate(box AggregateKind::Array(_), ops) => {
+            format!(
+                "[{}]",
+                ops.iter()
+                    .map(|op| format_operand(op, debug_info))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
         Rvalue::Aggregate(kind, ops) => {
             format!(
                 "Aggregate {:?} {}",
@@ -136,8 +149,10 @@ fn format_rvalue<'tcx>(rvalue: &Rvalue<'tcx>, debug_info: &[VarDebugInfo]) -> St
                     .join(", ")
             )
         }
-        Rvalue::ShallowInitBox(_, _) => todo!(),
-        Rvalue::CopyForDeref(_) => todo!(),
+        Rvalue::ShallowInitBox(_, _) => unsupported!("Rvalue::ShallowInitBox"),
+        Rvalue::CopyForDeref(place) => {
+            format!("deref_copy {}", format_place(place, debug_info))
+        }
     }
 }
 fn format_terminator<'tcx>(
@@ -154,15 +169,19 @@ fn format_terminator<'tcx>(
             call_source,
             fn_span,
         } => {
-            format!(
-                "{} = {}({})",
-                format_place(destination, debug_info),
+            let call = format!(
+                "{}({})",
                 format_operand(func, debug_info),
                 args.iter()
                     .map(|arg| format_operand(arg, debug_info))
                     .collect::<Vec<_>>()
                     .join(", ")
-            )
+            );
+            match target {
+                Some(_) => format!("{} = {call}", format_place(destination, debug_info)),
+                // The destination is never written
+                None => format!("{call} (diverges)"),
+            }
         }
         _ => format!("{:?}", terminator),
     }
@@ -183,69 +202,148 @@ fn format_stmt<'tcx>(stmt: &Statement<'tcx>, debug_info: &[VarDebugInfo]) -> Str
         mir::StatementKind::SetDiscriminant {
             place,
             variant_index,
-        } => todo!(),
-        mir::StatementKind::Deinit(_) => todo!(),
+        } => unsupported!("StatementKind::SetDiscriminant"),
+        mir::StatementKind::Deinit(_) => unsupported!("StatementKind::Deinit"),
         mir::StatementKind::StorageLive(local) => {
             format!("StorageLive({})", format_local(local, debug_info))
         }
         mir::StatementKind::StorageDead(local) => {
             format!("StorageDead({})", format_local(local, debug_info))
         }
-        mir::StatementKind::Retag(_, _) => todo!(),
-        mir::StatementKind::PlaceMention(_) => todo!(),
-        mir::StatementKind::AscribeUserType(_, _) => {
-            format!("AscribeUserType(...)")
+        mir::StatementKind::Retag(_, _) => unsupported!("StatementKind::Retag"),
+        mir::StatementKind::PlaceMention(_) => unsupported!("StatementKind::PlaceMention"),
+        mir::StatementKind::AscribeUserType(box (place, _), variance) => {
+            format!(
+                "AscribeUserType({}, {:?})",
+                format_place(place, debug_info),
+                variance
+            )
         }
-        mir::StatementKind::Coverage(_) => todo!(),
-        mir::StatementKind::Intrinsic(_) => todo!(),
-        mir::StatementKind::ConstEvalCounter => todo!(),
-        mir::StatementKind::Nop => todo!(),
+        mir::StatementKind::Coverage(_) => unsupported!("StatementKind::Coverage"),
+        mir::StatementKind::Intrinsic(_) => unsupported!("StatementKind::Intrinsic"),
+        mir::StatementKind::ConstEvalCounter => unsupported!("StatementKind::ConstEvalCounter"),
+        mir::StatementKind::Nop => unsupported!("StatementKind::Nop"),
     }
 }
 
-fn mk_mir_graph(body: &Body<'_>) -> MirGraph {
+const DEFAULT_MAX_STMT_LEN: usize = 200;
+
+/// The maximum number of characters of a statement written to `mir.json`,
+/// can be overridden with the `PCS_MAX_STMT_LEN` environment variable.
+fn max_stmt_len() -> usize {
+    std::env::var("PCS_MAX_STMT_LEN")
+        .ok()
+        .and_then(|len| len.parse().ok())
+        .unwrap_or(DEFAULT_MAX_STMT_LEN)
+}
+
+/// Returns `None` if `stmt` has at most `max_len` characters, otherwise the
+/// first `max_len` characters followed by the number of omitted ones.
+fn truncate_stmt(stmt: &str, max_len: usize) -> Option<String> {
+    let len = stmt.chars().count();
+    if len <= max_len {
+        return None;
+    }
+    let prefix: String = stmt.chars().take(max_len).collect();
+    Some(format!("{}\u{2026} (+{} chars)", prefix, len - max_len))
+}
+
+/// The label of the edge taken by a `SwitchInt` on `discr` if it has the value
+/// `val`. If `discr` is the discriminant of an enum, this is the name of the
+/// corresponding variant.
+fn switch_target_label<'tcx>(
+    body: &Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    data: &BasicBlockData<'tcx>,
+    discr: &Operand<'tcx>,
+    val: u128,
+) -> String {
+    let enum_place = discr.place().and_then(|discr| {
+        data.statements.iter().rev().find_map(|stmt| match &stmt.kind {
+            StatementKind::Assign(box (target, Rvalue::Discriminant(place))) if *target == discr => {
+                Some(*place)
+            }
+            _ => None,
+        })
+    });
+    let variant = enum_place.and_then(|place| match place.ty(body, tcx).ty.kind() {
+        ty::TyKind::Adt(adt_def, _) if adt_def.is_enum() => adt_def
+            .discriminants(tcx)
+            .find(|(_, discr)| discr.val == val)
+            .map(|(idx, _)| adt_def.variant(idx).name.to_string()),
+        _ => None,
+    });
+    variant.unwrap_or_else(|| format!("{}", val))
+}
+
+fn mk_mir_graph<'tcx>(
+    body: &Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    max_stmt_len: usize,
+) -> (MirGraph, LongStmts) {
     let mut nodes = Vec::new();
     let mut edges = Vec::new();
+    let mut long_stmts = LongStmts::new();
+
+    let dominators = body.basic_blocks.dominators();
+    let predecessors = body.basic_blocks.predecessors();
+    // An edge is a back edge if its target dominates its source
+    let is_back_edge = |source: BasicBlock, target: BasicBlock| {
+        dominators.is_reachable(source) && dominators.dominates(target, source)
+    };
+    let mk_edge = |source: BasicBlock, target: BasicBlock, label: String| MirEdge {
+        source: source.as_usize(),
+        target: target.as_usize(),
+        label,
+        back_edge: is_back_edge(source, target),
+        imaginary: false,
+    };
+    // Only there for the borrow checker, see `utils::real_edges`
+    let mk_imaginary_edge = |source: BasicBlock, target: BasicBlock, label: String| MirEdge {
+        imaginary: true,
+        ..mk_edge(source, target, label)
+    };
 
     for (bb, data) in body.basic_blocks.iter_enumerated() {
-        let stmts = data
-            .statements
-            .iter()
-            .map(|stmt| format_stmt(stmt, &body.var_debug_info));
+        let stmts = data.statements.iter().enumerate().map(|(idx, stmt)| {
+            let stmt = format_stmt(stmt, &body.var_debug_info);
+            match truncate_stmt(&stmt, max_stmt_len) {
+                Some(truncated) => {
+                    long_stmts.insert(format!("{:?}:{}", bb, idx), stmt);
+                    truncated
+                }
+                None => stmt,
+            }
+        });
 
         let terminator = format_terminator(&data.terminator().kind, &body.var_debug_info);
 
         nodes.push(MirNode {
-            id: format!("{:?}", bb),
-            block: bb.as_usize(),
+            id: bb.as_usize(),
+            label: format!("{:?}", bb),
             stmts: stmts.collect(),
             terminator,
+            is_loop_head: predecessors[bb].iter().any(|pred| is_back_edge(*pred, bb)),
+            ignored: data.is_cleanup && ignore_cleanup_enabled(),
+            predecessor_count: predecessors[bb].len(),
+            place_statuses: vec![],
         });
 
         match &data.terminator().kind {
             TerminatorKind::Goto { target } => {
-                edges.push(MirEdge {
-                    source: format!("{:?}", bb),
-                    target: format!("{:?}", target),
-                    label: "goto".to_string(),
-                });
+                edges.push(mk_edge(bb, *target, "goto".to_string()));
             }
             TerminatorKind::SwitchInt { discr, targets } => {
                 for (val, target) in targets.iter() {
-                    edges.push(MirEdge {
-                        source: format!("{:?}", bb),
-                        target: format!("{:?}", target),
-                        label: format!("{}", val),
-                    });
+                    let label = switch_target_label(body, tcx, data, discr, val);
+                    edges.push(mk_edge(bb, target, label));
                 }
-                edges.push(MirEdge {
-                    source: format!("{:?}", bb),
-                    target: format!("{:?}", targets.otherwise()),
-                    label: "otherwise".to_string(),
-                });
+                edges.push(mk_edge(bb, targets.otherwise(), "otherwise".to_string()));
             }
             TerminatorKind::UnwindResume => {}
-            TerminatorKind::UnwindTerminate(_) => todo!(),
+            TerminatorKind::UnwindTerminate(_) => {
+                unsupported!("TerminatorKind::UnwindTerminate");
+            }
             TerminatorKind::Return => {}
             TerminatorKind::Unreachable => {}
             TerminatorKind::Drop {
@@ -254,11 +352,7 @@ fn mk_mir_graph(body: &Body<'_>) -> MirGraph {
                 unwind,
                 replace,
             } => {
-                edges.push(MirEdge {
-                    source: format!("{:?}", bb),
-                    target: format!("{:?}", target),
-                    label: "drop".to_string(),
-                });
+                edges.push(mk_edge(bb, *target, "drop".to_string()));
             }
             TerminatorKind::Call {
                 func,
@@ -270,22 +364,21 @@ fn mk_mir_graph(body: &Body<'_>) -> MirGraph {
                 fn_span,
             } => {
                 if let Some(target) = target {
-                    edges.push(MirEdge {
-                        source: format!("{:?}", bb),
-                        target: format!("{:?}", target),
-                        label: "call".to_string(),
-                    });
-                    match unwind {
-                        UnwindAction::Continue => todo!(),
-                        UnwindAction::Unreachable => todo!(),
-                        UnwindAction::Terminate(_) => todo!(),
-                        UnwindAction::Cleanup(cleanup) => {
-                            edges.push(MirEdge {
-                                source: format!("{:?}", bb),
-                                target: format!("{:?}", cleanup),
-                                label: "unwind".to_string(),
-                            });
-                        }
+                    edges.push(mk_edge(bb, *target, "call".to_string()));
+                }
+                // Diverging calls (without a target) can still unwind
+                match unwind {
+                    UnwindAction::Continue => {
+                        unsupported!("UnwindAction::Continue");
+                    }
+                    UnwindAction::Unreachable => {
+                        unsupported!("UnwindAction::Unreachable");
+                    }
+                    UnwindAction::Terminate(_) => {
+                        unsupported!("UnwindAction::Terminate");
+                    }
+                    UnwindAction::Cleanup(cleanup) => {
+                        edges.push(mk_edge(bb, *cleanup, "unwind".to_string()));
                     }
                 }
             }
@@ -297,49 +390,48 @@ fn mk_mir_graph(body: &Body<'_>) -> MirGraph {
                 unwind,
             } => {
                 match unwind {
-                    UnwindAction::Continue => todo!(),
-                    UnwindAction::Unreachable => todo!(),
-                    UnwindAction::Terminate(_) => todo!(),
+                    UnwindAction::Continue => {
+                        unsupported!("UnwindAction::Continue");
+                    }
+                    UnwindAction::Unreachable => {
+                        unsupported!("UnwindAction::Unreachable");
+                    }
+                    UnwindAction::Terminate(_) => {
+                        unsupported!("UnwindAction::Terminate");
+                    }
                     UnwindAction::Cleanup(cleanup) => {
-                        edges.push(MirEdge {
-                            source: format!("{:?}", bb),
-                            target: format!("{:?}", cleanup),
-                            label: format!("unwind"),
-                        });
+                        edges.push(mk_edge(bb, *cleanup, "unwind".to_string()));
                     }
                 }
-                edges.push(MirEdge {
-                    source: format!("{:?}", bb),
-                    target: format!("{:?}", target),
-                    label: format!("success"),
-                });
+                edges.push(mk_edge(bb, *target, "success".to_string()));
             }
             TerminatorKind::Yield {
                 value,
                 resume,
                 resume_arg,
                 drop,
-            } => todo!(),
-            TerminatorKind::GeneratorDrop => todo!(),
+            } => {
+                edges.push(mk_edge(bb, *resume, "resume".to_string()));
+                if let Some(drop) = drop {
+                    edges.push(mk_edge(bb, *drop, "drop".to_string()));
+                }
+            }
+            TerminatorKind::GeneratorDrop => {}
             TerminatorKind::FalseEdge {
                 real_target,
                 imaginary_target,
             } => {
-                edges.push(MirEdge {
-                    source: format!("{:?}", bb),
-                    target: format!("{:?}", real_target),
-                    label: "real".to_string(),
-                });
+                edges.push(mk_edge(bb, *real_target, "real".to_string()));
+                edges.push(mk_imaginary_edge(bb, *imaginary_target, "imaginary".to_string()));
             }
             TerminatorKind::FalseUnwind {
                 real_target,
                 unwind,
             } => {
-                edges.push(MirEdge {
-                    source: format!("{:?}", bb),
-                    target: format!("{:?}", real_target),
-                    label: "real".to_string(),
-                });
+                edges.push(mk_edge(bb, *real_target, "real".to_string()));
+                if let UnwindAction::Cleanup(cleanup) = unwind {
+                    edges.push(mk_imaginary_edge(bb, *cleanup, "unwind".to_string()));
+                }
             }
             TerminatorKind::InlineAsm {
                 template,
@@ -348,15 +440,213 @@ fn mk_mir_graph(body: &Body<'_>) -> MirGraph {
                 line_spans,
                 destination,
                 unwind,
-            } => todo!(),
+            } => {
+                unsupported!("TerminatorKind::InlineAsm");
+            }
         }
     }
 
-    MirGraph { nodes, edges }
+    (MirGraph { nodes, edges }, long_stmts)
 }
-pub fn generate_json_from_mir(path: &str, body: &Body<'_>) -> io::Result<()> {
-    let mir_graph = mk_mir_graph(body);
-    let mut file = File::create(path)?;
-    serde_json::to_writer(&mut file, &mir_graph)?;
+
+/// The MIR graph of `body`, with statements truncated to the length given by
+/// `PCS_MAX_STMT_LEN`, and the full text of the truncated statements.
+pub fn mir_graph<'tcx>(body: &Body<'tcx>, tcx: TyCtxt<'tcx>) -> (MirGraph, LongStmts) {
+    mk_mir_graph(body, tcx, max_stmt_len())
+}
+
+/// Writes `mir_graph` to `path`. The full text of any statement that was
+/// truncated is written to `mir_long_stmts.json` in the same directory.
+pub fn write_mir_graph(path: &str, mir_graph: &MirGraph, long_stmts: &LongStmts) -> io::Result<()> {
+    write_json(path, mir_graph)?;
+    if !long_stmts.is_empty() {
+        let dir = std::path::Path::new(path)
+            .parent()
+            .unwrap_or_else(|| std::path::Path::new("."));
+        write_json(dir.join(MIR_LONG_STMTS_FILE), long_stmts)?;
+    }
     Ok(())
 }
+
+/// Writes the MIR graph of `body` to `path`, see [`write_mir_graph`].
+pub fn generate_json_from_mir<'tcx>(
+    path: &str,
+    body: &Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+) -> io::Result<MirGraph> {
+    let (mir_graph, long_stmts) = mir_graph(body, tcx);
+    write_mir_graph(path, &mir_graph, &long_stmts)?;
+    Ok(mir_graph)
+}
+
+/// The MIR graph of `body` as a Mermaid `flowchart`, with one node per basic
+/// block and the edges of [`mir_graph`]. Statements are never truncated,
+/// since there is no file to hold their full text.
+pub fn generate_mermaid_from_mir<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> String {
+    let (mir_graph, _) = mk_mir_graph(body, tcx, usize::MAX);
+    mermaid_from_mir_graph(&mir_graph)
+}
+
+/// Escapes `text` for use in a quoted Mermaid label.
+fn mermaid_escape(text: &str) -> String {
+    text.replace('#', "#35;")
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+fn mermaid_from_mir_graph(mir_graph: &MirGraph) -> String {
+    let mut out = String::from("flowchart TD\n");
+    for node in &mir_graph.nodes {
+        let lines = std::iter::once(&node.label)
+            .chain(&node.stmts)
+            .chain(std::iter::once(&node.terminator))
+            .map(|line| mermaid_escape(line))
+            .collect::<Vec<_>>();
+        out.push_str(&format!("    bb{}[\"{}\"]\n", node.id, lines.join("<br/>")));
+    }
+    for edge in &mir_graph.edges {
+        // Imaginary edges are dotted, as in the JSON viewer
+        let arrow = if edge.imaginary { "-.->" } else { "-->" };
+        out.push_str(&format!(
+            "    bb{} {}|\"{}\"| bb{}\n",
+            edge.source,
+            arrow,
+            mermaid_escape(&edge.label),
+            edge.target
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::with_body;
+
+    fn node(id: usize, stmts: &[&str], terminator: &str) -> MirNode {
+        MirNode {
+            id,
+            label: format!("bb{id}"),
+            stmts: stmts.iter().map(|stmt| stmt.to_string()).collect(),
+            terminator: terminator.to_string(),
+            is_loop_head: false,
+            ignored: false,
+            predecessor_count: 0,
+            place_statuses: vec![],
+        }
+    }
+
+    fn edge(source: usize, target: usize, label: &str, imaginary: bool) -> MirEdge {
+        MirEdge {
+            source,
+            target,
+            label: label.to_string(),
+            back_edge: false,
+            imaginary,
+        }
+    }
+
+    #[test]
+    fn mermaid_references_each_block() {
+        let mir_graph = MirGraph {
+            nodes: vec![
+                node(0, &["x = const 1_i32"], "falseEdge"),
+                node(1, &["y = &mut x"], "goto"),
+                node(2, &[], "return"),
+            ],
+            edges: vec![
+                edge(0, 1, "real", false),
+                edge(0, 2, "imaginary", true),
+                edge(1, 2, "goto", false),
+            ],
+        };
+        let mermaid = mermaid_from_mir_graph(&mir_graph);
+        assert!(mermaid.starts_with("flowchart"));
+        for block in 0..3 {
+            assert!(mermaid.contains(&format!("    bb{block}[\"bb{block}<br/>")));
+        }
+        assert!(mermaid.contains("bb0 -->|\"real\"| bb1"));
+        assert!(mermaid.contains("bb0 -.->|\"imaginary\"| bb2"));
+    }
+
+    #[test]
+    fn ascription_shows_place_and_variance() {
+        // `let x: &'a u32 = ...` ascribes the type of `_1`
+        let stmt = Statement {
+            source_info: mir::SourceInfo::outermost(rustc_interface::span::DUMMY_SP),
+            kind: StatementKind::AscribeUserType(
+                Box::new((
+                    Local::from_usize(1).into(),
+                    mir::UserTypeProjection {
+                        base: ty::UserTypeAnnotationIndex::from_usize(0),
+                        projs: vec![],
+                    },
+                )),
+                ty::Variance::Covariant,
+            ),
+        };
+        // The variance is printed as in rustc's MIR dumps
+        assert_eq!(format_stmt(&stmt, &[]), "AscribeUserType(_1, +)");
+    }
+
+    #[test]
+    fn offset_is_shown_as_method_call() {
+        // `ptr.offset(count)` after the intrinsic has been lowered
+        let place = |local| mir::Place::from(Local::from_usize(local));
+        let rvalue = Rvalue::BinaryOp(
+            BinOp::Offset,
+            Box::new((Operand::Copy(place(1)), Operand::Move(place(2)))),
+        );
+        assert_eq!(format_rvalue(&rvalue, &[]), "_1.offset(move _2)");
+    }
+
+    #[test]
+    fn switch_on_enum_is_labelled_with_variants() {
+        let source = "
+            pub enum Dir { North = 1, East = 5, South = 9 }
+            pub fn turn(d: Dir) -> u32 {
+                match d { Dir::North => 0, Dir::East => 1, Dir::South => 2 }
+            }
+        ";
+        let (mir_graph, _) = with_body(source, "turn", |tcx, mir| mir_graph(&mir.body, tcx));
+        let switch = mir_graph
+            .nodes
+            .iter()
+            .find(|node| node.terminator.starts_with("switchInt"))
+            .unwrap();
+        let mut labels: Vec<_> = mir_graph
+            .edges
+            .iter()
+            .filter(|edge| edge.source == switch.id)
+            .map(|edge| edge.label.as_str())
+            .collect();
+        labels.sort();
+        assert_eq!(labels, ["East", "North", "South", "otherwise"]);
+    }
+
+    #[test]
+    fn long_array_literal_is_truncated() {
+        let elements = (0..1000).map(|i| i.to_string()).collect::<Vec<_>>();
+        let source = format!("pub fn f() -> [u32; 1000] {{ [{}] }}", elements.join(", "));
+        let (mir_graph, long_stmts) = with_body(&source, "f", |tcx, mir| mir_graph(&mir.body, tcx));
+        assert_eq!(long_stmts.len(), 1);
+        let (key, stmt) = long_stmts.iter().next().unwrap();
+        assert!(stmt.contains("const 999_u32]"), "{stmt}");
+
+        let (block, index) = key.strip_prefix("bb").unwrap().split_once(':').unwrap();
+        let node = &mir_graph.nodes[block.parse::<usize>().unwrap()];
+        let truncated = &node.stmts[index.parse::<usize>().unwrap()];
+        let omitted = stmt.chars().count() - DEFAULT_MAX_STMT_LEN;
+        assert!(truncated.ends_with(&format!("\u{2026} (+{omitted} chars)")));
+        assert!(stmt.starts_with(truncated.split('\u{2026}').next().unwrap()));
+    }
+
+    #[test]
+    fn mermaid_escapes_labels() {
+        assert_eq!(
+            mermaid_escape("\"a\" <T> #1"),
+            "#quot;a#quot; #lt;T#gt; #35;1"
+        );
+    }
+}
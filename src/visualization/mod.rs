@@ -4,16 +4,24 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+pub mod compare;
+pub mod fixpoint_debug;
+pub mod heatmap;
+pub mod html;
 pub mod mir_graph;
+pub mod options;
+pub mod place_status;
+pub mod schema;
 
 use crate::{
     borrows::domain::{Borrow, BorrowKind, BorrowsState, MaybeOldPlace, RegionAbstraction},
     free_pcs::{CapabilityKind, CapabilityLocal, CapabilitySummary},
-    rustc_interface,
-    utils::{Place, PlaceRepacker},
+    rustc_interface, unsupported,
+    utils::{Place, PlaceRepacker, SnapshotLocation},
 };
 use std::{
-    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::Write as _,
     fs::File,
     io::{self, Write},
     rc::Rc,
@@ -39,12 +47,97 @@ use rustc_interface::{
     },
 };
 
+/// Writes `value` to `path` as JSON, pretty-printed if requested with
+/// `--pcs-json-pretty`.
+pub fn write_json<T: serde::Serialize>(
+    path: impl AsRef<std::path::Path>,
+    value: &T,
+) -> io::Result<()> {
+    let file = io::BufWriter::new(File::create(path)?);
+    if options::output_options().json_pretty {
+        serde_json::to_writer_pretty(file, value)?;
+    } else {
+        serde_json::to_writer(file, value)?;
+    }
+    Ok(())
+}
+
+/// Writes `meta.json` to `dir_path`, listing the files that were generated
+/// for each function in `functions`.
+pub fn generate_meta_json(
+    dir_path: &str,
+    functions: &schema::Functions,
+    facts: BTreeMap<String, schema::FactsMode>,
+    rustc_version: &str,
+) -> io::Result<()> {
+    let mut files = BTreeMap::new();
+    for (name, function_dir) in functions {
+        let mut function_files = std::fs::read_dir(format!("{}/{}", dir_path, function_dir))?
+            .map(|entry| entry.map(|e| e.file_name().to_string_lossy().into_owned()))
+            .collect::<io::Result<Vec<_>>>()?;
+        function_files.sort();
+        files.insert(name.clone(), function_files);
+    }
+    let meta = schema::Meta::new(
+        rustc_version.to_string(),
+        files,
+        facts,
+        crate::utils::ignore_cleanup_enabled(),
+    );
+    write_json(format!("{}/{}", dir_path, schema::META_FILE), &meta)
+}
+
+/// Collects the files generated in `dir_path` for each of `functions`
+/// (including `meta.json`, which must have been generated already) into the
+/// single file `out_path`.
+pub fn generate_combined_json(
+    dir_path: &str,
+    functions: &schema::Functions,
+    out_path: &str,
+) -> io::Result<()> {
+    let meta: schema::Meta =
+        serde_json::from_reader(File::open(format!("{}/{}", dir_path, schema::META_FILE))?)?;
+    let mut combined = BTreeMap::new();
+    for (name, function_dir) in functions {
+        let mut files = BTreeMap::new();
+        for file_name in &meta.files[name] {
+            let contents =
+                std::fs::read_to_string(format!("{dir_path}/{function_dir}/{file_name}"))?;
+            let value = if file_name.ends_with(".json") {
+                serde_json::from_str(&contents)?
+            } else {
+                serde_json::Value::String(contents)
+            };
+            files.insert(file_name.clone(), value);
+        }
+        combined.insert(name.clone(), files);
+    }
+    write_json(
+        out_path,
+        &schema::CombinedOutput {
+            meta,
+            functions: combined,
+        },
+    )
+}
+
+/// Escapes `input` for HTML text and attribute values, which includes the
+/// HTML-like labels of DOT graphs.
+pub(crate) fn escape_html(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
 pub fn place_id<'tcx>(place: &Place<'tcx>) -> String {
     format!("{:?}", place)
 }
 
-struct GraphDrawer {
-    file: File,
+struct GraphDrawer<W: io::Write> {
+    out: W,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -67,21 +160,28 @@ enum NodeType {
     PlaceNode {
         label: String,
         capability: Option<CapabilityKind>,
-        location: Option<Location>,
+        location: Option<SnapshotLocation>,
     },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 enum ReferenceEdgeType {
-    RustcBorrow(BorrowIndex, RegionVid),
+    /// The loans of the edge, with their regions.
+    RustcBorrow(Vec<(BorrowIndex, RegionVid)>),
     PCS,
 }
 
 impl std::fmt::Display for ReferenceEdgeType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::RustcBorrow(borrow_index, region_vid) => {
-                write!(f, "{:?}: {:?}", borrow_index, region_vid)
+            Self::RustcBorrow(loans) => {
+                let loans: Vec<_> = loans
+                    .iter()
+                    .map(|(borrow_index, region_vid)| {
+                        format!("{:?}: {:?}", borrow_index, region_vid)
+                    })
+                    .collect();
+                write!(f, "{}", loans.join(", "))
             }
             Self::PCS => write!(f, "PCS"),
         }
@@ -151,17 +251,27 @@ pub fn get_source_name_from_place<'tcx>(
                 mir::ProjectionElem::Field(field, _) => {
                     name = format!("{}.{}", name, field.as_usize());
                 }
-                mir::ProjectionElem::Index(_) => todo!(),
+                mir::ProjectionElem::Index(index) => {
+                    let index = get_source_name_from_local(index, debug_info)
+                        .unwrap_or_else(|| format!("{:?}", index));
+                    name = format!("{}[{}]", name, index);
+                }
                 mir::ProjectionElem::ConstantIndex {
-                    offset,
-                    min_length,
-                    from_end,
-                } => todo!(),
-                mir::ProjectionElem::Subslice { from, to, from_end } => todo!(),
+                    offset, from_end, ..
+                } => {
+                    let sign = if *from_end { "-" } else { "" };
+                    name = format!("{}[{}{}]", name, sign, offset);
+                }
+                mir::ProjectionElem::Subslice { from, to, from_end } => {
+                    let sign = if *from_end { "-" } else { "" };
+                    name = format!("{}[{}..{}{}]", name, from, sign, to);
+                }
                 mir::ProjectionElem::Downcast(d, v) => {
                     name = format!("downcast {:?} as {:?}", name, d);
                 }
-                mir::ProjectionElem::OpaqueCast(_) => todo!(),
+                mir::ProjectionElem::OpaqueCast(ty) => {
+                    name = format!("{} as {}", name, ty);
+                }
             }
         }
         name
@@ -173,7 +283,7 @@ struct GraphConstructor<'a, 'tcx> {
     repacker: Rc<PlaceRepacker<'a, 'tcx>>,
     borrows_domain: &'a BorrowsState<'tcx>,
     borrow_set: &'a BorrowSet<'tcx>,
-    inserted_nodes: Vec<(Place<'tcx>, Option<Location>)>,
+    inserted_nodes: Vec<(Place<'tcx>, Option<SnapshotLocation>)>,
     nodes: Vec<GraphNode>,
     edges: HashSet<GraphEdge>,
     rank: HashMap<NodeId, usize>,
@@ -198,14 +308,18 @@ impl<'a, 'tcx> GraphConstructor<'a, 'tcx> {
         }
     }
 
-    fn existing_node_id(&self, place: Place<'tcx>, location: Option<Location>) -> Option<NodeId> {
+    fn existing_node_id(
+        &self,
+        place: Place<'tcx>,
+        location: Option<SnapshotLocation>,
+    ) -> Option<NodeId> {
         self.inserted_nodes
             .iter()
             .position(|(p, n)| *p == place && *n == location)
             .map(|idx| NodeId(idx))
     }
 
-    fn node_id(&mut self, place: Place<'tcx>, location: Option<Location>) -> NodeId {
+    fn node_id(&mut self, place: Place<'tcx>, location: Option<SnapshotLocation>) -> NodeId {
         if let Some(idx) = self.existing_node_id(place, location) {
             idx
         } else {
@@ -227,19 +341,30 @@ impl<'a, 'tcx> GraphConstructor<'a, 'tcx> {
     fn insert_place_node(
         &mut self,
         place: Place<'tcx>,
-        location: Option<Location>,
+        location: Option<SnapshotLocation>,
         kind: Option<CapabilityKind>,
     ) -> NodeId {
         if let Some(node_id) = self.existing_node_id(place, location) {
             return node_id;
         }
         let id = self.node_id(place, location);
-        let label = get_source_name_from_place(
-            place.local,
-            place.projection,
-            &self.repacker.body().var_debug_info,
-        )
-        .unwrap_or_else(|| format!("{:?}: {}", place, place.ty(*self.repacker).ty));
+        // The debug info of closure bodies refers to captured places, which
+        // `get_source_name_from_place` does not understand
+        let label = if place.is_closure_env(*self.repacker) {
+            place.to_short_string(*self.repacker)
+        } else {
+            get_source_name_from_place(
+                place.local,
+                place.projection,
+                &self.repacker.body().var_debug_info,
+            )
+            .unwrap_or_else(|| format!("{:?}: {}", place, self.repacker.place_ty(place).ty))
+        };
+        let label = if place.is_unsized(*self.repacker) {
+            format!("{} opaque (unsized)", label)
+        } else {
+            label
+        };
         let node = GraphNode {
             id,
             node_type: NodeType::PlaceNode {
@@ -256,7 +381,7 @@ impl<'a, 'tcx> GraphConstructor<'a, 'tcx> {
     fn insert_place_and_previous_projections(
         &mut self,
         place: Place<'tcx>,
-        location: Option<Location>,
+        location: Option<SnapshotLocation>,
         kind: Option<CapabilityKind>,
     ) -> NodeId {
         let node = self.insert_place_node(place, location, kind);
@@ -292,21 +417,24 @@ impl<'a, 'tcx> GraphConstructor<'a, 'tcx> {
         for borrow in &self.borrows_domain.borrows {
             let borrowed_place = self.insert_place_and_previous_projections(
                 borrow.borrowed_place.place().into(),
-                borrow.borrowed_place.before_location(),
+                borrow.borrowed_place.snapshot_location(),
                 None,
             );
             let assigned_place = self.insert_place_and_previous_projections(
                 borrow.assigned_place.place().into(),
-                borrow.assigned_place.before_location(),
+                borrow.assigned_place.snapshot_location(),
                 None,
             );
-            match borrow.kind {
-                BorrowKind::Rustc(borrow_index) => {
-                    let borrow_data = &self.borrow_set[borrow_index];
+            match &borrow.kind {
+                BorrowKind::Rustc(loans) => {
+                    let loans = loans
+                        .iter()
+                        .map(|borrow_index| (borrow_index, self.borrow_set[borrow_index].region))
+                        .collect();
                     self.edges.insert(GraphEdge::ReferenceEdge {
                         borrowed_place,
                         assigned_place,
-                        edge_type: ReferenceEdgeType::RustcBorrow(borrow_index, borrow_data.region),
+                        edge_type: ReferenceEdgeType::RustcBorrow(loans),
                     });
                 }
                 BorrowKind::PCS { .. } => {
@@ -319,23 +447,12 @@ impl<'a, 'tcx> GraphConstructor<'a, 'tcx> {
             }
         }
 
-        let mut before_places: HashSet<(Place<'tcx>, Location)> = HashSet::new();
-        for borrow in &self.borrows_domain.borrows {
-            if let MaybeOldPlace::OldPlace { place, before } = borrow.assigned_place {
-                before_places.insert((place, before));
-            }
-            if let MaybeOldPlace::OldPlace { place, before } = borrow.borrowed_place {
-                before_places.insert((place, before));
-            }
-        }
-        for (place, location) in before_places.iter() {
-            for (place2, location2) in before_places.iter() {
-                if location == location2 && place2.is_deref_of(*place) {
-                    let source = self.node_id(*place, Some(*location));
-                    let target = self.node_id(*place2, Some(*location));
-                    self.edges
-                        .insert(GraphEdge::ProjectionEdge { source, target });
-                }
+        for (base, expansion) in self.borrows_domain.deref_expansions() {
+            let source = self.node_id(base.place(), base.snapshot_location());
+            for place in expansion {
+                let target = self.node_id(place.place(), place.snapshot_location());
+                self.edges
+                    .insert(GraphEdge::ProjectionEdge { source, target });
             }
         }
 
@@ -345,31 +462,44 @@ impl<'a, 'tcx> GraphConstructor<'a, 'tcx> {
     }
 }
 
-impl GraphDrawer {
-    fn new(file_path: &str) -> Self {
-        let file = File::create(file_path).unwrap();
-        Self { file }
+impl<W: io::Write> GraphDrawer<W> {
+    fn new(out: W) -> Self {
+        Self { out }
     }
 
-    fn draw(mut self, graph: Graph) -> io::Result<()> {
-        writeln!(self.file, "digraph CapabilitySummary {{")?;
-        writeln!(self.file, "node [shape=rect]")?;
+    fn draw(mut self, graph: Graph, latest: &schema::Latest) -> io::Result<()> {
+        writeln!(self.out, "digraph CapabilitySummary {{")?;
+        writeln!(self.out, "node [shape=rect]")?;
         for node in graph.nodes {
             self.draw_node(node)?;
         }
         for edge in graph.edges {
             self.draw_edge(edge)?;
         }
-        writeln!(&mut self.file, "}}")
+        self.draw_latest(latest)?;
+        writeln!(&mut self.out, "}}")
     }
 
-    fn escape_html(input: String) -> String {
-        input
-            .replace("&", "&amp;")
-            .replace("<", "&lt;")
-            .replace(">", "&gt;")
-            .replace("\"", "&quot;")
-            .replace("'", "&#39;")
+    fn draw_latest(&mut self, latest: &schema::Latest) -> io::Result<()> {
+        if latest.is_empty() {
+            return Ok(());
+        }
+        let rows = latest
+            .iter()
+            .fold(String::new(), |mut rows, (place, location)| {
+                let _ = write!(
+                    rows,
+                    "<TR><TD>{}</TD><TD>{}</TD></TR>",
+                    escape_html(place),
+                    escape_html(location)
+                );
+                rows
+            });
+        writeln!(
+            self.out,
+            "    \"latest\" [shape=plaintext, label=<<TABLE BORDER=\"0\" CELLBORDER=\"1\"><TR><TD COLSPAN=\"2\">latest</TD></TR>{}</TABLE>>];",
+            rows
+        )
     }
 
     fn draw_node(&mut self, node: GraphNode) -> io::Result<()> {
@@ -385,15 +515,15 @@ impl GraphDrawer {
                 };
                 let location_text = match location {
                     Some(l) => {
-                        let base = Self::escape_html(format!("before {:?}", l));
+                        let base = escape_html(&l.to_string());
                         format!("<br/>{}", base)
                     }
                     None => "".to_string(),
                 };
                 writeln!(
-                    self.file,
+                    self.out,
                     "    \"{}\" [label=<<FONT FACE=\"courier\">{}</FONT>&nbsp;{}{}>, fontcolor=\"{}\", color=\"{}\"];",
-                    node.id, Self::escape_html(label), Self::escape_html(capability_text), location_text, color, color
+                    node.id, escape_html(&label), escape_html(&capability_text), location_text, color, color
                 )?;
             }
         }
@@ -418,7 +548,7 @@ impl GraphDrawer {
             ""
         };
         writeln!(
-            self.file,
+            self.out,
             "    \"{}\" -> \"{}\" [label=\"{}\"{}{}]",
             source, target, label, style_part, arrowhead_part
         )
@@ -447,6 +577,23 @@ impl GraphDrawer {
     }
 }
 
+/// The graph written by [`generate_dot_graph`], in DOT format.
+pub fn dot_graph<'a, 'tcx: 'a>(
+    repacker: Rc<PlaceRepacker<'a, 'tcx>>,
+    summary: &CapabilitySummary<'tcx>,
+    borrows_domain: &BorrowsState<'tcx>,
+    borrow_set: &BorrowSet<'tcx>,
+) -> String {
+    let latest = borrows_domain.latest.to_schema(*repacker);
+    let constructor = GraphConstructor::new(summary, repacker, borrows_domain, borrow_set);
+    let graph = constructor.construct_graph();
+    let mut dot = Vec::new();
+    GraphDrawer::new(&mut dot)
+        .draw(graph, &latest)
+        .expect("Writing to a Vec cannot fail");
+    String::from_utf8(dot).unwrap()
+}
+
 pub fn generate_dot_graph<'a, 'tcx: 'a>(
     location: Location,
     repacker: Rc<PlaceRepacker<'a, 'tcx>>,
@@ -456,10 +603,10 @@ pub fn generate_dot_graph<'a, 'tcx: 'a>(
     input_facts: &PoloniusInput,
     file_path: &str,
 ) -> io::Result<()> {
-    let constructor = GraphConstructor::new(summary, repacker, borrows_domain, borrow_set);
-    let graph = constructor.construct_graph();
-    let mut drawer = GraphDrawer::new(file_path);
-    drawer.draw(graph)
+    std::fs::write(
+        file_path,
+        dot_graph(repacker, summary, borrows_domain, borrow_set),
+    )
 
     // for (idx, region_abstraction) in borrows_domain.region_abstractions.iter().enumerate() {
     //     let ra_node_label = format!("ra{}", idx);
@@ -490,3 +637,72 @@ pub fn generate_dot_graph<'a, 'tcx: 'a>(
     //     }
     // }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rustc_interface::middle::mir::START_BLOCK;
+
+    use super::*;
+    use crate::{combined_pcs::PcsContext, run_combined_pcs, test_utils::with_body};
+
+    #[test]
+    fn combined_file_contains_every_function() {
+        let dir = std::env::temp_dir().join(format!("pcs-combined-{}", std::process::id()));
+        let dir_path = dir.to_str().unwrap();
+        let functions = schema::Functions::from([
+            ("f".to_string(), "f".to_string()),
+            ("m::g".to_string(), "m_g".to_string()),
+        ]);
+        for function_dir in functions.values() {
+            fs::create_dir_all(dir.join(function_dir)).unwrap();
+            fs::write(dir.join(function_dir).join("mir.json"), r#"{"nodes":[]}"#).unwrap();
+            fs::write(dir.join(function_dir).join("graph.dot"), "digraph {}").unwrap();
+        }
+        generate_meta_json(dir_path, &functions, BTreeMap::new(), "rustc").unwrap();
+        let out_path = dir.join("combined.json");
+        generate_combined_json(dir_path, &functions, out_path.to_str().unwrap()).unwrap();
+
+        let combined: schema::CombinedOutput =
+            serde_json::from_str(&fs::read_to_string(&out_path).unwrap()).unwrap();
+        assert_eq!(combined.functions.keys().collect::<Vec<_>>(), ["f", "m::g"]);
+        for files in combined.functions.values() {
+            assert_eq!(files["mir.json"], serde_json::json!({ "nodes": [] }));
+            assert_eq!(files["graph.dot"], "digraph {}");
+        }
+        assert_eq!(combined.meta.files.len(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn dot_graph_of_a_borrow() {
+        let source = "pub fn f(x: &mut u32) { let r = &mut *x; *r = 1; }";
+        let dot = with_body(source, "f", |tcx, mir| {
+            let rp = PcsContext::new(tcx, mir).rp;
+            let mut cursor = run_combined_pcs(mir, tcx);
+            // After `_2 = &mut (*_1)`
+            cursor.seek(Location {
+                block: START_BLOCK,
+                statement_index: 1,
+            });
+            assert!(!cursor.borrows().borrows.is_empty());
+            dot_graph(
+                Rc::new(rp),
+                cursor.owned(),
+                cursor.borrows(),
+                &mir.borrow_set,
+            )
+        });
+        assert!(dot.starts_with("digraph CapabilitySummary {\n"), "{dot}");
+        assert!(dot.ends_with("}\n"), "{dot}");
+        for node in ["*x</FONT>&nbsp;E", "r</FONT>&nbsp;E"] {
+            assert!(dot.contains(node), "{dot}");
+        }
+        let borrow_edges = dot
+            .lines()
+            .filter(|line| line.contains("style=\"dashed\""))
+            .count();
+        assert_eq!(borrow_edges, 1, "{dot}");
+    }
+}
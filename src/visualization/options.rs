@@ -0,0 +1,44 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::sync::Mutex;
+
+/// Controls which files are written to the visualization directory, set from
+/// the `--pcs-*` command line flags.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutputOptions {
+    /// Also write a self-contained `report.html` for each function.
+    pub emit_html: bool,
+    /// Pretty-print the JSON files instead of writing them compactly.
+    pub json_pretty: bool,
+    /// Cross-check the borrows against the loans in scope per rustc and write
+    /// the discrepancies to `warnings.json`, see `borrows::loan_check`.
+    pub check_loans: bool,
+}
+
+impl OutputOptions {
+    const DEFAULT: Self = Self {
+        emit_html: false,
+        json_pretty: false,
+        check_loans: false,
+    };
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+static OPTIONS: Mutex<OutputOptions> = Mutex::new(OutputOptions::DEFAULT);
+
+pub fn set_output_options(options: OutputOptions) {
+    *OPTIONS.lock().unwrap() = options;
+}
+
+pub fn output_options() -> OutputOptions {
+    *OPTIONS.lock().unwrap()
+}
@@ -0,0 +1,126 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    borrows::domain::BorrowsState,
+    free_pcs::{CapabilityKind, CapabilityLocal, CapabilitySummary},
+    utils::{LeafConfig, Place, PlaceRepacker},
+};
+
+use super::schema::{PlaceStatus, PlaceStatusKind};
+
+fn status_of<'tcx>(
+    place: Place<'tcx>,
+    kind: CapabilityKind,
+    borrows: &BorrowsState<'tcx>,
+    repacker: PlaceRepacker<'_, 'tcx>,
+) -> PlaceStatusKind {
+    let is_borrowed = borrows.live_borrows().any(|borrow| {
+        borrow
+            .borrowed_place
+            .place()
+            .conflicts_with(place, repacker)
+    });
+    if is_borrowed {
+        return PlaceStatusKind::Borrowed;
+    }
+    match kind {
+        CapabilityKind::Exclusive | CapabilityKind::ShallowExclusive => PlaceStatusKind::Live,
+        // Without capability to read, the place was either moved out of (if it
+        // held a value before) or was never initialized
+        CapabilityKind::Write => {
+            let is_arg = (1..=repacker.body().arg_count).contains(&place.local.as_usize());
+            if is_arg || borrows.latest_of(place).is_some() {
+                PlaceStatusKind::Moved
+            } else {
+                PlaceStatusKind::Uninit
+            }
+        }
+    }
+}
+
+/// The status of each place with a capability, and of each place borrowed by
+/// a live borrow, ordered by name.
+pub fn place_statuses<'tcx>(
+    summary: &CapabilitySummary<'tcx>,
+    borrows: &BorrowsState<'tcx>,
+    repacker: PlaceRepacker<'_, 'tcx>,
+) -> Vec<PlaceStatus> {
+    let mut statuses = BTreeMap::new();
+    for local in summary.iter() {
+        if let CapabilityLocal::Allocated(projections) = local {
+            for (&place, &kind) in projections.iter() {
+                statuses.insert(
+                    place.to_short_string(repacker),
+                    (place, status_of(place, kind, borrows, repacker)),
+                );
+            }
+        }
+    }
+    for borrow in borrows.live_borrows() {
+        let place = borrow.borrowed_place.place();
+        statuses
+            .entry(place.to_short_string(repacker))
+            .or_insert((place, PlaceStatusKind::Borrowed));
+    }
+    statuses
+        .into_iter()
+        .map(|(name, (place, status))| PlaceStatus {
+            place: name,
+            status,
+            expandable: place.leaves(repacker, LeafConfig::default()) != [place],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        run_free_pcs_no_output, rustc_interface::middle::mir::START_BLOCK, test_utils::with_body,
+    };
+
+    #[test]
+    fn moved_place_is_marked_moved() {
+        let source = "
+            pub fn f(x: String) -> String {
+                let y = x;
+                y
+            }
+        ";
+        let statuses = with_body(source, "f", |tcx, mir| {
+            let mut analysis = run_free_pcs_no_output(mir, tcx);
+            let rp = analysis.repacker();
+            analysis
+                .get_all_for_bb(START_BLOCK)
+                .statements
+                .iter()
+                .map(|statement| place_statuses(&statement.state, &statement.extra.after, rp))
+                .collect::<Vec<_>>()
+        });
+        let status = |statuses: &[PlaceStatus], name| {
+            statuses
+                .iter()
+                .find(|status| status.place == name)
+                .map(|status| status.status)
+        };
+        let moved = statuses
+            .iter()
+            .position(|statuses| status(statuses, "x") == Some(PlaceStatusKind::Moved))
+            .expect("`x` is never moved");
+        assert_eq!(status(&statuses[moved], "y"), Some(PlaceStatusKind::Live));
+        // Before the move, `x` is live and `y` was never initialized
+        assert!(moved > 0);
+        assert_eq!(status(&statuses[0], "x"), Some(PlaceStatusKind::Live));
+        assert_eq!(status(&statuses[0], "y"), Some(PlaceStatusKind::Uninit));
+        // `y` is not an argument, but was written before it is moved out of
+        let returned = statuses.last().unwrap();
+        assert_eq!(status(returned, "y"), Some(PlaceStatusKind::Moved));
+        assert_eq!(status(returned, "RETURN"), Some(PlaceStatusKind::Live));
+    }
+}
@@ -0,0 +1,543 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The shape of every JSON file read by the web viewer. Bump
+//! [`SCHEMA_VERSION`] whenever any of these types changes.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde_derive::{Deserialize, Serialize};
+
+pub const SCHEMA_VERSION: u32 = 25;
+
+pub const META_FILE: &str = "meta.json";
+pub const FUNCTIONS_FILE: &str = "functions.json";
+pub const MIR_FILE: &str = "mir.json";
+pub const MIR_LONG_STMTS_FILE: &str = "mir_long_stmts.json";
+pub const HEATMAP_FILE: &str = "heatmap.json";
+pub const HTML_REPORT_FILE: &str = "report.html";
+pub const CAPABILITIES_FILE: &str = "capabilities.json";
+pub const ACTIONS_FILE: &str = "actions.json";
+pub const FIXPOINT_FILE: &str = "fixpoint.json";
+pub const WARNINGS_FILE: &str = "warnings.json";
+pub const SIGNATURE_FILE: &str = "signature.json";
+pub const UNSUPPORTED_FILE: &str = "unsupported.json";
+
+/// `functions.json`: maps the name of each analysed function to the
+/// directory containing its output.
+pub type Functions = HashMap<String, String>;
+
+/// `meta.json`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Meta {
+    pub schema_version: u32,
+    pub pcs_version: String,
+    pub rustc_version: String,
+    /// The files generated for each function, relative to its directory.
+    pub files: BTreeMap<String, Vec<String>>,
+    /// The facts of the borrow checker each function was analyzed with.
+    #[serde(default)]
+    pub facts: BTreeMap<String, FactsMode>,
+    /// Whether cleanup blocks were skipped (`PCS_IGNORE_CLEANUP=1`), so that
+    /// the results only hold on paths that do not unwind.
+    #[serde(default)]
+    pub happy_path_only: bool,
+}
+
+impl Meta {
+    pub fn new(
+        rustc_version: String,
+        files: BTreeMap<String, Vec<String>>,
+        facts: BTreeMap<String, FactsMode>,
+        happy_path_only: bool,
+    ) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            pcs_version: env!("CARGO_PKG_VERSION").to_string(),
+            rustc_version,
+            files,
+            facts,
+            happy_path_only,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FactsMode {
+    /// The input and output facts of Polonius.
+    Polonius,
+    /// Only the input facts, after Polonius failed on the function.
+    Nll,
+}
+
+/// The single file written with `--pcs-combined-output`: the content of
+/// every file of every function, keyed by function name and then file name.
+/// JSON files are embedded as JSON values, other files (e.g. DOT graphs) as
+/// strings.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CombinedOutput {
+    pub meta: Meta,
+    pub functions: BTreeMap<String, BTreeMap<String, serde_json::Value>>,
+}
+
+/// `mir.json`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MirGraph {
+    pub nodes: Vec<MirNode>,
+    pub edges: Vec<MirEdge>,
+}
+
+/// `mir_long_stmts.json`: the full text of the statements that were
+/// truncated in `mir.json`, keyed by `bb{block}:{statement_index}`.
+pub type LongStmts = BTreeMap<String, String>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MirNode {
+    /// The index of the block, referred to by [`MirEdge::source`] and
+    /// [`MirEdge::target`].
+    pub id: usize,
+    /// The name of the block for display, e.g. `bb3`.
+    pub label: String,
+    pub stmts: Vec<String>,
+    pub terminator: String,
+    /// Whether the block is the target of a back edge.
+    pub is_loop_head: bool,
+    /// Whether the block is a cleanup block that was not analyzed, see
+    /// [`Meta::happy_path_only`]. Its place statuses are empty.
+    #[serde(default)]
+    pub ignored: bool,
+    pub predecessor_count: usize,
+    /// The status of the places after each statement, the last entry is for
+    /// the terminator. Empty if the analysis was not run.
+    pub place_statuses: Vec<Vec<PlaceStatus>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PlaceStatus {
+    pub place: String,
+    pub status: PlaceStatusKind,
+    /// Whether the place could be expanded into several places by its type
+    /// (see `Place::leaves`).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub expandable: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaceStatusKind {
+    Live,
+    Moved,
+    Borrowed,
+    Uninit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MirEdge {
+    /// The [`MirNode::id`] of the block the edge leaves.
+    pub source: usize,
+    /// The [`MirNode::id`] of the block the edge enters.
+    pub target: usize,
+    pub label: String,
+    /// Whether the target of the edge dominates its source.
+    pub back_edge: bool,
+    /// Whether control never flows along the edge: the imaginary target of
+    /// a `FalseEdge` or the unwind target of a `FalseUnwind`.
+    pub imaginary: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MaybeOldPlace {
+    pub place: String,
+    /// For old places, when the snapshot was taken, e.g. `before bb2[3]`.
+    pub at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Borrow {
+    pub kind: String,
+    pub borrowed_place: MaybeOldPlace,
+    pub assigned_place: MaybeOldPlace,
+    pub is_mut: bool,
+    /// For a two-phase borrow, when it is activated, e.g.
+    /// `activated at bb1[2]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub two_phase: Option<String>,
+    /// Where the borrow was created; only with the `debug_info` feature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug_info: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BorrowsState {
+    pub borrows: Vec<Borrow>,
+    pub latest: Latest,
+    /// The places blocked by each place at the end of a chain of borrows
+    /// (see `BorrowsState::leaf_places`), ordered by place.
+    #[serde(default)]
+    pub blocked_sets: Vec<BlockedSet>,
+}
+
+/// The places that stay blocked as long as `place` is kept alive, ordered
+/// by place.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlockedSet {
+    pub place: MaybeOldPlace,
+    pub blocked: Vec<MaybeOldPlace>,
+}
+
+/// Maps each place written since the start of the function to the point of
+/// its most recent write, e.g. `after bb2[3]` or `join bb4`.
+pub type Latest = BTreeMap<String, String>;
+
+/// `block_{bb}_stmt_{idx}_borrows.json`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BorrowsDomain {
+    pub before_start: BorrowsState,
+    pub before_after: BorrowsState,
+    pub start: BorrowsState,
+    pub after: BorrowsState,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "action")]
+pub enum BorrowAction {
+    AddBorrow { borrow: Borrow },
+    RemoveBorrow { borrow: Borrow },
+}
+
+/// `actions.json`: the actions taken at each location of the function at
+/// which there are any, ordered by location.
+pub type ActionsByLocation = Vec<LocationActions>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LocationActions {
+    pub location: Location,
+    /// In the order in which they are applied.
+    pub actions: Vec<PcsAction>,
+}
+
+/// `warnings.json`: the discrepancies found by `--pcs-check-loans`, ordered
+/// by location. `unsupported.json` has the same shape and lists the places
+/// that are not tracked, e.g. because they are behind a raw pointer.
+pub type Warnings = Vec<Warning>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub location: Location,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    pub block: usize,
+    pub statement_index: usize,
+}
+
+/// A place both in rendered form (e.g. `x.f`) and in structured form, so that
+/// consumers need not parse the rendering.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Place {
+    pub place: String,
+    pub local: usize,
+    pub projections: Vec<PlaceElem>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PlaceElem {
+    Deref,
+    Field { field: usize },
+    /// An index by the value of a local.
+    Index { local: usize },
+    ConstantIndex { offset: u64, min_length: u64, from_end: bool },
+    Subslice { from: u64, to: u64, from_end: bool },
+    Downcast { variant: usize, name: Option<String> },
+    OpaqueCast,
+}
+
+/// A repack of the owned places or a change of the borrows. Capabilities are
+/// rendered as in `capabilities.json`, e.g. `E`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "action")]
+pub enum PcsAction {
+    StorageDead { local: usize },
+    IgnoreStorageDead { local: usize },
+    Weaken { place: Place, from: String, to: String },
+    Expand { place: Place, guide: Place, capability: String },
+    Collapse { place: Place, guide: Place, capability: String },
+    DerefShallowInit { place: Place, guide: Place },
+    AddBorrow { borrow: Borrow },
+    RemoveBorrow { borrow: Borrow },
+}
+
+/// `block_{bb}_stmt_{idx}_borrows_graph.json`: the borrows state after the
+/// statement as a graph. Edges refer to nodes by their index in `nodes`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BorrowsGraph {
+    pub nodes: Vec<BorrowsGraphNode>,
+    pub edges: Vec<BorrowsGraphEdge>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type")]
+pub enum BorrowsGraphNode {
+    Place { place: MaybeOldPlace },
+    /// The region abstraction with the given index in the borrows state.
+    Abstraction { index: usize },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type")]
+pub enum BorrowsGraphEdge {
+    Borrow {
+        kind: String,
+        borrowed_place: usize,
+        assigned_place: usize,
+        is_mut: bool,
+    },
+    /// The old place `base` is dereferenced, and `expansion` are the places
+    /// reached through the dereference, see `BorrowsState::deref_expansions`.
+    DerefExpansion {
+        base: usize,
+        expansion: Vec<usize>,
+        /// `base` as a short string, e.g. `p`.
+        base_place: String,
+        /// `expansion` as sorted short strings, e.g. `["(*p).x", "(*p).y"]`.
+        expansion_places: Vec<String>,
+    },
+    AbstractionLoanIn { place: usize, abstraction: usize },
+    AbstractionLoanOut { abstraction: usize, place: usize },
+}
+
+/// `signature.json`: the lifetimes in the signature of the function, in order
+/// of first appearance.
+pub type SignatureRegions = Vec<SignatureRegion>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SignatureRegion {
+    /// The region variable standing for the lifetime in the body, e.g. `'?1`.
+    pub region: String,
+    /// The name of the lifetime, e.g. `'a`, unless it is anonymous.
+    pub name: Option<String>,
+}
+
+/// `heatmap.json`: maps each block id (e.g. `bb3`) to the amount of
+/// capability churn in that block.
+pub type Heatmap = BTreeMap<String, BlockHeat>;
+
+/// `fixpoint.json`: maps each block id (e.g. `bb3`) to the number of times
+/// the dataflow engine visited it before reaching a fixpoint.
+pub type BlockVisits = BTreeMap<String, usize>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockHeat {
+    pub expands: usize,
+    pub collapses: usize,
+    pub weakens: usize,
+    pub borrows_added: usize,
+    pub borrows_removed: usize,
+    pub old_places_created: usize,
+}
+
+/// Maps each place with a capability to that capability, e.g. `x.f` to `E`.
+pub type Capabilities = BTreeMap<String, String>;
+
+/// `capabilities.json`: the capabilities after each statement, keyed by
+/// location, e.g. `bb2[3]`.
+pub type CapabilitiesByLocation = BTreeMap<String, Capabilities>;
+
+/// The state after a statement, as embedded in `report.html`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StatementState {
+    pub capabilities: Capabilities,
+    pub borrows: BorrowsState,
+}
+
+/// `debug/{block}_iter{n}.json`: the entry state of a block on the `n`-th
+/// time the dataflow engine visits it, see `PCS_DEBUG_BLOCK`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlockVisit {
+    pub block: String,
+    pub iteration: usize,
+    pub capabilities: Capabilities,
+    pub borrows: BorrowsState,
+    /// The difference to the previous visit, absent on the first one.
+    pub diff: Option<BlockVisitDiff>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockVisitDiff {
+    pub capabilities_added: Capabilities,
+    pub capabilities_removed: Capabilities,
+    pub borrows_added: Vec<Borrow>,
+    pub borrows_removed: Vec<Borrow>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use serde::{de::DeserializeOwned, Serialize};
+    use serde_json::Value;
+
+    use super::*;
+    use crate::{
+        run_free_pcs,
+        test_utils::with_body,
+        visualization::{generate_combined_json, generate_meta_json, write_json},
+    };
+
+    /// Parses `path` as a `T`, and checks that no field was dropped by
+    /// serializing it back.
+    fn check_file<T: Serialize + DeserializeOwned>(path: &Path) {
+        let contents = fs::read_to_string(path).unwrap();
+        let value: Value = serde_json::from_str(&contents).unwrap();
+        let parsed: T = serde_json::from_str(&contents)
+            .unwrap_or_else(|err| panic!("{} does not match its schema: {err}", path.display()));
+        assert_eq!(
+            serde_json::to_value(parsed).unwrap(),
+            value,
+            "{}",
+            path.display()
+        );
+    }
+
+    #[test]
+    fn emitted_files_match_the_schema() {
+        let dir = std::env::temp_dir().join(format!("pcs-schema-{}", std::process::id()));
+        let dir_path = dir.to_str().unwrap().to_string();
+        let function_dir = format!("{dir_path}/f");
+        with_body(
+            "pub fn f(x: &mut (u32, u32), b: bool) -> &mut u32 {
+                let y = &mut x.0;
+                if b { *y += 1; }
+                y
+            }",
+            "f",
+            |tcx, mir| {
+                run_free_pcs(mir, tcx, Some(&function_dir));
+            },
+        );
+        let functions = Functions::from([("f".to_string(), "f".to_string())]);
+        write_json(dir.join(FUNCTIONS_FILE), &functions).unwrap();
+        let facts = BTreeMap::from([("f".to_string(), FactsMode::Polonius)]);
+        generate_meta_json(&dir_path, &functions, facts, "rustc").unwrap();
+        let combined = dir.join("combined.json");
+        generate_combined_json(&dir_path, &functions, combined.to_str().unwrap()).unwrap();
+
+        check_file::<Functions>(&dir.join(FUNCTIONS_FILE));
+        check_file::<Meta>(&dir.join(META_FILE));
+        check_file::<CombinedOutput>(&combined);
+        let mut checked = 0;
+        for entry in fs::read_dir(&function_dir).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_str().unwrap();
+            match name {
+                MIR_FILE => check_file::<MirGraph>(&path),
+                MIR_LONG_STMTS_FILE => check_file::<LongStmts>(&path),
+                HEATMAP_FILE => check_file::<Heatmap>(&path),
+                CAPABILITIES_FILE => check_file::<CapabilitiesByLocation>(&path),
+                ACTIONS_FILE => check_file::<ActionsByLocation>(&path),
+                FIXPOINT_FILE => check_file::<BlockVisits>(&path),
+                WARNINGS_FILE | UNSUPPORTED_FILE => check_file::<Warnings>(&path),
+                SIGNATURE_FILE => check_file::<SignatureRegions>(&path),
+                _ if name.ends_with("_borrows.json") => check_file::<BorrowsDomain>(&path),
+                _ if name.ends_with("_borrows_graph.json") => check_file::<BorrowsGraph>(&path),
+                _ if name.ends_with(".dot") => continue,
+                _ => panic!("{name} has no schema"),
+            }
+            checked += 1;
+        }
+        assert!(checked > 10, "Only {checked} files were emitted");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn actions_round_trip() {
+        let place = |place: &str, projections| Place {
+            place: place.to_string(),
+            local: 1,
+            projections,
+        };
+        let x = place("x", vec![]);
+        let x_0 = place("x.0", vec![PlaceElem::Field { field: 0 }]);
+        let borrow = Borrow {
+            kind: "PCS".to_string(),
+            borrowed_place: MaybeOldPlace {
+                place: "x".to_string(),
+                at: Some("before bb0[1]".to_string()),
+            },
+            assigned_place: MaybeOldPlace {
+                place: "r".to_string(),
+                at: None,
+            },
+            is_mut: true,
+            two_phase: Some("activated at bb1[2]".to_string()),
+            debug_info: None,
+        };
+        let actions: ActionsByLocation = vec![LocationActions {
+            location: Location {
+                block: 0,
+                statement_index: 1,
+            },
+            actions: vec![
+                PcsAction::StorageDead { local: 2 },
+                PcsAction::IgnoreStorageDead { local: 3 },
+                PcsAction::Weaken {
+                    place: x.clone(),
+                    from: "E".to_string(),
+                    to: "W".to_string(),
+                },
+                PcsAction::Expand {
+                    place: x.clone(),
+                    guide: x_0.clone(),
+                    capability: "E".to_string(),
+                },
+                PcsAction::Collapse {
+                    place: x.clone(),
+                    guide: x_0,
+                    capability: "E".to_string(),
+                },
+                PcsAction::DerefShallowInit {
+                    place: x.clone(),
+                    guide: place("*x", vec![PlaceElem::Deref]),
+                },
+                PcsAction::AddBorrow {
+                    borrow: borrow.clone(),
+                },
+                PcsAction::RemoveBorrow { borrow },
+            ],
+        }];
+        let json = serde_json::to_value(&actions).unwrap();
+        let tags: Vec<_> = json[0]["actions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|action| action["action"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            tags,
+            [
+                "StorageDead",
+                "IgnoreStorageDead",
+                "Weaken",
+                "Expand",
+                "Collapse",
+                "DerefShallowInit",
+                "AddBorrow",
+                "RemoveBorrow"
+            ]
+        );
+        assert_eq!(
+            json[0]["actions"][4]["guide"]["projections"][0]["kind"],
+            "field"
+        );
+        // Absent optional fields are omitted
+        assert!(json[0]["actions"][6]["borrow"].get("debug_info").is_none());
+        let parsed: ActionsByLocation = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, actions);
+    }
+}
@@ -0,0 +1,10 @@
+fn main() {
+    let mut a = 1;
+    let mut b = 2;
+    let refs = [&mut a, &mut b];
+    *refs[0] = 3;
+    a = 4;
+    let x = 5;
+    let shared = [&x; 4];
+    let _y = *shared[1];
+}
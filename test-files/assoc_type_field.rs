@@ -0,0 +1,22 @@
+struct Wrapper<I: Iterator> {
+    iter: I,
+    item: I::Item,
+}
+
+// Expanding `*w` creates `(*w).item`, whose declared type `I::Item` must be
+// normalized to `(u32, u32)` before `(*w).item` can be expanded in turn
+fn sibling(w: &mut Wrapper<std::iter::Once<(u32, u32)>>) -> u32 {
+    let it = &mut w.iter;
+    let r = &mut w.item.0;
+    *r += 1;
+    it.next();
+    w.item.1
+}
+
+fn main() {
+    let mut w = Wrapper {
+        iter: std::iter::once((1, 2)),
+        item: (0, 0),
+    };
+    sibling(&mut w);
+}
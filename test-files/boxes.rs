@@ -0,0 +1,8 @@
+fn f(b: Box<(u32, u32)>) -> u32 {
+    let r = &b.0;
+    *r + b.1
+}
+
+fn main() {
+    f(Box::new((1, 2)));
+}
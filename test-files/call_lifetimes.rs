@@ -0,0 +1,14 @@
+fn longest<'a>(x: &'a str, y: &'a str) -> &'a str {
+    if x.len() > y.len() {
+        x
+    } else {
+        y
+    }
+}
+
+fn main() {
+    let a = String::from("ab");
+    let b = String::from("c");
+    let l = longest(&a, &b);
+    let _n = l.len();
+}
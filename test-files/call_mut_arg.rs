@@ -0,0 +1,9 @@
+fn inc(x: &mut i32) -> i32 {
+    *x + 1
+}
+
+fn main() {
+    let mut x = 1;
+    x = inc(&mut x);
+    let _y = x;
+}
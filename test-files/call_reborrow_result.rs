@@ -0,0 +1,10 @@
+fn get(x: &mut (i32, i32)) -> &mut i32 {
+    &mut x.0
+}
+
+fn main() {
+    let mut p = (1, 2);
+    let r = get(&mut p);
+    *r = 3;
+    let _q = p.1;
+}
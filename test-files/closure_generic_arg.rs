@@ -0,0 +1,9 @@
+fn call<F: FnMut()>(mut f: F) {
+    f();
+}
+
+fn main() {
+    let mut n = 0;
+    call(|| n += 1);
+    let _m = n;
+}
@@ -0,0 +1,16 @@
+fn main() {
+    let mut n = 0;
+    let p = (1, 2);
+    let s = String::new();
+    let mut inc = || n += p.0;
+    inc();
+    let mv = move || {
+        let _t = s;
+    };
+    mv();
+    let nested = || {
+        let inner = || p.1;
+        inner()
+    };
+    nested();
+}
@@ -0,0 +1,22 @@
+// Overwriting `r` makes the loans of both fields behind it old: `r` as of
+// before the assignment expands into `(*r).x` and `(*r).y`.
+struct Pair {
+    x: u32,
+    y: u32,
+}
+
+fn fields_of_old<'a>(p: &'a mut Pair, q: &'a mut Pair) -> &'a mut u32 {
+    let mut r = p;
+    let x = &mut r.x;
+    let y = &mut r.y;
+    r = q;
+    r.x += 1;
+    *y += 1;
+    x
+}
+
+fn main() {
+    let mut p = Pair { x: 1, y: 2 };
+    let mut q = Pair { x: 3, y: 4 };
+    *fields_of_old(&mut p, &mut q) += 1;
+}
@@ -0,0 +1,29 @@
+fn fail(msg: &str) -> ! {
+    panic!("{}", msg)
+}
+
+fn check(x: &mut u32) -> u32 {
+    let r = &mut *x;
+    if *r == 0 {
+        fail("zero");
+    }
+    *r += 1;
+    *x
+}
+
+fn exit_early(v: &[u32]) -> usize {
+    if v.is_empty() {
+        std::process::exit(1);
+    }
+    v.len()
+}
+
+fn bare_panic() -> u32 {
+    panic!()
+}
+
+fn main() {
+    let mut x = 1;
+    check(&mut x);
+    exit_early(&[1]);
+}
@@ -0,0 +1,14 @@
+struct D;
+
+impl Drop for D {
+    fn drop(&mut self) {}
+}
+
+fn diverge() -> ! {
+    loop {}
+}
+
+fn main() {
+    let _d = D;
+    diverge();
+}
@@ -0,0 +1,9 @@
+fn main() {
+    let mut i = 0;
+    let mut sum = 0;
+    while i < 10 {
+        sum += i;
+        i += 1;
+    }
+    let _s = sum;
+}
@@ -0,0 +1,13 @@
+fn f(mut n: u32, b: bool) -> u32 {
+    while n > 0 {
+        if b {
+            return 1;
+        }
+        n -= 1;
+    }
+    n
+}
+
+fn main() {
+    f(3, true);
+}
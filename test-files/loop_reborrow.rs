@@ -0,0 +1,11 @@
+fn main() {
+    let mut a = 1;
+    let mut b = 2;
+    let mut r = &mut a;
+    let mut i = 0;
+    while i < 3 {
+        r = &mut b;
+        i += 1;
+    }
+    *r += 1;
+}
@@ -0,0 +1,17 @@
+// The loop head has a `FalseUnwind` terminator whose unwind target is never
+// reached.
+fn count_until(flags: &[bool]) -> usize {
+    let mut i = 0;
+    loop {
+        let c = flags[i];
+        if c {
+            break;
+        }
+        i += 1;
+    }
+    i
+}
+
+fn main() {
+    count_until(&[false, true]);
+}
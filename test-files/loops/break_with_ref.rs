@@ -0,0 +1,20 @@
+// The loop is left with a reference that was created in its last iteration
+fn pick(p: &mut (u32, u32), n: u32) -> &mut u32 {
+    let mut i = 0;
+    let r = loop {
+        if i == n {
+            break &mut p.0;
+        }
+        if i > 10 {
+            break &mut p.1;
+        }
+        i += 1;
+    };
+    *r += 1;
+    r
+}
+
+fn main() {
+    let mut p = (0, 0);
+    *pick(&mut p, 3) += 1;
+}
@@ -0,0 +1,14 @@
+// A reference to the current element is created in each iteration
+fn increment_all(values: &mut [u32]) {
+    let mut i = 0;
+    while i < values.len() {
+        let value = &mut values[i];
+        *value += 1;
+        i += 1;
+    }
+}
+
+fn main() {
+    let mut values = [1, 2, 3];
+    increment_all(&mut values);
+}
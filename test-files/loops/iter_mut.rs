@@ -0,0 +1,10 @@
+fn increment_all(values: &mut [u32]) {
+    for value in values.iter_mut() {
+        *value += 1;
+    }
+}
+
+fn main() {
+    let mut values = [1, 2, 3];
+    increment_all(&mut values);
+}
@@ -0,0 +1,20 @@
+struct Node {
+    value: u32,
+    next: Option<Box<Node>>,
+}
+
+// `cur` is reassigned to a reborrow of the node it points to in each iteration
+fn last_mut(mut cur: &mut Node) -> &mut u32 {
+    while cur.next.is_some() {
+        cur = cur.next.as_mut().unwrap();
+    }
+    &mut cur.value
+}
+
+fn main() {
+    let mut list = Node {
+        value: 0,
+        next: None,
+    };
+    *last_mut(&mut list) += 1;
+}
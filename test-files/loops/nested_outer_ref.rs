@@ -0,0 +1,20 @@
+// A reference created in the outer loop is used in every iteration of the
+// inner loop
+fn fill(pairs: &mut [(u32, u32); 3]) {
+    let mut i = 0;
+    while i < 3 {
+        let pair = &mut pairs[i];
+        let mut j = 0;
+        while j < 2 {
+            pair.0 += j;
+            pair.1 += 1;
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
+fn main() {
+    let mut pairs = [(0, 0); 3];
+    fill(&mut pairs);
+}
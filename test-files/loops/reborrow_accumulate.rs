@@ -0,0 +1,17 @@
+// Each iteration reborrows the reference of the previous one
+fn reborrow_repeatedly(x: &mut u32, n: u32) {
+    let mut cur = x;
+    let mut i = 0;
+    while i < n {
+        let next = &mut *cur;
+        *next += 1;
+        cur = next;
+        i += 1;
+    }
+    *cur += 1;
+}
+
+fn main() {
+    let mut x = 0;
+    reborrow_repeatedly(&mut x, 3);
+}
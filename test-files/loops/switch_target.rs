@@ -0,0 +1,19 @@
+// The reference may point to either `a` or `b` at the loop head
+fn count(n: u32) -> (u32, u32) {
+    let mut a = 0;
+    let mut b = 0;
+    let mut r = &mut a;
+    let mut i = 0;
+    while i < n {
+        *r += 1;
+        if i == 2 {
+            r = &mut b;
+        }
+        i += 1;
+    }
+    (a, b)
+}
+
+fn main() {
+    count(5);
+}
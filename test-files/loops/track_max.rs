@@ -0,0 +1,18 @@
+// A shared reference to the largest element seen so far lives across
+// iterations
+fn max(values: &[u32; 4]) -> u32 {
+    let mut best = &values[0];
+    let mut i = 1;
+    while i < 4 {
+        let candidate = &values[i];
+        if *candidate > *best {
+            best = candidate;
+        }
+        i += 1;
+    }
+    *best
+}
+
+fn main() {
+    max(&[3, 1, 4, 1]);
+}
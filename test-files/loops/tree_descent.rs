@@ -0,0 +1,29 @@
+struct Tree {
+    value: u32,
+    left: Option<Box<Tree>>,
+    right: Option<Box<Tree>>,
+}
+
+// Descends to a leaf, choosing a child by comparing with `key`
+fn leaf_mut(mut tree: &mut Tree, key: u32) -> &mut u32 {
+    loop {
+        let child = if key < tree.value {
+            &mut tree.left
+        } else {
+            &mut tree.right
+        };
+        match child {
+            Some(next) => tree = next,
+            None => return &mut tree.value,
+        }
+    }
+}
+
+fn main() {
+    let mut tree = Tree {
+        value: 1,
+        left: None,
+        right: None,
+    };
+    *leaf_mut(&mut tree, 0) += 1;
+}
@@ -0,0 +1,15 @@
+enum E {
+    A { x: u32 },
+    B(u32, u32),
+}
+
+fn f(e: E) -> u32 {
+    match e {
+        E::A { x } => x,
+        E::B(y, z) => y + z,
+    }
+}
+
+fn main() {
+    f(E::B(1, 2));
+}
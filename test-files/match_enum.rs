@@ -0,0 +1,17 @@
+enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+fn f(c: Color) -> i32 {
+    match c {
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Blue => 3,
+    }
+}
+
+fn main() {
+    f(Color::Green);
+}
@@ -0,0 +1,16 @@
+// The guard borrows the scrutinee `p.0` shallowly; the arm body then
+// mutably borrows a different part of it.
+fn add_if_positive(p: &mut (Option<u32>, u32)) {
+    match p.0 {
+        Some(x) if x > 0 => {
+            let r = &mut p.1;
+            *r += x;
+        }
+        _ => {}
+    }
+}
+
+fn main() {
+    let mut p = (Some(1), 2);
+    add_if_positive(&mut p);
+}
@@ -0,0 +1,11 @@
+fn f(o: &mut Option<(u32, u32)>) {
+    match o {
+        Some((a, _)) => *a += 1,
+        None => {}
+    }
+}
+
+fn main() {
+    let mut o = Some((1, 2));
+    f(&mut o);
+}
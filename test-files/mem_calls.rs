@@ -0,0 +1,38 @@
+// `swap`, `replace` and `take` only write through their `&mut` arguments,
+// so the loans of those arguments end at the call. `my_swap` has the same
+// signature as `swap` but is not known, so its call is abstracted
+// conservatively.
+fn my_swap(a: &mut u32, b: &mut u32) {
+    let t = *a;
+    *a = *b;
+    *b = t;
+}
+
+fn swap_generic(a: &mut u32, b: &mut u32) -> u32 {
+    my_swap(a, b);
+    *a + *b
+}
+
+fn swap_then_use(a: &mut u32, b: &mut u32) -> u32 {
+    std::mem::swap(a, b);
+    *a + *b
+}
+
+fn replace_field(p: &mut (String, u32)) -> String {
+    let old = std::mem::replace(&mut p.0, String::new());
+    p.1 += 1;
+    old
+}
+
+fn take_option(o: &mut Option<String>) -> Option<String> {
+    let t = o.take();
+    let u = std::mem::take(o);
+    t.or(u)
+}
+
+fn main() {
+    let mut a = 1;
+    let mut b = 2;
+    swap_generic(&mut a, &mut b);
+    swap_then_use(&mut a, &mut b);
+}
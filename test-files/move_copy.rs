@@ -0,0 +1,17 @@
+struct Pair {
+    n: u32,
+    s: String,
+}
+
+fn consume(_s: String) {}
+
+fn main() {
+    let p = Pair {
+        n: 1,
+        s: String::new(),
+    };
+    // Copying `p.n` leaves it usable, moving `p.s` leaves it uninitialized
+    let n = p.n;
+    consume(p.s);
+    let _m = n + p.n;
+}
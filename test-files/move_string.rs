@@ -0,0 +1,6 @@
+fn main() {
+    let a = String::new();
+    let b = a;
+    let r = &b;
+    let _l = r.len();
+}
@@ -0,0 +1,7 @@
+fn main() {
+    let mut x = 1;
+    let r = &mut x;
+    *r += 1;
+    let s = &x;
+    let _y = *s;
+}
@@ -0,0 +1,68 @@
+// Borrows of nested fields through one and two levels of references, after
+// a sibling of an intermediate field has already been borrowed, so that
+// part of the expansion down to the borrowed place already exists. In the
+// `overwrite` functions the reference is then overwritten, so the borrowed
+// places become old and are expanded from the old reference.
+struct C {
+    c: u32,
+    d: u32,
+}
+
+struct B {
+    b: C,
+    e: u32,
+}
+
+struct A {
+    a: B,
+    f: u32,
+}
+
+fn one_level(x: &mut A) -> u32 {
+    let e = &mut x.a.e;
+    let c = &mut x.a.b.c;
+    *c += 1;
+    *e += 1;
+    x.a.b.c + x.a.b.d
+}
+
+fn two_levels(x: &mut &mut A) -> u32 {
+    let e = &mut x.a.e;
+    let c = &mut x.a.b.c;
+    *c += 1;
+    *e += 1;
+    x.a.b.c + x.f
+}
+
+fn overwrite_one<'a>(x: &'a mut A, y: &'a mut A) -> &'a mut u32 {
+    let mut r = x;
+    let e = &mut r.a.e;
+    let c = &mut r.a.b.c;
+    r = y;
+    r.f += 1;
+    *e += 1;
+    c
+}
+
+fn overwrite_two<'a>(x: &'a mut &'a mut A, y: &'a mut &'a mut A) -> &'a mut u32 {
+    let mut r = x;
+    let e = &mut r.a.e;
+    let c = &mut r.a.b.c;
+    r = y;
+    r.f += 1;
+    *e += 1;
+    c
+}
+
+fn main() {
+    let mut a = A {
+        a: B {
+            b: C { c: 1, d: 2 },
+            e: 3,
+        },
+        f: 4,
+    };
+    one_level(&mut a);
+    let mut r = &mut a;
+    two_levels(&mut r);
+}
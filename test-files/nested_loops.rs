@@ -0,0 +1,13 @@
+fn main() {
+    let mut v = (0, 0);
+    let mut i = 0;
+    while i < 3 {
+        let mut j = 0;
+        while j < 3 {
+            v.1 += j;
+            j += 1;
+        }
+        v.0 += i;
+        i += 1;
+    }
+}
@@ -0,0 +1,36 @@
+// Reborrows through two dereferences of nested reference arguments.
+fn inner_mut(x: &mut &mut u32) -> u32 {
+    let r = &mut **x;
+    *r += 1;
+    **x
+}
+
+fn inner_shared(x: &&u32) -> u32 {
+    let r = &**x;
+    *r + **x
+}
+
+fn outer_then_inner(x: &mut &mut u32) {
+    let outer = &mut *x;
+    let inner = &mut **outer;
+    *inner = 0;
+}
+
+fn main() {
+    let mut n = 1;
+    let mut m = &mut n;
+    inner_mut(&mut m);
+    outer_then_inner(&mut m);
+    let s = &2;
+    inner_shared(&s);
+}
+
+// After `a` is overwritten, `**a` in the borrow of `r` refers to the old
+// value of `a`, through both levels of references.
+fn retarget<'a>(x: &'a mut &'a mut u32, y: &'a mut &'a mut u32) -> &'a mut u32 {
+    let mut a = x;
+    let r = &mut **a;
+    a = y;
+    **a += 1;
+    r
+}
@@ -0,0 +1,36 @@
+struct Pair {
+    a: i32,
+    b: String,
+}
+
+struct Handle {
+    ptr: *mut i32,
+}
+
+impl Handle {
+    fn get(&self) -> i32 {
+        unsafe { *self.ptr }
+    }
+
+    fn set(&mut self, value: i32) {
+        unsafe { *self.ptr = value }
+    }
+}
+
+fn main() {
+    let mut pair = Pair { a: 1, b: String::new() };
+    let p: *mut Pair = &mut pair;
+    unsafe {
+        (*p).a = 2;
+        let r = &mut (*p).b;
+        r.push('x');
+        let s = &(*p).a;
+        let _t = *s;
+        let q: *const *mut Pair = &p;
+        (**q).a = 3;
+    }
+    let mut x = 1;
+    let mut h = Handle { ptr: &mut x };
+    h.set(3);
+    let _v = h.get();
+}
@@ -0,0 +1,10 @@
+fn f(x: &mut (i32, i32)) {
+    let r = &mut x.0;
+    let s = &mut *r;
+    *s = 1;
+}
+
+fn main() {
+    let mut p = (1, 2);
+    f(&mut p);
+}
@@ -0,0 +1,10 @@
+fn first<'a>(x: &'a mut (i32, i32)) -> &'a mut i32 {
+    let r = &mut x.0;
+    let s = &mut *r;
+    s
+}
+
+fn main() {
+    let mut p = (1, 2);
+    *first(&mut p) = 3;
+}
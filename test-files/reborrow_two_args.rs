@@ -0,0 +1,11 @@
+fn pick<'a>(x: &'a mut (i32, i32), y: &mut i32) -> &'a mut i32 {
+    let t = &mut *y;
+    *t = 1;
+    &mut x.1
+}
+
+fn main() {
+    let mut p = (1, 2);
+    let mut q = 3;
+    *pick(&mut p, &mut q) = 4;
+}
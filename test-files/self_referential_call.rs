@@ -0,0 +1,25 @@
+// The regions of `link`'s signature are all equal, so the regions of the
+// result form a single SCC at the call site and the call is abstracted by a
+// single region abstraction.
+struct Node<'a> {
+    value: u32,
+    next: Option<&'a mut Node<'a>>,
+}
+
+fn link<'a>(node: &'a mut Node<'a>) -> &'a mut Node<'a> {
+    node
+}
+
+fn call_link<'a>(node: &'a mut Node<'a>) -> u32 {
+    let linked = link(node);
+    linked.value += 1;
+    linked.value
+}
+
+fn main() {
+    let mut node = Node {
+        value: 0,
+        next: None,
+    };
+    call_link(&mut node);
+}
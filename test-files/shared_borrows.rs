@@ -0,0 +1,7 @@
+fn main() {
+    let p = (1, 2);
+    let a = &p;
+    let b = &p.0;
+    let c = a.1 + *b;
+    let _d = c;
+}
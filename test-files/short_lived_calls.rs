@@ -0,0 +1,27 @@
+// Each call's result borrows from `x` only until it is used, so the region
+// abstraction of the call is collected once the loan is killed: the number
+// of abstractions stays bounded instead of growing with every call.
+fn first<'a>(x: &'a mut u32) -> &'a mut u32 {
+    x
+}
+
+fn many_calls(x: &mut u32) -> u32 {
+    let a = first(x);
+    *a += 1;
+    let b = first(x);
+    *b += 2;
+    let c = first(x);
+    *c += 3;
+    let d = first(x);
+    *d += 4;
+    let e = first(x);
+    *e += 5;
+    let f = first(x);
+    *f += 6;
+    *x
+}
+
+fn main() {
+    let mut x = 0;
+    many_calls(&mut x);
+}
@@ -0,0 +1,23 @@
+struct Counter {
+    count: u32,
+}
+
+impl Counter {
+    fn count_mut(&mut self) -> &mut u32 {
+        &mut self.count
+    }
+}
+
+fn f<'a, 'b, T>(x: &'a mut T, y: &'b T) -> &'a mut T {
+    let _y = y;
+    x
+}
+
+fn main() {
+    let mut a = 1;
+    let b = 2;
+    let r = f(&mut a, &b);
+    *r = 3;
+    let mut counter = Counter { count: 0 };
+    *counter.count_mut() += 1;
+}
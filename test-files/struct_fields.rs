@@ -0,0 +1,18 @@
+struct Inner {
+    val: u32,
+}
+
+struct Outer {
+    inner: Inner,
+    pair: (u32, Inner),
+}
+
+fn f(o: &mut Outer) -> u32 {
+    let r = &mut o.inner.val;
+    *r += 1;
+    let t = &mut o.pair.1.val;
+    *t += 1;
+    o.pair.0
+}
+
+fn main() {}
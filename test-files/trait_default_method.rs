@@ -0,0 +1,50 @@
+trait Counter {
+    fn value(&mut self) -> &mut u32;
+
+    fn bump(&mut self) -> u32 {
+        let v = self.value();
+        *v += 1;
+        *v
+    }
+}
+
+struct Single {
+    count: u32,
+}
+
+impl Counter for Single {
+    fn value(&mut self) -> &mut u32 {
+        &mut self.count
+    }
+}
+
+struct Pair {
+    counts: (u32, u32),
+}
+
+impl Pair {
+    fn bump(&mut self) -> u32 {
+        self.counts.1 += 1;
+        self.counts.1
+    }
+}
+
+impl Counter for Pair {
+    fn value(&mut self) -> &mut u32 {
+        &mut self.counts.0
+    }
+
+    fn bump(&mut self) -> u32 {
+        let v = &mut self.counts.0;
+        *v += 2;
+        *v
+    }
+}
+
+fn main() {
+    let mut single = Single { count: 0 };
+    Counter::bump(&mut single);
+    let mut pair = Pair { counts: (0, 0) };
+    Counter::bump(&mut pair);
+    pair.bump();
+}
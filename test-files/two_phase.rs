@@ -0,0 +1,16 @@
+// `v` is borrowed mutably for `push` before `v.len()` is evaluated; the
+// borrow is only activated by the call.
+fn push_len(v: &mut Vec<usize>) {
+    v.push(v.len());
+}
+
+fn push_len_local() -> Vec<usize> {
+    let mut v = Vec::new();
+    v.push(v.len());
+    v
+}
+
+fn main() {
+    let mut v = push_len_local();
+    push_len(&mut v);
+}
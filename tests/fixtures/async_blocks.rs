@@ -0,0 +1,16 @@
+async fn add(x: &mut u32, n: u32) {
+    *x += n;
+}
+
+async fn f() -> u32 {
+    let mut x = 0;
+    let a = async { 1 };
+    let b = async { 2 };
+    add(&mut x, a.await).await;
+    add(&mut x, b.await).await;
+    x
+}
+
+fn main() {
+    drop(f());
+}
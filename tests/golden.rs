@@ -0,0 +1,801 @@
+// © 2023, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Runs `pcs_bin` on each program in `test-files/` (including subdirectories)
+//! and compares a summary of its output with `tests/golden/<program>.txt`.
+//! The other tests check the options of `pcs_bin` on small fixtures.
+//!
+//! Run with `BLESS=1` to overwrite the golden files with the current output.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde_json::Value;
+
+/// Loops are expected to converge well within this number of iterations.
+const MAX_BLOCK_VISITS: usize = 50;
+
+fn read_json(path: &Path) -> Value {
+    let contents = fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("Failed to read {}: {err}", path.display()));
+    serde_json::from_str(&contents)
+        .unwrap_or_else(|err| panic!("Failed to parse {}: {err}", path.display()))
+}
+
+/// Parses a location of the form `bb3[2]`.
+fn parse_location(location: &str) -> (usize, usize) {
+    let (block, statement_index) = location
+        .strip_prefix("bb")
+        .and_then(|location| location.strip_suffix(']'))
+        .and_then(|location| location.split_once('['))
+        .unwrap_or_else(|| panic!("Invalid location {location}"));
+    (block.parse().unwrap(), statement_index.parse().unwrap())
+}
+
+fn format_place(place: &Value) -> String {
+    place["place"].as_str().unwrap().to_string()
+}
+
+fn format_borrow(borrow: &Value) -> String {
+    let maybe_old_place = |place: &Value| match place["at"].as_str() {
+        Some(at) => format!("{} at {at}", format_place(place)),
+        None => format_place(place),
+    };
+    let mutability = if borrow["is_mut"].as_bool().unwrap() {
+        "mut "
+    } else {
+        ""
+    };
+    let two_phase = match borrow["two_phase"].as_str() {
+        Some(two_phase) => format!(" (two-phase, {two_phase})"),
+        None => String::new(),
+    };
+    format!(
+        "&{mutability}{} -> {}{two_phase}",
+        maybe_old_place(&borrow["borrowed_place"]),
+        maybe_old_place(&borrow["assigned_place"])
+    )
+}
+
+fn format_action(action: &Value) -> String {
+    let kind = action["action"].as_str().unwrap();
+    let args = match kind {
+        "StorageDead" | "IgnoreStorageDead" => format!("_{}", action["local"]),
+        "Weaken" => format!(
+            "{}, {} -> {}",
+            format_place(&action["place"]),
+            action["from"].as_str().unwrap(),
+            action["to"].as_str().unwrap()
+        ),
+        "Expand" | "Collapse" => format!(
+            "{}, {}, {}",
+            format_place(&action["place"]),
+            format_place(&action["guide"]),
+            action["capability"].as_str().unwrap()
+        ),
+        "DerefShallowInit" => format!(
+            "{}, {}",
+            format_place(&action["place"]),
+            format_place(&action["guide"])
+        ),
+        "AddBorrow" | "RemoveBorrow" => format_borrow(&action["borrow"]),
+        _ => panic!("Unknown action {action}"),
+    };
+    format!("{kind}({args})")
+}
+
+/// Formats the actions at a location. Consecutive borrow actions come from a
+/// hash set, so they are sorted to make the output deterministic.
+fn format_actions(actions: &[Value]) -> Vec<String> {
+    let mut formatted = Vec::new();
+    let mut borrow_actions = Vec::new();
+    for action in actions {
+        let line = format_action(action);
+        if matches!(
+            action["action"].as_str(),
+            Some("AddBorrow" | "RemoveBorrow")
+        ) {
+            borrow_actions.push(line);
+        } else {
+            borrow_actions.sort();
+            formatted.append(&mut borrow_actions);
+            formatted.push(line);
+        }
+    }
+    borrow_actions.sort();
+    formatted.append(&mut borrow_actions);
+    formatted
+}
+
+/// Checks that the edges of `mir.json` refer to blocks that are nodes, and
+/// returns the graph.
+fn check_mir_graph(name: &str, dir: &Path) -> Value {
+    let graph = read_json(&dir.join("mir.json"));
+    let ids: Vec<_> = graph["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|node| node["id"].as_u64().unwrap())
+        .collect();
+    for edge in graph["edges"].as_array().unwrap() {
+        for end in ["source", "target"] {
+            let id = edge[end].as_u64().unwrap();
+            assert!(
+                ids.contains(&id),
+                "Edge {edge} of {name} refers to a missing node"
+            );
+        }
+    }
+    graph
+}
+
+/// Summarizes the output of the function in `dir`: the lifetimes of its
+/// signature, the capabilities and borrows at the end of each block, the
+/// visits of each loop head, the imaginary edges, the untracked places and
+/// the actions at each location.
+fn summarize_function(summary: &mut String, name: &str, dir: &Path) {
+    let graph = check_mir_graph(name, dir);
+    writeln!(summary, "fn {name}").unwrap();
+    let signature_regions = read_json(&dir.join("signature.json"));
+    let signature_regions = signature_regions
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|region| match region["name"].as_str() {
+            Some(name) => format!("{} ({name})", region["region"].as_str().unwrap()),
+            None => region["region"].as_str().unwrap().to_string(),
+        })
+        .collect::<Vec<_>>();
+    if !signature_regions.is_empty() {
+        writeln!(
+            summary,
+            "  signature regions: {}",
+            signature_regions.join(", ")
+        )
+        .unwrap();
+    }
+    let capabilities = read_json(&dir.join("capabilities.json"));
+    let mut terminators: BTreeMap<usize, (usize, &Value)> = BTreeMap::new();
+    for (location, capabilities) in capabilities.as_object().unwrap() {
+        let (block, statement_index) = parse_location(location);
+        let terminator = terminators
+            .entry(block)
+            .or_insert((statement_index, capabilities));
+        if statement_index >= terminator.0 {
+            *terminator = (statement_index, capabilities);
+        }
+    }
+    for (block, (statement_index, capabilities)) in terminators {
+        let capabilities = capabilities
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(place, kind)| format!("{place}: {}", kind.as_str().unwrap()))
+            .collect::<Vec<_>>();
+        let borrows =
+            read_json(&dir.join(format!("block_{block}_stmt_{statement_index}_borrows.json")));
+        let num_borrows = borrows["after"]["borrows"].as_array().unwrap().len();
+        let borrows_graph = read_json(&dir.join(format!(
+            "block_{block}_stmt_{statement_index}_borrows_graph.json"
+        )));
+        let num_abstractions = borrows_graph["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|node| node["type"] == "Abstraction")
+            .count();
+        let abstractions = if num_abstractions > 0 {
+            format!(", {num_abstractions} abstractions")
+        } else {
+            String::new()
+        };
+        writeln!(
+            summary,
+            "  bb{block}: {num_borrows} borrows{abstractions}; {}",
+            capabilities.join(", ")
+        )
+        .unwrap();
+        let nodes = borrows_graph["nodes"].as_array().unwrap();
+        let mut deref_expansions = borrows_graph["edges"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|edge| edge["type"] == "DerefExpansion")
+            .map(|edge| {
+                let at = &nodes[edge["base"].as_u64().unwrap() as usize]["place"]["at"];
+                let expansion_places = edge["expansion_places"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|place| place.as_str().unwrap())
+                    .collect::<Vec<_>>();
+                format!(
+                    "    deref {} at {} -> {}",
+                    edge["base_place"].as_str().unwrap(),
+                    at.as_str().unwrap(),
+                    expansion_places.join(", ")
+                )
+            })
+            .collect::<Vec<_>>();
+        deref_expansions.sort();
+        for deref_expansion in deref_expansions {
+            writeln!(summary, "{deref_expansion}").unwrap();
+        }
+    }
+    for edge in graph["edges"].as_array().unwrap() {
+        if edge["imaginary"].as_bool().unwrap() {
+            writeln!(
+                summary,
+                "  imaginary edge bb{} -> bb{} ({})",
+                edge["source"],
+                edge["target"],
+                edge["label"].as_str().unwrap()
+            )
+            .unwrap();
+        }
+    }
+    let visits = read_json(&dir.join("fixpoint.json"));
+    for node in graph["nodes"].as_array().unwrap() {
+        if !node["is_loop_head"].as_bool().unwrap() {
+            continue;
+        }
+        let block = node["id"].as_u64().unwrap();
+        let borrows = read_json(&dir.join(format!("block_{block}_stmt_0_borrows.json")));
+        writeln!(
+            summary,
+            "  loop head bb{block}: {} visits; {} borrows at entry",
+            visits[format!("bb{block}")],
+            borrows["before_start"]["borrows"].as_array().unwrap().len()
+        )
+        .unwrap();
+    }
+    let unsupported = read_json(&dir.join("unsupported.json"));
+    for warning in unsupported.as_array().unwrap() {
+        let location = &warning["location"];
+        writeln!(
+            summary,
+            "  bb{}[{}]: unsupported: {}",
+            location["block"],
+            location["statement_index"],
+            warning["message"].as_str().unwrap()
+        )
+        .unwrap();
+    }
+    let actions = read_json(&dir.join("actions.json"));
+    for location_actions in actions.as_array().unwrap() {
+        let location = &location_actions["location"];
+        for action in format_actions(location_actions["actions"].as_array().unwrap()) {
+            writeln!(
+                summary,
+                "  bb{}[{}]: {action}",
+                location["block"], location["statement_index"]
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// A command running `pcs_bin` on `program`.
+fn pcs_bin(program: &Path) -> Command {
+    let sysroot = Command::new("rustc")
+        .args(["--print", "sysroot"])
+        .output()
+        .expect("Failed to run rustc");
+    let sysroot = String::from_utf8(sysroot.stdout).unwrap();
+    let mut command = Command::new(env!("CARGO_BIN_EXE_pcs_bin"));
+    command
+        .arg(fs::canonicalize(program).unwrap())
+        .env("LD_LIBRARY_PATH", Path::new(sysroot.trim()).join("lib"));
+    command
+}
+
+/// The program run by [`run_fixture`]: a file of the repository, or a
+/// source written to the directory of the run.
+enum Program<'a> {
+    File(&'a str),
+    Source(&'a str),
+}
+
+/// Runs `pcs_bin` with `args` and `envs` on `program`, in an empty directory
+/// named after `name` and the test process, and returns the directory and
+/// the stderr of the run. Fails unless the run exits with `success`.
+fn run_fixture(
+    name: &str,
+    program: Program<'_>,
+    args: &[&str],
+    envs: &[(&str, &str)],
+    success: bool,
+) -> (PathBuf, String) {
+    let work_dir = std::env::temp_dir().join(format!("pcs-{name}-{}", std::process::id()));
+    if work_dir.exists() {
+        fs::remove_dir_all(&work_dir).unwrap();
+    }
+    fs::create_dir_all(&work_dir).unwrap();
+    let program = match program {
+        Program::File(path) => PathBuf::from(path),
+        Program::Source(source) => {
+            let path = work_dir.join("main.rs");
+            fs::write(&path, source).unwrap();
+            path
+        }
+    };
+    let output = pcs_bin(&program)
+        .args(args)
+        .envs(envs.iter().copied())
+        .current_dir(&work_dir)
+        .output()
+        .expect("Failed to run pcs_bin");
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert_eq!(
+        output.status.success(),
+        success,
+        "pcs_bin on {}:\n{stderr}",
+        program.display()
+    );
+    (work_dir, stderr)
+}
+
+/// Runs the analysis on `program` and summarizes its output.
+fn run_program(program: &Path) -> String {
+    let name = program.file_stem().unwrap().to_str().unwrap();
+    let (work_dir, _) = run_fixture(
+        &format!("golden-{name}"),
+        Program::File(program.to_str().unwrap()),
+        &[],
+        &[("PCS_MAX_BLOCK_VISITS", &MAX_BLOCK_VISITS.to_string())],
+        true,
+    );
+
+    let data_dir = work_dir.join("visualization/data");
+    let functions = read_json(&data_dir.join("functions.json"));
+    let mut dirs: Vec<_> = functions
+        .as_object()
+        .unwrap()
+        .values()
+        .map(|dir| dir.as_str().unwrap())
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    assert_eq!(
+        dirs.len(),
+        functions.as_object().unwrap().len(),
+        "Functions of {} share an output directory: {functions}",
+        program.display()
+    );
+    let mut summary = String::new();
+    for (name, dir) in functions.as_object().unwrap() {
+        summarize_function(&mut summary, name, &data_dir.join(dir.as_str().unwrap()));
+    }
+    fs::remove_dir_all(&work_dir).unwrap();
+    summary
+}
+
+/// A line diff of `expected` and `actual`, based on their longest common
+/// subsequence of lines.
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<_> = expected.lines().collect();
+    let actual: Vec<_> = actual.lines().collect();
+    let mut lcs = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            writeln!(diff, "  {}", expected[i]).unwrap();
+            i += 1;
+            j += 1;
+        } else if i < expected.len() && (j == actual.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            writeln!(diff, "- {}", expected[i]).unwrap();
+            i += 1;
+        } else {
+            writeln!(diff, "+ {}", actual[j]).unwrap();
+            j += 1;
+        }
+    }
+    diff
+}
+
+/// The programs in `dir` and its subdirectories, e.g. those imported from
+/// the Prusti test suite into `test-files/imported` by `pcs_import_prusti`.
+fn collect_programs(dir: &Path, programs: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_programs(&path, programs);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            programs.push(path);
+        }
+    }
+}
+
+#[test]
+fn golden() {
+    let bless = std::env::var("BLESS").as_deref() == Ok("1");
+    let golden_dir = PathBuf::from("tests/golden");
+    let mut programs = Vec::new();
+    collect_programs(Path::new("test-files"), &mut programs);
+    programs.sort();
+    assert!(!programs.is_empty(), "No programs found in test-files/");
+
+    let mut failures = Vec::new();
+    for program in programs {
+        let actual = run_program(&program);
+        let golden = golden_dir.join(
+            program
+                .strip_prefix("test-files")
+                .unwrap()
+                .with_extension("txt"),
+        );
+        if bless {
+            fs::create_dir_all(golden.parent().unwrap()).unwrap();
+            fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&golden).unwrap_or_default();
+        if expected != actual {
+            failures.push(format!(
+                "{} differs from {}:\n{}",
+                program.display(),
+                golden.display(),
+                diff(&expected, &actual)
+            ));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{}\nRun with BLESS=1 to update the golden files.",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn no_divergences() {
+    let mut programs = Vec::new();
+    collect_programs(Path::new("test-files"), &mut programs);
+    programs.sort();
+    let mut divergences = String::new();
+    for program in programs {
+        let output = pcs_bin(&program)
+            .arg("--pcs-only-errors")
+            .output()
+            .expect("Failed to run pcs_bin");
+        assert!(
+            output.status.success(),
+            "pcs_bin failed on {}:\n{}",
+            program.display(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        divergences.push_str(&String::from_utf8(output.stdout).unwrap());
+    }
+    assert!(divergences.is_empty(), "{divergences}");
+}
+
+/// The corpus is seeded with the programs on which the owned capabilities
+/// and the borrows are most likely to disagree: two-phase borrows and chains
+/// of reborrows, also through loops.
+#[test]
+fn validity_checks() {
+    let mut programs = Vec::new();
+    collect_programs(Path::new("test-files"), &mut programs);
+    programs.sort();
+    for seed in ["two_phase.rs", "reborrow_chain.rs", "loops/iter_mut.rs"] {
+        assert!(
+            programs.contains(&Path::new("test-files").join(seed)),
+            "test-files/{seed} is missing"
+        );
+    }
+    let mut failures = String::new();
+    for program in programs {
+        let output = pcs_bin(&program)
+            .arg("--pcs-no-visualization")
+            .env("PCS_VALIDITY_CHECKS", "1")
+            .output()
+            .expect("Failed to run pcs_bin");
+        if !output.status.success() {
+            writeln!(
+                failures,
+                "{}:\n{}",
+                program.display(),
+                String::from_utf8_lossy(&output.stderr)
+            )
+            .unwrap();
+        }
+    }
+    assert!(failures.is_empty(), "{failures}");
+}
+
+/// An `async fn` containing two `async` blocks: each of the three
+/// coroutine bodies is written to its own directory.
+#[test]
+fn coroutines() {
+    let (work_dir, _) = run_fixture(
+        "coroutines",
+        Program::File("tests/fixtures/async_blocks.rs"),
+        &["--edition=2021", "--pcs-coroutines"],
+        &[],
+        true,
+    );
+
+    let data_dir = work_dir.join("visualization/data");
+    let functions = read_json(&data_dir.join("functions.json"));
+    let mut names: Vec<_> = functions.as_object().unwrap().keys().cloned().collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "add",
+            "add_async0",
+            "f",
+            "f_async0",
+            "f_async1",
+            "f_async2",
+            "main"
+        ]
+    );
+    for (name, dir) in functions.as_object().unwrap() {
+        check_mir_graph(name, &data_dir.join(dir.as_str().unwrap()));
+    }
+    fs::remove_dir_all(&work_dir).unwrap();
+}
+
+#[test]
+fn polonius_fallback() {
+    for (stage, report) in [
+        ("borrowck", "Polonius failed"),
+        ("analysis", "the analysis failed with Polonius facts"),
+    ] {
+        let (work_dir, stderr) = run_fixture(
+            &format!("fallback-{stage}"),
+            Program::File("test-files/call_lifetimes.rs"),
+            &[],
+            &[("PCS_INJECT_POLONIUS_FAILURE", &format!("{stage}:longest"))],
+            true,
+        );
+        assert!(
+            stderr.contains(&format!(
+                "[pcs-fallback] longest: {report} (injected Polonius failure)"
+            )),
+            "The fallback at {stage} was not reported:\n{stderr}"
+        );
+
+        let meta = read_json(&work_dir.join("visualization/data/meta.json"));
+        assert_eq!(meta["facts"]["longest"], "Nll");
+        assert_eq!(meta["facts"]["main"], "Polonius");
+        assert!(meta["files"]["longest"]
+            .as_array()
+            .unwrap()
+            .contains(&Value::from("actions.json")));
+        fs::remove_dir_all(&work_dir).unwrap();
+    }
+}
+
+/// The entries of `capabilities.json` and `actions.json` and the contents of
+/// the `block_*.json` files of the function in `dir`, outside of the `ignored`
+/// blocks. The DOT graphs are left out, their edges are not in a stable order.
+fn block_results(dir: &Path, ignored: &[usize]) -> BTreeMap<String, Value> {
+    let mut results = BTreeMap::new();
+    let capabilities = read_json(&dir.join("capabilities.json"));
+    for (location, capabilities) in capabilities.as_object().unwrap() {
+        if !ignored.contains(&parse_location(location).0) {
+            results.insert(format!("capabilities {location}"), capabilities.clone());
+        }
+    }
+    for actions in read_json(&dir.join("actions.json")).as_array().unwrap() {
+        let block = actions["location"]["block"].as_u64().unwrap() as usize;
+        if !ignored.contains(&block) {
+            let location = format!("bb{block}[{}]", actions["location"]["statement_index"]);
+            results.insert(format!("actions {location}"), actions["actions"].clone());
+        }
+    }
+    for entry in fs::read_dir(dir).unwrap() {
+        let file = entry.unwrap().file_name().into_string().unwrap();
+        let Some(block) = file
+            .strip_prefix("block_")
+            .filter(|rest| rest.ends_with(".json"))
+            .and_then(|rest| rest.split('_').next())
+        else {
+            continue;
+        };
+        if ignored.contains(&block.parse().unwrap()) {
+            continue;
+        }
+        let contents = fs::read_to_string(dir.join(&file)).unwrap();
+        results.insert(file, Value::from(contents));
+    }
+    results
+}
+
+#[test]
+fn ignore_cleanup() {
+    let mut programs = Vec::new();
+    collect_programs(Path::new("test-files"), &mut programs);
+    programs.sort();
+    let mut ignored_blocks = 0;
+    for program in programs {
+        let file = || Program::File(program.to_str().unwrap());
+        let (full_dir, _) = run_fixture("cleanup-full", file(), &[], &[], true);
+        let (happy_dir, _) = run_fixture(
+            "cleanup-happy",
+            file(),
+            &[],
+            &[("PCS_IGNORE_CLEANUP", "1")],
+            true,
+        );
+        let full = full_dir.join("visualization/data");
+        let happy = happy_dir.join("visualization/data");
+        assert_eq!(read_json(&full.join("meta.json"))["happy_path_only"], false);
+        assert_eq!(read_json(&happy.join("meta.json"))["happy_path_only"], true);
+        let functions = read_json(&happy.join("functions.json"));
+        for (name, dir) in functions.as_object().unwrap() {
+            let dir = dir.as_str().unwrap();
+            let mir = read_json(&happy.join(dir).join("mir.json"));
+            let ignored: Vec<usize> = mir["nodes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter(|node| node["ignored"] == true)
+                .map(|node| node["id"].as_u64().unwrap() as usize)
+                .collect();
+            ignored_blocks += ignored.len();
+            let full = block_results(&full.join(dir), &ignored);
+            let happy = block_results(&happy.join(dir), &ignored);
+            let differing: Vec<_> = full
+                .keys()
+                .chain(happy.keys())
+                .filter(|key| full.get(*key) != happy.get(*key))
+                .collect();
+            assert!(
+                differing.is_empty(),
+                "Results of {name} in {} differ outside of cleanup blocks: {differing:?}",
+                program.display()
+            );
+        }
+        fs::remove_dir_all(&full_dir).unwrap();
+        fs::remove_dir_all(&happy_dir).unwrap();
+    }
+    assert!(ignored_blocks > 0, "No cleanup blocks were ignored");
+}
+
+/// `--pcs-json-pretty` changes the layout of the JSON files, not their
+/// contents.
+#[test]
+fn json_pretty() {
+    let program = || Program::File("test-files/move_string.rs");
+    let (compact_dir, _) = run_fixture("compact", program(), &[], &[], true);
+    let (pretty_dir, _) = run_fixture("pretty", program(), &["--pcs-json-pretty"], &[], true);
+
+    for file in ["functions.json", "meta.json", "main/capabilities.json"] {
+        let compact =
+            fs::read_to_string(compact_dir.join("visualization/data").join(file)).unwrap();
+        let pretty = fs::read_to_string(pretty_dir.join("visualization/data").join(file)).unwrap();
+        assert!(!compact.contains('\n'), "{file} is not compact:\n{compact}");
+        assert!(pretty.contains('\n'), "{file} is not pretty:\n{pretty}");
+        assert_eq!(
+            serde_json::from_str::<Value>(&compact).unwrap(),
+            serde_json::from_str::<Value>(&pretty).unwrap()
+        );
+    }
+    fs::remove_dir_all(&compact_dir).unwrap();
+    fs::remove_dir_all(&pretty_dir).unwrap();
+}
+
+/// `--pcs-verbose` traces the borrows added and removed by each statement.
+#[test]
+fn verbose_trace() {
+    let program = || Program::File("test-files/reborrow_chain.rs");
+    let (quiet_dir, quiet) =
+        run_fixture("quiet", program(), &["--pcs-no-visualization"], &[], true);
+    assert!(!quiet.contains("[pcs]"), "{quiet}");
+    let (verbose_dir, verbose) = run_fixture(
+        "verbose",
+        program(),
+        &["--pcs-no-visualization", "--pcs-verbose"],
+        &[],
+        true,
+    );
+    for expected in [
+        "[pcs] borrows at bb0[1] (_2 = &mut ((*_1).0: i32)):\n  + Rustc(bw0): &mut (*_1).0 -> _2\n",
+        "[pcs] borrows at bb0[8] (StorageDead(_3)):\n  - Rustc(bw1): &mut (*_2) -> _3\n",
+    ] {
+        assert!(verbose.contains(expected), "{verbose}");
+    }
+    fs::remove_dir_all(quiet_dir).unwrap();
+    fs::remove_dir_all(verbose_dir).unwrap();
+}
+
+/// `--pcs-max-blocks` skips the functions with more basic blocks than the
+/// limit, and still analyzes the others.
+#[test]
+fn max_blocks() {
+    let (work_dir, stderr) = run_fixture(
+        "max-blocks",
+        Program::Source(
+            "fn small(x: u32) -> u32 { x }
+             fn large(x: u32) -> u32 { if x > 1 { x - 1 } else { 0 } }
+             fn main() { small(1); large(2); }",
+        ),
+        &["--pcs-max-blocks=4"],
+        &[],
+        true,
+    );
+    assert_eq!(
+        stderr.lines().collect::<Vec<_>>(),
+        ["Skipping large: it has 6 basic blocks, more than 4"]
+    );
+    let functions = read_json(&work_dir.join("visualization/data/functions.json"));
+    let mut analyzed: Vec<_> = functions.as_object().unwrap().keys().collect();
+    analyzed.sort();
+    assert_eq!(analyzed, ["main", "small"]);
+    fs::remove_dir_all(&work_dir).unwrap();
+}
+
+/// With `--pcs-strict`, unsupported MIR constructs are reported at the end
+/// instead of aborting the analysis.
+#[test]
+fn strict_report() {
+    let (work_dir, stderr) = run_fixture(
+        "strict",
+        // `vec!` boxes its elements with `Rvalue::ShallowInitBox`
+        Program::Source("fn main() { let _v = vec![1, 2]; }"),
+        &["--pcs-strict"],
+        &[],
+        false,
+    );
+    assert!(
+        stderr.contains("Encountered 1 unsupported MIR constructs:"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("  Rvalue::ShallowInitBox in `main` (src/visualization/mir_graph.rs:"),
+        "{stderr}"
+    );
+    fs::remove_dir_all(work_dir).unwrap();
+}
+
+/// The HTML report of `<Single as Counter>::value`, whose name must be
+/// escaped, compared with `tests/golden/html/Single_as_Counter_value.html`.
+#[test]
+fn html_report() {
+    let golden = Path::new("tests/golden/html/Single_as_Counter_value.html");
+    let (work_dir, _) = run_fixture(
+        "html",
+        Program::File("test-files/trait_default_method.rs"),
+        &["--pcs-emit=json,html"],
+        &[],
+        true,
+    );
+    let data_dir = work_dir.join("visualization/data");
+    let functions = read_json(&data_dir.join("functions.json"));
+    let function_dir = functions["<Single as Counter>::value"].as_str().unwrap();
+    let actual = fs::read_to_string(data_dir.join(function_dir).join("report.html")).unwrap();
+    fs::remove_dir_all(&work_dir).unwrap();
+    assert!(actual.contains("<title>PCS report: &lt;Single as Counter&gt;::value</title>"));
+    if std::env::var("BLESS").as_deref() == Ok("1") {
+        fs::create_dir_all(golden.parent().unwrap()).unwrap();
+        fs::write(golden, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(golden).unwrap_or_default();
+    assert!(
+        expected == actual,
+        "The report differs from {}:\n{}\nRun with BLESS=1 to update the golden files.",
+        golden.display(),
+        diff(&expected, &actual)
+    );
+}
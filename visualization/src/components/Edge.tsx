@@ -36,6 +36,7 @@ export default function Edge({
         y2={endY}
         stroke="black"
         strokeWidth={2}
+        strokeDasharray={edge.data.imaginary ? "6 4" : undefined}
       />
       {edge.data.label && (
         <text
@@ -28,21 +28,35 @@ const fetchJsonFile = async (filePath: string) => {
 type GraphData = {
   initialNodes: {
     id: string;
-    data: { block: number; stmts: string[]; terminator: string };
+    data: BasicBlockData;
   }[];
   initialEdges: {
     id: string;
     source: string;
     target: string;
-    data: { label: string };
+    data: { label: string; imaginary: boolean };
   }[];
 };
 
-async function getGraphData(func: string): Promise<GraphData> {
-  const graphFilePath = `data/${func}/mir.json`;
+async function getGraphData(functionDir: string): Promise<GraphData> {
+  const graphFilePath = `data/${functionDir}/mir.json`;
   const graph: {
-    nodes: { id: string; block: number; stmts: string[]; terminator: string }[];
-    edges: { source: string; target: string; label: string }[];
+    nodes: {
+      id: number;
+      label: string;
+      stmts: string[];
+      terminator: string;
+      is_loop_head: boolean;
+      ignored: boolean;
+      predecessor_count: number;
+    }[];
+    edges: {
+      source: number;
+      target: number;
+      label: string;
+      back_edge: boolean;
+      imaginary: boolean;
+    }[];
   } = await fetchJsonFile(graphFilePath);
 
   const initialNodes = graph.nodes.map((node) => {
@@ -52,9 +66,10 @@ async function getGraphData(func: string): Promise<GraphData> {
         isOnSelectedPath: false,
         currentPoint: { block: 0, stmt: 0 },
         data: {
-          block: node.block,
+          block: node.id,
           stmts: node.stmts,
           terminator: node.terminator,
+          ignored: node.ignored,
         },
         setCurrentPoint: () => {},
       })
@@ -63,11 +78,12 @@ async function getGraphData(func: string): Promise<GraphData> {
     const height = container.offsetHeight;
     container.remove();
     return {
-      id: node.id,
+      id: `${node.id}`,
       data: {
-        block: node.block,
+        block: node.id,
         stmts: node.stmts,
         terminator: node.terminator,
+        ignored: node.ignored,
       },
       width: 300,
       height,
@@ -76,10 +92,11 @@ async function getGraphData(func: string): Promise<GraphData> {
 
   const initialEdges = graph.edges.map((edge, idx) => ({
     id: `${edge.source}-${edge.target}-${idx}`,
-    source: edge.source,
-    target: edge.target,
+    source: `${edge.source}`,
+    target: `${edge.target}`,
     data: {
       label: edge.label,
+      imaginary: edge.imaginary,
     },
     type: "straight",
   }));
@@ -162,6 +179,8 @@ function BasicBlockTable({
         borderCollapse: "collapse",
         width: "300px",
         border: isOnSelectedPath ? "5px solid red" : "1px solid black",
+        color: data.ignored ? "gray" : undefined,
+        opacity: data.ignored ? 0.5 : undefined,
       }}
     >
       <tbody>
@@ -208,10 +227,10 @@ function BasicBlockTable({
   );
 }
 
-const getPaths = async (functionName: string) => {
+const getPaths = async (functionDir: string) => {
   try {
     const paths: number[][] = await fetchJsonFile(
-      `data/${functionName}/paths.json`
+      `data/${functionDir}/paths.json`
     );
     return paths;
   } catch (error) {
@@ -226,7 +245,7 @@ async function main() {
   if (!initialFunction || !Object.keys(functions).includes(initialFunction)) {
     initialFunction = Object.keys(functions)[0];
   }
-  const initialPaths = await getPaths(initialFunction);
+  const initialPaths = await getPaths(functions[initialFunction]);
 
   let initialPath = 0;
   let initialPathStr = localStorage.getItem("selectedPath");
@@ -261,7 +280,7 @@ async function main() {
     };
 
     async function loadDotGraph() {
-      const dotFilePath = `data/${selectedFunction}/block_${currentPoint.block}_stmt_${currentPoint.stmt}.dot`;
+      const dotFilePath = `data/${functions[selectedFunction]}/block_${currentPoint.block}_stmt_${currentPoint.stmt}.dot`;
       const dotData = await fetchDotFile(dotFilePath);
       const dotGraph = document.getElementById("dot-graph");
       if (!dotGraph) {
@@ -281,7 +300,7 @@ async function main() {
     useEffect(() => {
       if (selectedFunction) {
         (async function () {
-          const graphData = await getGraphData(selectedFunction);
+          const graphData = await getGraphData(functions[selectedFunction]);
           const { nodes, edges } = layout(
             graphData.initialNodes,
             graphData.initialEdges,
@@ -290,7 +309,7 @@ async function main() {
           console.log(nodes);
           setNodes(nodes);
           setEdges(edges);
-          setPaths(await getPaths(selectedFunction));
+          setPaths(await getPaths(functions[selectedFunction]));
         })();
       }
     }, [selectedFunction]);
@@ -325,7 +344,7 @@ async function main() {
         if (currentBlockIndex === -1) return;
 
         const pathToCurrentBlock = currentPath.slice(0, currentBlockIndex + 1);
-        const heapFilePath = `data/${selectedFunction}/path_${pathToCurrentBlock.map((block) => `bb${block}`).join("_")}_stmt_${currentPoint.stmt}.json`;
+        const heapFilePath = `data/${functions[selectedFunction]}/path_${pathToCurrentBlock.map((block) => `bb${block}`).join("_")}_stmt_${currentPoint.stmt}.json`;
 
         try {
           const data: PathData = await fetchJsonFile(heapFilePath);
@@ -438,7 +457,7 @@ async function main() {
           >
             {Object.keys(functions).map((func) => (
               <option key={func} value={func}>
-                {functions[func]}
+                {func}
               </option>
             ))}
           </select>
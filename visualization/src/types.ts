@@ -2,6 +2,8 @@ export type BasicBlockData = {
   block: number;
   stmts: string[];
   terminator: string;
+  // A cleanup block that was not analyzed
+  ignored: boolean;
 };
 
 export type DagreInputNode<T> = {
@@ -19,7 +21,7 @@ export type DagreNode<T> = {
 
 export type MaybeOldPlace = {
   place: string;
-  before?: string;
+  at?: string;
 };
 
 export type Borrow = {
@@ -27,6 +29,12 @@ export type Borrow = {
   borrowed_place: MaybeOldPlace;
   is_mut: boolean;
   kind: string;
+  debug_info?: string;
+};
+
+export type BlockedSet = {
+  place: MaybeOldPlace;
+  blocked: MaybeOldPlace[];
 };
 
 export type BorrowAction = {
@@ -40,7 +48,18 @@ export type PathData = {
   borrow_actions_mid: BorrowAction[];
   borrows: {
     borrows: Borrow[]
+    latest: Record<string, string>
+    blocked_sets: BlockedSet[]
   }
   repacks_middle: string[]
   repacks_start: string[]
 };
+
+export type Meta = {
+  schema_version: number;
+  pcs_version: string;
+  rustc_version: string;
+  files: Record<string, string[]>;
+  facts: Record<string, "Polonius" | "Nll">;
+  happy_path_only: boolean;
+};